@@ -0,0 +1,151 @@
+/*
+ * Neutral TS template engine, C ABI.
+ *
+ * Built by the neutralts-ffi crate, the library is libneutralts_ffi:
+ *
+ *     cargo build --release -p neutralts-ffi
+ *
+ * Strings passed in are NUL-terminated UTF-8 and borrowed for the call only.
+ * Strings returned are owned by the template: they are NUL-terminated, stay
+ * valid until the same function is called again or the template is freed,
+ * and must not be freed by the caller. The len out parameter, if not NULL,
+ * gets the length without the NUL, the body may contain NUL bytes.
+ *
+ * Generated with cbindgen from src/lib.rs, do not edit.
+ */
+
+#ifndef NEUTRALTS_H
+#define NEUTRALTS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ A template and the strings returned to C.
+ */
+typedef struct NeutralTemplate NeutralTemplate;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Creates a template from a file, or an empty one if `file_path` is NULL.
+
+ Returns NULL if the file cannot be read. Free it with `neutral_template_free`.
+
+ # Safety
+
+ `file_path` is NULL or a valid NUL-terminated string.
+ */
+struct NeutralTemplate *neutral_template_new(const char *file_path);
+
+/*
+ Sets the template source, instead of a file.
+
+ Returns 0, or -1 if the arguments are not valid.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `source` a valid
+ NUL-terminated string.
+ */
+int neutral_template_set_src(struct NeutralTemplate *t, const char *source);
+
+/*
+ Merges a schema in JSON.
+
+ Returns 0, or -1 if the arguments are not valid or the JSON cannot be parsed.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `schema` a valid
+ NUL-terminated string.
+ */
+int neutral_template_merge_schema_json(struct NeutralTemplate *t, const char *schema);
+
+/*
+ Merges a schema in MessagePack, `len` bytes from `data`.
+
+ Returns 0, or -1 if the arguments are not valid or the data cannot be decoded.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `data` points to `len`
+ readable bytes.
+ */
+int neutral_template_merge_schema_msgpack(struct NeutralTemplate *t,
+                                          const uint8_t *data,
+                                          size_t len);
+
+/*
+ Renders the template.
+
+ Returns the output owned by the template, or NULL if `t` is NULL.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+ */
+const char *neutral_template_render(struct NeutralTemplate *t, size_t *len);
+
+/*
+ The status code of the last render, e.g.: 200, or -1 if `t` is NULL.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`.
+ */
+int neutral_template_status(const struct NeutralTemplate *t);
+
+/*
+ The status text of the last render, e.g.: "OK".
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+ */
+const char *neutral_template_status_text(struct NeutralTemplate *t, size_t *len);
+
+/*
+ The status parameter of the last render, e.g.: the URL of a redirect.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+ */
+const char *neutral_template_status_param(struct NeutralTemplate *t, size_t *len);
+
+/*
+ The headers of the last render in JSON, `[["name", "value"], ...]`.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+ */
+const char *neutral_template_headers(struct NeutralTemplate *t, size_t *len);
+
+/*
+ The errors of the last render in JSON, a list of objects with the kind,
+ bif_name, message, file, line, column and snippet, `[]` if there are none.
+
+ # Safety
+
+ `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+ */
+const char *neutral_template_errors(struct NeutralTemplate *t, size_t *len);
+
+/*
+ Frees the template and the strings it returned, NULL is ignored.
+
+ # Safety
+
+ `t` is NULL or a template from `neutral_template_new` not yet freed.
+ */
+void neutral_template_free(struct NeutralTemplate *t);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NEUTRALTS_H */
@@ -0,0 +1,311 @@
+//! C ABI of Neutral TS, see include/neutralts.h.
+//!
+//! ```text
+//!  NeutralTemplate *t = neutral_template_new("index.ntpl");
+//!  neutral_template_merge_schema_json(t, "{\"data\":{\"name\":\"World\"}}");
+//!  const char *body = neutral_template_render(t, NULL);
+//!  int status = neutral_template_status(t);
+//!  neutral_template_free(t);
+//! ```
+//!
+//! Strings passed in are NUL-terminated UTF-8 and borrowed for the call only.
+//! Strings returned are owned by the template: they are NUL-terminated, stay
+//! valid until the same function is called again or the template is freed,
+//! and must not be freed by the caller. The length out parameter, if not
+//! NULL, gets the length without the NUL, the body may contain NUL bytes.
+//!
+//! A panic does not cross the C boundary, the function returns NULL or -1.
+
+use neutralts::Template;
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+/// A template and the strings returned to C.
+pub struct NeutralTemplate {
+    template: Template,
+    body: Vec<u8>,
+    status_text: Vec<u8>,
+    status_param: Vec<u8>,
+    headers: Vec<u8>,
+    errors: Vec<u8>,
+}
+
+impl NeutralTemplate {
+    fn new(template: Template) -> Self {
+        NeutralTemplate {
+            template,
+            body: vec![0],
+            status_text: vec![0],
+            status_param: vec![0],
+            headers: vec![0],
+            errors: vec![0],
+        }
+    }
+}
+
+// Runs f, or returns on_panic if it panics.
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(on_panic)
+}
+
+// Stores the string NUL-terminated in buf and returns it.
+fn c_string(buf: &mut Vec<u8>, s: &str, len: *mut usize) -> *const c_char {
+    buf.clear();
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+    if !len.is_null() {
+        // SAFETY: the caller passes NULL or a valid pointer.
+        unsafe { *len = s.len() };
+    }
+
+    buf.as_ptr() as *const c_char
+}
+
+// SAFETY: the caller passes NULL or a valid NUL-terminated string.
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+/// Creates a template from a file, or an empty one if `file_path` is NULL.
+///
+/// Returns NULL if the file cannot be read. Free it with `neutral_template_free`.
+///
+/// # Safety
+///
+/// `file_path` is NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_new(file_path: *const c_char) -> *mut NeutralTemplate {
+    guard(ptr::null_mut(), || {
+        let Ok(mut template) = Template::new() else {
+            return ptr::null_mut();
+        };
+
+        if !file_path.is_null() {
+            let Some(file_path) = (unsafe { str_arg(file_path) }) else {
+                return ptr::null_mut();
+            };
+            if template.set_src_path(file_path).is_err() {
+                return ptr::null_mut();
+            }
+        }
+
+        Box::into_raw(Box::new(NeutralTemplate::new(template)))
+    })
+}
+
+/// Sets the template source, instead of a file.
+///
+/// Returns 0, or -1 if the arguments are not valid.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `source` a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_set_src(
+    t: *mut NeutralTemplate,
+    source: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        let (Some(t), Some(source)) = (unsafe { t.as_mut() }, unsafe { str_arg(source) }) else {
+            return -1;
+        };
+        t.template.set_src_str(source);
+
+        0
+    })
+}
+
+/// Merges a schema in JSON.
+///
+/// Returns 0, or -1 if the arguments are not valid or the JSON cannot be parsed.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `schema` a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_merge_schema_json(
+    t: *mut NeutralTemplate,
+    schema: *const c_char,
+) -> c_int {
+    guard(-1, || {
+        let (Some(t), Some(schema)) = (unsafe { t.as_mut() }, unsafe { str_arg(schema) }) else {
+            return -1;
+        };
+
+        match t.template.merge_schema_str(schema) {
+            Ok(()) => 0,
+            Err(_) => -1,
+        }
+    })
+}
+
+/// Merges a schema in MessagePack, `len` bytes from `data`.
+///
+/// Returns 0, or -1 if the arguments are not valid or the data cannot be decoded.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `data` points to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_merge_schema_msgpack(
+    t: *mut NeutralTemplate,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    guard(-1, || {
+        let Some(t) = (unsafe { t.as_mut() }) else {
+            return -1;
+        };
+        if data.is_null() {
+            return -1;
+        }
+        let bytes = unsafe { slice::from_raw_parts(data, len) };
+
+        match t.template.merge_schema_msgpack(bytes) {
+            Ok(()) => 0,
+            Err(_) => -1,
+        }
+    })
+}
+
+/// Renders the template.
+///
+/// Returns the output owned by the template, or NULL if `t` is NULL.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_render(
+    t: *mut NeutralTemplate,
+    len: *mut usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        let Some(t) = (unsafe { t.as_mut() }) else {
+            return ptr::null();
+        };
+        let out = t.template.render();
+
+        c_string(&mut t.body, &out, len)
+    })
+}
+
+/// The status code of the last render, e.g.: 200, or -1 if `t` is NULL.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_status(t: *const NeutralTemplate) -> c_int {
+    guard(-1, || {
+        let Some(t) = (unsafe { t.as_ref() }) else {
+            return -1;
+        };
+
+        t.template.get_status_code().parse().unwrap_or(-1)
+    })
+}
+
+/// The status text of the last render, e.g.: "OK".
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_status_text(
+    t: *mut NeutralTemplate,
+    len: *mut usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        let Some(t) = (unsafe { t.as_mut() }) else {
+            return ptr::null();
+        };
+        let text = t.template.get_status_text().clone();
+
+        c_string(&mut t.status_text, &text, len)
+    })
+}
+
+/// The status parameter of the last render, e.g.: the URL of a redirect.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_status_param(
+    t: *mut NeutralTemplate,
+    len: *mut usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        let Some(t) = (unsafe { t.as_mut() }) else {
+            return ptr::null();
+        };
+        let param = t.template.get_status_param().clone();
+
+        c_string(&mut t.status_param, &param, len)
+    })
+}
+
+/// The headers of the last render in JSON, `[["name", "value"], ...]`.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_headers(
+    t: *mut NeutralTemplate,
+    len: *mut usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        let Some(t) = (unsafe { t.as_mut() }) else {
+            return ptr::null();
+        };
+        let headers = serde_json::to_string(&t.template.get_headers()).unwrap_or_default();
+
+        c_string(&mut t.headers, &headers, len)
+    })
+}
+
+/// The errors of the last render in JSON, a list of objects with the kind,
+/// bif_name, message, file, line, column and snippet, `[]` if there are none.
+///
+/// # Safety
+///
+/// `t` is a template from `neutral_template_new`, `len` is NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_errors(
+    t: *mut NeutralTemplate,
+    len: *mut usize,
+) -> *const c_char {
+    guard(ptr::null(), || {
+        let Some(t) = (unsafe { t.as_mut() }) else {
+            return ptr::null();
+        };
+        let errors = serde_json::to_string(t.template.get_errors()).unwrap_or_default();
+
+        c_string(&mut t.errors, &errors, len)
+    })
+}
+
+/// Frees the template and the strings it returned, NULL is ignored.
+///
+/// # Safety
+///
+/// `t` is NULL or a template from `neutral_template_new` not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn neutral_template_free(t: *mut NeutralTemplate) {
+    guard((), || {
+        if !t.is_null() {
+            drop(unsafe { Box::from_raw(t) });
+        }
+    })
+}
@@ -0,0 +1,71 @@
+/* Uses the C ABI, built and run by test-ffi.rs. */
+
+#include <stdio.h>
+#include <string.h>
+#include "neutralts.h"
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,     \
+                    __LINE__, #cond);                                   \
+            return 1;                                                   \
+        }                                                               \
+    } while (0)
+
+int main(void)
+{
+    size_t len = 0;
+
+    /* source and JSON schema */
+    NeutralTemplate *t = neutral_template_new(NULL);
+    CHECK(t != NULL);
+    CHECK(neutral_template_set_src(t, "<p>Hello {:;name:}</p>") == 0);
+    CHECK(neutral_template_merge_schema_json(t, "{\"data\":{\"name\":\"World\"}}") == 0);
+    CHECK(neutral_template_merge_schema_json(t, "{not json") == -1);
+    const char *body = neutral_template_render(t, &len);
+    CHECK(strcmp(body, "<p>Hello World</p>") == 0);
+    CHECK(len == strlen("<p>Hello World</p>"));
+    CHECK(neutral_template_status(t) == 200);
+    CHECK(strcmp(neutral_template_status_text(t, NULL), "OK") == 0);
+    CHECK(strcmp(neutral_template_errors(t, NULL), "[]") == 0);
+    neutral_template_free(t);
+
+    /* file and MessagePack schema: {"data": {"name": "msgpack"}} */
+    static const uint8_t schema[] = {
+        0x81, 0xa4, 'd', 'a', 't', 'a',
+        0x81, 0xa4, 'n', 'a', 'm', 'e',
+        0xa7, 'm', 's', 'g', 'p', 'a', 'c', 'k',
+    };
+    t = neutral_template_new("tests/snippets.ntpl");
+    CHECK(t != NULL);
+    CHECK(neutral_template_merge_schema_msgpack(t, schema, sizeof(schema)) == 0);
+    CHECK(neutral_template_merge_schema_msgpack(t, (const uint8_t *)"\xc1", 1) == -1);
+    CHECK(neutral_template_set_src(t, "{:;name:}") == 0);
+    CHECK(strcmp(neutral_template_render(t, NULL), "msgpack") == 0);
+    neutral_template_free(t);
+
+    /* status, headers and errors */
+    t = neutral_template_new(NULL);
+    CHECK(neutral_template_set_src(t,
+        "{:header; X-Test >> yes :}"
+        "{:include; {:flg; require :} >> not-found.ntpl :}"
+        "{:redirect; 302 >> /login :}") == 0);
+    neutral_template_render(t, NULL);
+    CHECK(neutral_template_status(t) == 302);
+    CHECK(strcmp(neutral_template_status_param(t, NULL), "/login") == 0);
+    CHECK(strcmp(neutral_template_headers(t, NULL), "[[\"X-Test\",\"yes\"]]") == 0);
+    const char *errors = neutral_template_errors(t, &len);
+    CHECK(len > 2 && strstr(errors, "\"bif_name\":\"include\"") != NULL);
+    neutral_template_free(t);
+
+    /* invalid arguments */
+    CHECK(neutral_template_new("not-found.ntpl") == NULL);
+    CHECK(neutral_template_render(NULL, NULL) == NULL);
+    CHECK(neutral_template_status(NULL) == -1);
+    CHECK(neutral_template_merge_schema_json(NULL, "{}") == -1);
+    neutral_template_free(NULL);
+
+    printf("ok\n");
+    return 0;
+}
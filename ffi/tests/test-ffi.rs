@@ -0,0 +1,73 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+
+// The crate is also an rlib, so cargo test builds the shared library next
+// to the test binary.
+fn lib_dir() -> PathBuf {
+    env::current_exe().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn test_ffi_c_program() {
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let lib_dir = lib_dir();
+    let exe = env::temp_dir().join(format!("neutralts-ffi-{}", std::process::id()));
+
+    let compiled = Command::new(&cc)
+        .arg(format!("{}/tests/ffi.c", MANIFEST_DIR))
+        .arg(format!("-I{}/include", MANIFEST_DIR))
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lneutralts_ffi")
+        .arg("-o")
+        .arg(&exe)
+        .status();
+
+    let Ok(compiled) = compiled else {
+        println!("skip test_ffi_c_program: {} not found", cc);
+        return;
+    };
+    assert!(compiled.success());
+
+    // The C program opens the files of the neutralts tests.
+    let output = Command::new(&exe)
+        .current_dir(format!("{}/..", MANIFEST_DIR))
+        .output()
+        .unwrap();
+    fs::remove_file(&exe).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "ok\n");
+}
+
+// The header is generated with cbindgen, it must be up to date.
+#[test]
+fn test_ffi_header() {
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", MANIFEST_DIR)).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/lib.rs", MANIFEST_DIR))
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    let header_path = format!("{}/include/neutralts.h", MANIFEST_DIR);
+
+    if env::var_os("NEUTRALTS_UPDATE_HEADER").is_some() {
+        fs::write(&header_path, &generated).unwrap();
+    }
+    let header = fs::read_to_string(&header_path).unwrap();
+    assert!(
+        header == generated,
+        "include/neutralts.h is out of date, see cbindgen.toml"
+    );
+}
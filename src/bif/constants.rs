@@ -8,8 +8,10 @@ pub(crate) const BIF_ERROR_UNKNOWN_BIF: &str = "unknown bif";
 pub(crate) const BIF_ERROR_BIF_DEPRECATED: &str = "Bif 'count' is deprecated";
 pub(crate) const BIF_ERROR_INSECURE_FILE_NAME: &str = "insecure file name";
 pub(crate) const BIF_ERROR_FILE_NOT_FOUND: &str = "file not found";
+pub(crate) const BIF_ERROR_PATH_NOT_ALLOWED: &str = "path outside allowed roots";
 pub(crate) const BIF_ERROR_ONLY_PYTHON_ENGINE: &str = "only Python and PHP engines are supported";
 pub(crate) const BIF_ERROR_OBJ_FILE_NOT_FOUND: &str = "obj script not found";
+pub(crate) const BIF_ERROR_ENGINE_NOT_COMPILED: &str = "engine not compiled in";
 pub(crate) const BIF_ERROR_ARGS_FROM_TO_NOT_FOUND: &str = "arguments 'from' and 'to' not found";
 pub(crate) const BIF_ERROR_ARGS_TO_NOT_FOUND: &str = "arguments 'to' not found";
 pub(crate) const BIF_ERROR_ARGS_KEY_NOT_FOUND: &str = "arguments 'key' not found";
@@ -25,3 +27,12 @@ pub(crate) const BIF_ERROR_REDIRECT_REQUIRES_URL: &str = "this redirection requi
 pub(crate) const BIF_ERROR_STATUS_CODE_NOT_ALLOWED: &str = "status code not allowed";
 pub(crate) const BIF_ERROR_REDIRECT_TYPE_NOT_ALLOWED: &str = "redirect type not allowed";
 pub(crate) const BIF_ERROR_DECLARED_IS_EMPTY: &str = " declared is empty";
+pub(crate) const BIF_ERROR_ARGS_NAME_NOT_FOUND: &str = "arguments 'name' not found";
+pub(crate) const BIF_ERROR_EXTENDS_ALREADY_SET: &str = "extends already set";
+pub(crate) const BIF_ERROR_ALREADY_INCLUDED: &str = "template already included";
+pub(crate) const BIF_ERROR_PARENT_OUTSIDE_BLOCK: &str = "parent outside a block";
+pub(crate) const BIF_ERROR_INVALID_HEADER_NAME: &str = "invalid header name";
+pub(crate) const BIF_ERROR_INVALID_HEADER_VALUE: &str = "invalid header value";
+pub(crate) const BIF_ERROR_SET_COOKIE_HEADER: &str = "cookies are set with the bif cookie";
+pub(crate) const BIF_ERROR_INVALID_COOKIE_NAME: &str = "invalid cookie name";
+pub(crate) const BIF_ERROR_INVALID_COOKIE_VALUE: &str = "invalid cookie value";
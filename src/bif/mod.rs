@@ -1,17 +1,27 @@
 //
 
-use crate::{block_parser::BlockInherit, constants::*, json, shared::Shared, utils::*, Value};
+use crate::{
+    block_parser::BlockInherit, compiled::CompiledBlock, compiled::CompiledTemplate,
+    compiled::Modifiers, compiled::SourceOrigin, constants::*, json, render_error::*,
+    shared::Shared, utils::*, Value,
+};
 use chrono::Local;
+use constants::{BIF_ERROR_MODIFIER_NOT_ALLOWED, BIF_ERROR_PATH_NOT_ALLOWED};
+use std::borrow::Cow;
+use std::sync::Arc;
 
-mod constants;
+pub(crate) mod constants;
 mod parse_bif_allow;
 mod parse_bif_array;
+mod parse_bif_block;
 mod parse_bif_bool;
 mod parse_bif_cache;
 mod parse_bif_coalesce;
 mod parse_bif_code;
 mod parse_bif_contains;
+mod parse_bif_cookie;
 mod parse_bif_count;
+mod parse_bif_custom;
 mod parse_bif_data;
 mod parse_bif_date;
 mod parse_bif_debug;
@@ -21,11 +31,13 @@ mod parse_bif_each;
 mod parse_bif_else;
 mod parse_bif_eval;
 mod parse_bif_exit;
+mod parse_bif_extends;
 mod parse_bif_fetch;
 mod parse_bif_filled;
 mod parse_bif_flg;
 mod parse_bif_for;
 mod parse_bif_hash;
+mod parse_bif_header;
 mod parse_bif_include;
 mod parse_bif_join;
 mod parse_bif_lang;
@@ -34,6 +46,7 @@ mod parse_bif_moveto;
 mod parse_bif_neutral;
 mod parse_bif_obj;
 mod parse_bif_param;
+mod parse_bif_parent;
 mod parse_bif_rand;
 mod parse_bif_redirect;
 mod parse_bif_replace;
@@ -43,10 +56,15 @@ mod parse_bif_sum;
 mod parse_bif_trans;
 mod parse_bif_unknown;
 mod parse_bif_var;
+pub(crate) mod rules;
 
+#[cfg(feature = "obj-php")]
 mod exec_php;
+#[cfg(feature = "obj-python")]
 mod exec_python;
+#[cfg(feature = "obj-php")]
 pub use exec_php::PhpExecutor;
+#[cfg(feature = "obj-python")]
 pub use exec_python::PythonExecutor;
 
 pub(crate) struct BifError {
@@ -74,6 +92,10 @@ pub(crate) struct Bif<'a> {
     pub(crate) dir: String,
     pub(crate) out: String,
     pub(crate) only: &'a str,
+    pub(crate) compiled: Option<&'a CompiledBlock>,
+    pub(crate) child_compiled: Option<Arc<CompiledTemplate>>,
+    pub(crate) origin: Option<SourceOrigin>,
+    pub(crate) child_origin: Option<SourceOrigin>,
     _none: &'a str,
 }
 
@@ -83,19 +105,14 @@ impl<'a> Bif<'a> {
         shared: &'a mut Shared,
         inherit: &'a mut BlockInherit,
         only: &'a str,
+        compiled: Option<&'a CompiledBlock>,
+        origin: Option<SourceOrigin>,
     ) -> Self {
         shared.bisf_count += 1;
         let count = shared.bisf_count;
         inherit.bif_count = shared.bisf_count;
 
-        if count > shared.bisf_max {
-            panic!(
-                "Infinite loop? {} bifs of {} max have been created.",
-                shared.bisf_max, count
-            );
-        }
-
-        Bif {
+        let bif = Bif {
             raw: raw_source, // should not be modified
             shared,
             inherit,
@@ -113,16 +130,38 @@ impl<'a> Bif<'a> {
             dir: String::new(),
             out: String::new(),
             only,
+            compiled,
+            child_compiled: None,
+            origin,
+            child_origin: None,
             _none: "",
+        };
+
+        if count > bif.shared.bisf_max {
+            let msg = format!(
+                "Infinite loop? {} bifs of {} max have been created.",
+                count, bif.shared.bisf_max
+            );
+            let error = bif.render_error(RenderErrorKind::Render, "", &msg);
+            bif.shared.abort(error);
         }
+
+        bif
     }
 
     // Divides the bif into its parts and executes the bif parse function.
     pub(crate) fn parse(&mut self) -> String {
+        if self.shared.aborted {
+            return EMPTY_STRING;
+        }
+
         let bif = strip_prefix_suffix(self.raw, BIF_OPEN, BIF_CLOSE);
         let result;
 
-        if let Some((name, src)) = bif.split_once(BIF_NAME) {
+        if let Some(compiled) = self.compiled.filter(|c| c.is_delimited()) {
+            self.name = compiled.full_name().to_string();
+            self.src = compiled.src().to_string();
+        } else if let Some((name, src)) = bif.split_once(BIF_NAME) {
             self.name = name.to_string();
             self.src = src.trim().to_string();
         } else {
@@ -130,21 +169,13 @@ impl<'a> Bif<'a> {
                 return self.raw.to_string();
             }
 
-            let show_error = self.shared.schema["config"]["error"]["show"]
-                .as_bool()
-                .unwrap();
             let error_line = format!("The delimiter was not found: {}", self.raw);
-            let error_line = error_line.replace(['\n', '\r'], " ");
-
-            if let Some(Value::Array(errors)) = self.shared.schema.get_mut("__error") {
-                errors.push(json!(error_line));
-            }
-
-            if show_error {
-                eprintln!("{}", error_line);
-            }
-
-            self.shared.has_error = true;
+            let error = self.render_error(
+                RenderErrorKind::Delimiter,
+                "",
+                "The delimiter was not found",
+            );
+            self.shared.add_error(error, &error_line);
 
             return EMPTY_STRING;
         }
@@ -159,14 +190,19 @@ impl<'a> Bif<'a> {
 
         // exec the function of each bif
         match &self.name[..] {
+            _ if !self.modifiers_allowed() => {
+                result = Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED))
+            }
             "" => result = self.parse_bif_var(),
             "allow" => result = self.parse_bif_allow(),
             "array" => result = self.parse_bif_array(),
+            "block" => result = self.parse_bif_block(),
             "bool" => result = self.parse_bif_bool(),
             "cache" => result = self.parse_bif_cache(),
             "coalesce" => result = self.parse_bif_coalesce(),
             "code" => result = self.parse_bif_code(),
             "contains" => result = self.parse_bif_contains(),
+            "cookie" => result = self.parse_bif_cookie(),
             "count" => result = self.parse_bif_count(),
             "data" => result = self.parse_bif_data(),
             "date" => result = self.parse_bif_date(),
@@ -176,11 +212,13 @@ impl<'a> Bif<'a> {
             "else" => result = self.parse_bif_else(),
             "eval" => result = self.parse_bif_eval(),
             "exit" => result = self.parse_bif_exit(),
+            "extends" => result = self.parse_bif_extends(),
             "fetch" => result = self.parse_bif_fetch(),
             "filled" => result = self.parse_bif_filled(),
             "flg" => result = self.parse_bif_flg(),
             "for" => result = self.parse_bif_for(),
             "hash" => result = self.parse_bif_hash(),
+            "header" => result = self.parse_bif_header(),
             "include" => result = self.parse_bif_include(),
             "join" => result = self.parse_bif_join(),
             "lang" => result = self.parse_bif_lang(),
@@ -188,6 +226,7 @@ impl<'a> Bif<'a> {
             "moveto" => result = self.parse_bif_moveto(),
             "neutral" => result = self.parse_bif_neutral(),
             "param" => result = self.parse_bif_param(),
+            "parent" => result = self.parse_bif_parent(),
             "rand" => result = self.parse_bif_rand(),
             "redirect" => result = self.parse_bif_redirect(),
             "replace" => result = self.parse_bif_replace(),
@@ -198,16 +237,12 @@ impl<'a> Bif<'a> {
             "trans" => result = self.parse_bif_trans(),
             "obj" => result = self.parse_bif_obj(),
             "debug" => result = self.parse_bif_debug(),
-            _ => result = self.parse_bif_unknown(),
+            _ => result = self.parse_bif_custom(),
         }
 
         match result {
             Ok(()) => (),
             Err(e) => {
-                let show_error = self.shared.schema["config"]["error"]["show"]
-                    .as_bool()
-                    .unwrap();
-
                 let datetime = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
                 let error_line = format!(
@@ -215,17 +250,15 @@ impl<'a> Bif<'a> {
                     datetime, e.name, e.msg, e.file, e.src
                 );
 
-                let error_line = error_line.replace(['\n', '\r'], " ");
-
-                if let Some(Value::Array(errors)) = self.shared.schema.get_mut("__error") {
-                    errors.push(json!(error_line));
-                }
-
-                if show_error {
-                    eprintln!("{}", error_line);
-                }
-
-                self.shared.has_error = true;
+                // The name as it is written, the alias can be e.g. "unknown"
+                let bif_name = if self.name.is_empty() {
+                    &e.name
+                } else {
+                    &self.name
+                };
+                let mut error = self.render_error(RenderErrorKind::Bif, bif_name, &e.msg);
+                error.snippet = RenderError::shorten(&e.src);
+                self.shared.add_error(error, &error_line);
             }
         }
 
@@ -247,29 +280,136 @@ impl<'a> Bif<'a> {
     //    v
     //  {:!snippet; ...
     //
+    pub(crate) fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            filter: self.mod_filter,
+            negate: self.mod_negate,
+            upline: self.mod_upline,
+            scope: self.mod_scope,
+        }
+    }
+
+    // Whether the bif accepts its modifiers, see rules::BIF_RULES.
+    fn modifiers_allowed(&self) -> bool {
+        match rules::bif_rules(&self.name) {
+            Some((allowed, _)) => rules::modifiers_allowed(allowed, self.modifiers()),
+            None => true,
+        }
+    }
+
+    // The first flag that the bif does not accept is an error, see rules::BIF_RULES.
+    pub(crate) fn check_flags(&self) -> Result<(), BifError> {
+        let allowed = rules::bif_rules(&self.name).and_then(|(_, flags)| flags);
+
+        match rules::flag_errors(allowed, self.flags.split('|')).first() {
+            Some(error) => Err(self.bif_error(error)),
+            None => Ok(()),
+        }
+    }
+
     pub(crate) fn set_modifiers(&mut self) -> String {
-        let mut index = 0;
-        while index < self.name.len() {
-            let start = &self.name[index..index + 1];
-            if start == BIF_MOD_FILTER
-                || start == BIF_MOD_NEGATE
-                || start == BIF_MOD_UPLINE
-                || start == BIF_MOD_SCOPE
-            {
-                match start {
-                    BIF_MOD_FILTER => self.mod_filter = true,
-                    BIF_MOD_NEGATE => self.mod_negate = true,
-                    BIF_MOD_UPLINE => self.mod_upline = true,
-                    BIF_MOD_SCOPE => self.mod_scope = true,
-                    _ => unreachable!(),
-                }
-                index += 1;
-            } else {
-                break;
+        let (modifiers, name) = match self.compiled.filter(|c| c.is_delimited()) {
+            Some(compiled) => (compiled.modifiers(), compiled.name()),
+            None => Modifiers::split(&self.name),
+        };
+
+        self.mod_filter = modifiers.filter;
+        self.mod_negate = modifiers.negate;
+        self.mod_upline = modifiers.upline;
+        self.mod_scope = modifiers.scope;
+
+        name.to_string()
+    }
+
+    // Get the compiled version of a source to be parsed by a child block,
+    // if there is one: the one set by the bif (e.g. included file) or the
+    // params or code of the compiled bif, as long as they have not changed.
+    pub(crate) fn compiled_for(&self, source: &str) -> Option<Arc<CompiledTemplate>> {
+        if let Some(compiled) = &self.child_compiled {
+            if compiled.source() == source {
+                return Some(Arc::clone(compiled));
             }
         }
 
-        self.name[index..].to_string()
+        let block = self.compiled?;
+
+        [block.code_tree(), block.params_tree()]
+            .into_iter()
+            .flatten()
+            .find(|tree| tree.source() == source)
+            .cloned()
+    }
+
+    // Set where the source parsed by a child block comes from.
+    pub(crate) fn set_child_origin(&self, child_inherit: &mut BlockInherit, source: &str) {
+        child_inherit.origin = self.origin_for(source);
+
+        if self.origin.is_some() {
+            child_inherit.fallback = self.origin.clone();
+        }
+    }
+
+    // Get the position of a source to be parsed by a child block, if it is
+    // known: the one set by the bif (e.g. included file or snippet) or the
+    // params or code of the bif, as long as they have not changed.
+    //
+    //        .-- params position
+    //        |               .-- code position
+    //        v               v
+    //  {:bif; params >> code :}
+    fn origin_for(&self, source: &str) -> Option<SourceOrigin> {
+        if let Some(origin) = &self.child_origin {
+            if origin.is_source_of(source) {
+                return Some(origin.clone());
+            }
+        }
+
+        let origin = self.origin.as_ref()?;
+        let name_end = self.raw.find(BIF_NAME)? + BIF_NAME.len();
+        let after_name = &self.raw[name_end..];
+        let params_pos = name_end + after_name.len() - after_name.trim_start().len();
+        let before_close = self.raw.strip_suffix(BIF_CLOSE)?.trim_end();
+        let code_pos = before_close.len().checked_sub(source.len())?;
+
+        [params_pos, code_pos]
+            .into_iter()
+            .map(|pos| origin.at(pos))
+            .find(|origin| origin.is_source_of(source))
+    }
+
+    // Resolve a path written in the template with the loader, e.g. "#" prefix,
+    // and get the canonical path, None if the file does not exist.
+    // The canonical path must be in config.allowed_roots, symbolic links
+    // are already resolved.
+    //
+    //  {:include; #/file.ntpl :} -> current_dir/file.ntpl -> /canonical/path/file.ntpl
+    //
+    // With search_dirs, relative paths are searched in config.template_dirs
+    // in order, and if not found, relative to the working directory.
+    //
+    //  {:include; file.ntpl :} -> theme/file.ntpl, base/file.ntpl, file.ntpl
+    pub(crate) fn resolve_file(&mut self, search_dirs: bool) -> Result<Option<String>, BifError> {
+        let (file_path, canonical_path) =
+            self.shared
+                .resolve_path(&self.inherit.current_dir, &self.file_path, search_dirs);
+        self.file_path = file_path;
+
+        match canonical_path {
+            Some(canonical_path) => self.check_allowed_path(canonical_path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // The canonical path must be in config.allowed_roots.
+    fn check_allowed_path(&self, canonical_path: String) -> Result<String, BifError> {
+        if !self.shared.is_allowed_path(&canonical_path) {
+            return Err(self.bif_error(&format!(
+                "{}: {}",
+                BIF_ERROR_PATH_NOT_ALLOWED, canonical_path
+            )));
+        }
+
+        Ok(canonical_path)
     }
 
     // Get key from schema data o local data
@@ -284,14 +424,18 @@ impl<'a> Bif<'a> {
     //     ...
     // }
     pub(crate) fn get_data(&self, name: &str) -> String {
-        if name.starts_with("local::") {
-            let local_name = name.strip_prefix("local::").unwrap_or(name);
-            get_from_key(
+        get_from_value(self.data_value(name).as_deref())
+    }
+
+    // Get a value from schema data, or from local data with "local::".
+    pub(crate) fn data_value(&self, name: &str) -> Option<Cow<'_, Value>> {
+        match name.strip_prefix("local::") {
+            Some(local_name) => resolve_pointer(
                 &self.shared.get_indir(&self.inherit.indir)["data"],
                 local_name,
             )
-        } else {
-            get_from_key(&self.shared.schema["data"], name)
+            .map(Cow::Borrowed),
+            None => self.shared.data_value(name),
         }
     }
 
@@ -326,8 +470,7 @@ impl<'a> Bif<'a> {
     // }
     pub(crate) fn get_trans(&self, text: &str) -> String {
         get_from_key(
-            &self.shared.get_indir(&self.inherit.indir)["locale"]["trans"]
-                [&self.shared.lang],
+            &self.shared.get_indir(&self.inherit.indir)["locale"]["trans"][&self.shared.lang],
             text,
         )
     }
@@ -363,6 +506,23 @@ impl<'a> Bif<'a> {
     //              ------------ -- ------------------------------
     //  {:!snippet; snippet_name >> <div>... {:* ... *:} ...</div> :}
     pub(crate) fn extract_params_code(&mut self, parse: bool) -> bool {
+        if let Some(compiled) = self
+            .compiled
+            .filter(|c| c.is_delimited() && c.src() == self.src)
+        {
+            self.params = compiled.params().to_string();
+            self.code = compiled.code().to_string();
+            let has_code = compiled.has_code();
+
+            if parse && self.params.contains(BIF_OPEN) {
+                self.shared.flags = EMPTY_STRING;
+                self.params = new_child_parse!(self, &self.params, false);
+                self.flags = self.shared.flags.clone();
+            }
+
+            return has_code;
+        }
+
         let position = get_code_position(&self.src);
         let has_code: bool = position.is_some();
 
@@ -384,6 +544,26 @@ impl<'a> Bif<'a> {
         has_code
     }
 
+    // Moves the {:flg; ... :} of the params to the flags, for the bifs whose
+    // params are not parsed by extract_params_code.
+    pub(crate) fn extract_flags(&mut self) {
+        let flg_block = extract_blocks(&self.params)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|(start, _)| self.params[*start..].starts_with("{:flg;"));
+        let Some((start, end)) = flg_block else {
+            return;
+        };
+        let flg = self.params[start..end].to_string();
+
+        self.shared.flags = EMPTY_STRING;
+        new_child_parse!(self, &flg, false);
+        self.flags = self.shared.flags.clone();
+        self.params = format!("{}{}", &self.params[..start], &self.params[end..])
+            .trim()
+            .to_string();
+    }
+
     // Extract bif arguments.
     //
     //          .-- arg 0 empty string
@@ -419,6 +599,25 @@ impl<'a> Bif<'a> {
         result
     }
 
+    pub(crate) fn render_error(
+        &self,
+        kind: RenderErrorKind,
+        bif_name: &str,
+        message: &str,
+    ) -> RenderError {
+        let (file, line, column) = self.inherit.location(self.origin.as_ref());
+
+        RenderError {
+            kind,
+            bif_name: bif_name.to_string(),
+            message: message.to_string(),
+            file,
+            line,
+            column,
+            snippet: RenderError::shorten(self.raw),
+        }
+    }
+
     pub(crate) fn bif_error(&self, msg: &str) -> BifError {
         BifError {
             msg: msg.to_string(),
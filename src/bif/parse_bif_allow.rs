@@ -1,17 +1,12 @@
 #![doc = include_str!("../../doc/bif-allow.md")]
 
 use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
-use std::collections::HashSet;
 
 impl<'a> Bif<'a> {
     /*
         {:allow; {:flg; partial casein replace :} name >> ... :}
     */
     pub(crate) fn parse_bif_allow(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
         let mut found = String::new();
         let words_string = get_from_key(
@@ -23,16 +18,7 @@ impl<'a> Bif<'a> {
             return Err(self.bif_error(&(self.params.clone() + BIF_ERROR_DECLARED_IS_EMPTY)));
         }
 
-        if !self.flags.is_empty() {
-            let flags_allowed: HashSet<&str> =
-                ["partial", "replace", "casein"].into_iter().collect();
-
-            for f in self.flags.split('|').filter(|s| !s.is_empty()) {
-                if !flags_allowed.contains(f) {
-                    return Err(self.bif_error(&format!("{} flag not allowed", f)));
-                }
-            }
-        }
+        self.check_flags()?;
 
         let mut words_list: Vec<&str> = words_string.split_whitespace().collect();
         self.code = new_child_parse!(self, &self.code, self.mod_scope);
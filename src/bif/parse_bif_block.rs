@@ -0,0 +1,124 @@
+#![doc = include_str!("../../doc/bif-block.md")]
+
+use crate::{
+    bif::constants::*, bif::Bif, bif::BifError, block_parser::BlockLevel,
+    compiled::compile_str_cached, constants::*, json,
+};
+
+impl<'a> Bif<'a> {
+    /*
+        Set block, in a template with extends:
+        {:block; name >> content :}
+
+        Play block, default content if it is not set:
+        {:block; name >> default content :}
+        {:block; name :}
+    */
+    pub(crate) fn parse_bif_block(&mut self) -> Result<(), BifError> {
+        self.out = EMPTY_STRING;
+        let has_code = self.extract_params_code(true);
+
+        self.check_flags()?;
+
+        let name = if has_code {
+            self.params.clone()
+        } else if self.code.contains(BIF_OPEN) {
+            new_child_parse!(self, &self.code, false)
+        } else {
+            self.code.clone()
+        };
+
+        if name.is_empty() {
+            return Err(self.bif_error(BIF_ERROR_ARGS_NAME_NOT_FOUND));
+        }
+
+        if has_code && self.inherit.extends.is_some() {
+            // As in snippets, the block is available from here in this block and its children.
+            self.inherit.create_block_schema(self.shared);
+
+            let origin = self.origin_for(&self.code);
+            let item = json!({ "code": &self.code, "dir": &self.inherit.current_dir });
+            let blocks = &mut self.shared.get_indir_mut(&self.inherit.indir)["blocks"][&name];
+            if !blocks.is_array() {
+                *blocks = json!([]);
+            }
+
+            // The most derived template sets its blocks first.
+            let level = match blocks.as_array_mut() {
+                Some(list) => {
+                    list.push(item);
+                    list.len() - 1
+                }
+                None => 0,
+            };
+
+            if let Some(origin) = origin {
+                self.shared.block_origins.insert((name, level), origin);
+            }
+
+            return Ok(());
+        }
+
+        let code = if has_code {
+            self.code.clone()
+        } else {
+            EMPTY_STRING
+        };
+        let level = BlockLevel {
+            name,
+            level: 0,
+            default_origin: self.origin_for(&code),
+            default: code,
+            default_dir: self.inherit.current_dir.clone(),
+        };
+        self.out = self.parse_block_level(level);
+
+        Ok(())
+    }
+
+    // Parse one of the contents of a block, with {:parent; :} the next one
+    // can be parsed from it.
+    pub(crate) fn parse_block_level(&mut self, level: BlockLevel) -> String {
+        let blocks = &self.shared.get_indir(&self.inherit.indir)["blocks"][&level.name];
+        let count = blocks.as_array().map_or(0, |list| list.len());
+
+        let (code, dir, origin) = if level.level < count {
+            let item = &blocks[level.level];
+            (
+                item["code"].as_str().unwrap_or_default().to_string(),
+                item["dir"].as_str().unwrap_or_default().to_string(),
+                self.shared
+                    .block_origins
+                    .get(&(level.name.clone(), level.level))
+                    .cloned(),
+            )
+        } else if level.level == count {
+            (
+                level.default.clone(),
+                level.default_dir.clone(),
+                level.default_origin.clone(),
+            )
+        } else {
+            return EMPTY_STRING;
+        };
+
+        if !code.contains(BIF_OPEN) {
+            return code;
+        }
+
+        // The directory inside the block is that of the template that set it.
+        self.dir = dir;
+        self.child_compiled = Some(compile_str_cached(&code));
+        self.child_origin = origin;
+
+        let prev_level = self.inherit.block_level.replace(level);
+        let out = new_child_parse!(self, &code, false);
+        self.inherit.block_level = prev_level;
+
+        out
+    }
+}
+
+#[cfg(test)]
+#[path = "parse_bif_block_tests.rs"]
+mod tests;
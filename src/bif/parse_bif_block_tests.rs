@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::*;
+
+    fn new_template() -> crate::Template {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template
+    }
+
+    #[test]
+    fn test_bif_block_default() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:block; name >> {:;__test-nts:} :}</div>");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div>nts</div>");
+    }
+
+    #[test]
+    fn test_bif_block_no_default() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:block; name :}</div>");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_bif_block_parent() {
+        let mut template = new_template();
+        template.set_src_str(
+            "{:extends; tests/extends-base.ntpl :}{:block; title >> {:parent; :} - Child :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(
+            result,
+            "<title>Base - Child</title>\n<main><p>base</p></main>"
+        );
+    }
+
+    #[test]
+    fn test_bif_block_parent_outside() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:parent; :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_bif_block_scope() {
+        let mut template = new_template();
+        template.set_src_str(
+            "{:extends; tests/extends-base.ntpl :}{:code; {:block; title >> Title :} :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<title>Base</title>\n<main><p>base</p></main>");
+
+        let mut template = new_template();
+        template.set_src_str(
+            "{:extends; tests/extends-base.ntpl :}{:+code; {:block; title >> Title :} :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<title>Title</title>\n<main><p>base</p></main>");
+    }
+
+    #[test]
+    fn test_bif_block_name_not_found() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:block; >> content :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_bif_block_modifiers() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:!block; name >> content :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+}
@@ -1,31 +1,30 @@
 #![doc = include_str!("../../doc/bif-cache.md")]
 
+use crate::cache::{begin_fill, sweep_on_write, FILL_TIMEOUT};
 use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
 use md5::Digest;
+use serde_json::{Map, Value};
 use sha2::Sha256;
-use std::fs;
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
 
 impl<'a> Bif<'a> {
     /*
+        {:cache; /expires/id/only_custom_id/vary/ >> ... :} {:* vary: CONTEXT paths *:}
         {:cache; /expires/id/only_custom_id/ >> ... :} {:* expires in seconds *:}
         {:cache; /expires/id/ >> ... :}
         {:cache; /expires/ >> ... :}
+        {:cache; {:flg; tags tag1 tag2 ... :} /expires/ >> ... :}
         {:!cache; ... :}
     */
     pub(crate) fn parse_bif_cache(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(false);
 
-        if self.params.contains("{:flg;") {
+        // The flags are checked even if the cache is disabled.
+        self.extract_flags();
+        if self.mod_negate && !self.flags.is_empty() {
             return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
         }
+        let tags = self.cache_tags()?;
 
         if self.mod_negate {
             if self.inherit.in_cache {
@@ -74,149 +73,124 @@ impl<'a> Bif<'a> {
             None => false,
         };
 
+        // optional vary, overrides config.cache_vary
+        let vary: Vec<String> = match args.get(4).map(|v| v.trim()) {
+            Some(vary) if !vary.is_empty() => vary.split_whitespace().map(String::from).collect(),
+            _ => self.shared.cache_vary.clone(),
+        };
+
         if !only_custom_id {
-            id.push_str(&self.shared.lang);
-            id.push_str(&expires);
-            if has_post {
-                id.push_str(
-                    &serde_json::to_string(&self.shared.schema["data"]["CONTEXT"]["POST"]).unwrap(),
-                );
-            }
-            if has_get {
-                id.push_str(
-                    &serde_json::to_string(&self.shared.schema["data"]["CONTEXT"]["GET"]).unwrap(),
-                );
-            }
-            if has_cookies {
-                id.push_str(
-                    &serde_json::to_string(&self.shared.schema["data"]["CONTEXT"]["COOKIES"])
-                        .unwrap(),
-                );
-            }
-            id.push_str(&self.get_data("CONTEXT->HOST"));
-            id.push_str(&self.get_data("CONTEXT->ROUTE"));
-            id.push_str(&self.code);
+            // Each part and each path with its value, so that different
+            // contexts never produce the same id.
+            let context = &self.shared.schema["data"]["CONTEXT"];
+            let vary: Map<String, Value> = vary
+                .into_iter()
+                .map(|path| {
+                    let value = resolve_pointer(context, &path).cloned();
+                    (path, value.unwrap_or(Value::Null))
+                })
+                .collect();
+            id = serde_json::to_string(&(&id, &self.shared.lang, &expires, vary, &self.code))
+                .unwrap();
         }
 
         let mut hasher = Sha256::new();
         hasher.update(id.clone());
         let cache_id = format!("{:x}", hasher.finalize());
-        let cache_dir = self.get_cache_dir(&cache_id);
-        let file = format!("{}/{}-{}", cache_dir, &cache_id, expires);
-        let file_path = Path::new(&file);
+        let ttl = expires.parse::<u64>().unwrap_or(0);
+        let cache = Arc::clone(&self.shared.cache);
+        let mut cached = cache.get(&cache_id, ttl);
+
+        // Only one render of the process fills it, until the guard is dropped.
+        let mut _fill_guard = None;
+        let mut stale = false;
+        if let Ok(None) = cached {
+            match begin_fill(&cache_id, ttl) {
+                Ok(guard) => _fill_guard = Some(guard),
+                Err(fill) => {
+                    // Another render is filling it, the stale copy or wait for it.
+                    cached = match cache.get_stale(&cache_id, ttl) {
+                        Ok(Some(content)) => {
+                            stale = true;
+                            Ok(Some(content))
+                        }
+                        _ => {
+                            fill.wait(FILL_TIMEOUT);
+                            cache.get(&cache_id, ttl)
+                        }
+                    };
+                }
+            }
+        }
 
-        if file_path.exists()
-            && !self.cache_file_expires(file_path, expires.parse::<u64>().unwrap_or(0))
-        {
-            if let Ok(content) = fs::read_to_string(file_path) {
+        match cached {
+            Ok(Some(content)) => {
+                let stats = &mut self.shared.cache_stats;
+                if stale {
+                    stats.stale += 1;
+                } else {
+                    stats.hits += 1;
+                }
+                stats.bytes_served += content.len() as u64;
                 self.out = content;
-            } else {
-                // The output is created even if there is an error
+            }
+            Ok(None) => {
+                self.shared.cache_stats.misses += 1;
+
                 if self.code.contains(BIF_OPEN) {
                     self.inherit.in_cache = true;
-                    self.out = new_child_parse!(self, &self.code, self.mod_scope);
+                    self.code = new_child_parse!(self, &self.code, self.mod_scope);
                     self.inherit.in_cache = restore_in_cache;
                 }
-                return Err(
-                    self.bif_error(&format!("Failed to read cache {}", file_path.display()))
-                );
-            }
-        } else {
-            if self.code.contains(BIF_OPEN) {
-                self.inherit.in_cache = true;
-                self.code = new_child_parse!(self, &self.code, self.mod_scope);
-                self.inherit.in_cache = restore_in_cache;
-            }
 
-            // The output is created even if there is an error
-            self.out = self.code.clone();
+                // The output is created even if there is an error
+                self.out = self.code.clone();
 
-            // Create cache dir
-            self.set_cache_dir(&cache_dir)?;
-
-            // Write in cache
-            match File::create(&file_path) {
-                Ok(mut file) => {
-                    if let Err(e) = file.write_all(&self.code.as_bytes()) {
-                        return Err(self.bif_error(&format!(
-                            "Failed to write to cache {}: {}",
-                            file_path.display(),
-                            e.to_string()
-                        )));
+                if let Err(e) = cache.set(&cache_id, ttl, &self.code) {
+                    self.shared.cache_stats.write_errors += 1;
+                    return Err(self.bif_error(&e));
+                }
+                self.shared.cache_stats.writes += 1;
+
+                if !tags.is_empty() {
+                    if let Err(e) = cache.tag(&cache_id, ttl, &tags) {
+                        self.shared.cache_stats.write_errors += 1;
+                        return Err(self.bif_error(&e));
                     }
                 }
-                Err(e) => {
-                    return Err(self.bif_error(&format!(
-                        "Failed to create file {}: {}",
-                        file_path.display(),
-                        e.to_string()
-                    )))
+
+                sweep_on_write(
+                    &self.shared.cache_dir,
+                    &self.shared.cache_prefix,
+                    self.shared.cache_purge_every,
+                    self.shared.cache_max_size,
+                );
+            }
+            Err(e) => {
+                self.shared.cache_stats.read_errors += 1;
+
+                // The output is created even if there is an error
+                if self.code.contains(BIF_OPEN) {
+                    self.inherit.in_cache = true;
+                    self.out = new_child_parse!(self, &self.code, self.mod_scope);
+                    self.inherit.in_cache = restore_in_cache;
                 }
+                return Err(self.bif_error(&e));
             }
         }
 
         Ok(())
     }
 
-    pub(crate) fn cache_file_expires(&self, file_path: &Path, expires: u64) -> bool {
-        let now: u64 = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let metadata = match fs::metadata(file_path) {
-            Ok(meta) => meta,
-            Err(_) => return true,
-        };
-
-        let modified_time = match metadata.modified() {
-            Ok(time) => time,
-            Err(_) => return true,
-        };
-
-        let duration_since_epoch = match modified_time.duration_since(UNIX_EPOCH) {
-            Ok(duration) => duration,
-            Err(_) => return true,
-        };
-
-        let file_modified_time = duration_since_epoch.as_secs();
-        let expiration_time = file_modified_time + expires;
-
-        if now > expiration_time {
-            return true;
-        }
-
-        false
-    }
-
-    pub(crate) fn set_cache_dir(&self, cache_dir: &str) -> Result<(), BifError> {
-        let cache_dir_levels = Path::new(&cache_dir);
-
-        match fs::create_dir_all(cache_dir_levels) {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                return Err(self.bif_error(&format!(
-                    "Failed to create cache directory {}: {}",
-                    cache_dir,
-                    e.to_string()
-                )))
-            }
+    // The tags of {:flg; tags tag1 tag2 ... :}
+    fn cache_tags(&self) -> Result<Vec<String>, BifError> {
+        let mut flags = self.flags.split('|').filter(|s| !s.is_empty());
+        match flags.next() {
+            Some("tags") => Ok(flags.map(String::from).collect()),
+            Some(flag) => Err(self.bif_error(&format!("{} flag not allowed", flag))),
+            None => Ok(Vec::new()),
         }
     }
-
-    pub(crate) fn get_cache_dir(&self, file: &str) -> String {
-        let mut cache_dir = self.shared.cache_dir.clone();
-
-        if !self.shared.cache_prefix.is_empty() {
-            cache_dir.push_str("/");
-            cache_dir.push_str(&self.shared.cache_prefix);
-        }
-
-        cache_dir.push_str("/");
-        cache_dir.push_str(&file[0..3]);
-
-        cache_dir.to_string()
-    }
 }
 
 #[cfg(test)]
@@ -562,4 +562,119 @@ mod tests {
         assert!(template.has_error());
         assert_eq!(result, "<div></div>");
     }
+
+    #[test]
+    fn test_bif_cache_tags() {
+        let mut template = crate::Template::new().unwrap();
+
+        let src = "<div>{:cache; {:flg; tags a-{:;__test-nts:} b :} /3/ >> nts :}</div>";
+
+        template.merge_schema_str(SCHEMA_CACHE).unwrap();
+        template.set_src_str(src);
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div>nts</div>");
+
+        let src = "<div>{:cache; {:flg; catalog :} /3/ >> nts :}</div>";
+
+        template.set_src_str(src);
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(template.get_errors()[0].message, "catalog flag not allowed");
+        assert_eq!(result, "<div></div>");
+
+        // The flags are checked with the cache disabled too.
+        template
+            .merge_schema_str(r#"{"config": {"cache_disable": true}}"#)
+            .unwrap();
+        template.set_src_str(src);
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(template.get_errors()[0].message, "catalog flag not allowed");
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_bif_cache_vary() {
+        let cache = std::sync::Arc::new(crate::MemoryCache::new(10));
+        let render = |vary: serde_json::Value, context: serde_json::Value, src: &str| {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA_CACHE).unwrap();
+            template.merge_schema_value(serde_json::json!({
+                "config": { "cache_vary": vary },
+                "data": { "CONTEXT": context }
+            }));
+            template.set_cache_backend(std::sync::Arc::clone(&cache));
+            template.set_src_str(src);
+            let out = template.render();
+            assert!(!template.has_error(), "{:?}", template.get_error());
+            out
+        };
+        let src = "{:cache; /60/ >> {:;CONTEXT->COOKIES->theme:} :}";
+        let vary = serde_json::json!(["COOKIES->theme", "HEADERS->Accept-Language"]);
+
+        // The tracking cookie is not in the cache id.
+        for tracking in ["a", "b"] {
+            let context = serde_json::json!({
+                "COOKIES": { "theme": "dark", "tracking": tracking },
+                "HEADERS": { "Accept-Language": "en" }
+            });
+            assert_eq!(render(vary.clone(), context, src), "dark");
+        }
+        assert_eq!(cache.len(), 1);
+
+        let context = serde_json::json!({
+            "COOKIES": { "theme": "light" },
+            "HEADERS": { "Accept-Language": "en" }
+        });
+        assert_eq!(render(vary.clone(), context, src), "light");
+        let context = serde_json::json!({
+            "COOKIES": { "theme": "light" },
+            "HEADERS": { "Accept-Language": "es" }
+        });
+        assert_eq!(render(vary.clone(), context, src), "light");
+        assert_eq!(cache.len(), 3);
+
+        // Overridden by the block, only the theme.
+        let src = "{:cache; /60/id/0/COOKIES->theme/ >> {:;CONTEXT->COOKIES->theme:} :}";
+        for lang in ["en", "es"] {
+            let context = serde_json::json!({
+                "COOKIES": { "theme": "dark" },
+                "HEADERS": { "Accept-Language": lang }
+            });
+            assert_eq!(render(vary.clone(), context, src), "dark");
+        }
+        assert_eq!(cache.len(), 4);
+
+        // The values are not concatenated, "dark" + "en" is not "darke" + "n".
+        let context = serde_json::json!({
+            "COOKIES": { "theme": "darke" },
+            "HEADERS": { "Accept-Language": "n" }
+        });
+        let src = "{:cache; /60/ >> {:;CONTEXT->COOKIES->theme:} :}";
+        assert_eq!(render(vary.clone(), context, src), "darke");
+        assert_eq!(cache.len(), 5);
+
+        // A missing path is not an empty one.
+        let context = serde_json::json!({ "COOKIES": { "theme": "" } });
+        assert_eq!(render(vary.clone(), context, src), "");
+        let context = serde_json::json!({ "COOKIES": {} });
+        assert_eq!(render(vary.clone(), context, src), "");
+        assert_eq!(cache.len(), 7);
+    }
+
+    #[test]
+    fn test_bif_cache_vary_invalid() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_value(serde_json::json!({
+            "config": { "cache_vary": "COOKIES->theme" }
+        }));
+        template.set_src_str("{:cache; /60/ >> nts :}");
+        template.render();
+        assert!(template.has_error());
+        assert_eq!(
+            template.get_errors()[0].message,
+            "config->cache_vary must be a list"
+        );
+    }
 }
@@ -1,7 +1,6 @@
 #![doc = include_str!("../../doc/bif-code.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
-use std::collections::HashSet;
+use crate::{bif::Bif, bif::BifError, constants::*, utils::*};
 
 impl<'a> Bif<'a> {
     /*
@@ -9,29 +8,9 @@ impl<'a> Bif<'a> {
         {:code; {:flg; safe noparse encode_tags encode_tags_after encode_bifs :} >>  <div>...</div>  :}
     */
     pub(crate) fn parse_bif_code(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            let flags_allowed: HashSet<&str> = [
-                "safe",
-                "encode_tags",
-                "encode_bifs",
-                "noparse",
-                "encode_tags_after",
-            ]
-            .into_iter()
-            .collect();
-
-            for f in self.flags.split('|').filter(|s| !s.is_empty()) {
-                if !flags_allowed.contains(f) {
-                    return Err(self.bif_error(&format!("{} flag not allowed", f)));
-                }
-            }
-        }
+        self.check_flags()?;
 
         if self.flags.contains("|safe|") {
             self.code = escape_chars(&unescape_chars(&self.code, false), false).to_string();
@@ -0,0 +1,65 @@
+#![doc = include_str!("../../doc/bif-cookie.md")]
+
+use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
+
+impl<'a> Bif<'a> {
+    /*
+        {:cookie; name >> value :}
+        {:cookie; name >> value; Path=/; Max-Age=3600; HttpOnly :}
+    */
+    pub(crate) fn parse_bif_cookie(&mut self) -> Result<(), BifError> {
+        // In a cache block, the cookie is set again when the cache is read.
+        if self.inherit.in_cache {
+            self.out = format!("{}{}{}", "{:!cache;", self.raw, ":}");
+        } else {
+            self.out = EMPTY_STRING;
+        }
+
+        let has_value = self.extract_params_code(true);
+
+        self.check_flags()?;
+
+        if !has_value {
+            return Err(self.bif_error(BIF_ERROR_ARGS_VALUE_NOT_FOUND));
+        }
+
+        if self.code.contains(BIF_OPEN) {
+            self.code = new_child_parse!(self, &self.code, false);
+        }
+
+        let name = self.params.trim().to_string();
+        let (value, options) = match self.code.split_once(';') {
+            Some((value, options)) => (value.trim(), options),
+            None => (self.code.trim(), ""),
+        };
+
+        if !is_http_token(&name) {
+            return Err(self.bif_error(&format!("{}: {}", BIF_ERROR_INVALID_COOKIE_NAME, name)));
+        }
+
+        // RFC 6265 cookie-octet
+        if !value
+            .chars()
+            .all(|c| c.is_ascii_graphic() && !matches!(c, '"' | ',' | ';' | '\\'))
+        {
+            return Err(self.bif_error(BIF_ERROR_INVALID_COOKIE_VALUE));
+        }
+
+        let mut set_cookie = format!("{}={}", name, value);
+        for option in options.split(';').map(str::trim).filter(|o| !o.is_empty()) {
+            if option.chars().any(|c| c.is_control()) {
+                return Err(self.bif_error(BIF_ERROR_INVALID_COOKIE_VALUE));
+            }
+            set_cookie.push_str("; ");
+            set_cookie.push_str(option);
+        }
+
+        self.shared.set_cookie(&name, &set_cookie);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "parse_bif_cookie_tests.rs"]
+mod tests;
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::*;
+
+    fn set_cookie(value: &str) -> (String, String) {
+        ("Set-Cookie".to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_bif_cookie() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:cookie; theme >> dark :}{:cookie; test >> {:;__test-nts:};Path=/;  HttpOnly; :}</div>",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(
+            template.get_headers(),
+            vec![
+                set_cookie("theme=dark"),
+                set_cookie("test=nts; Path=/; HttpOnly")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bif_cookie_replace() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:header; X-Test >> 1 :}{:cookie; theme >> dark :}{:cookie; theme >> ; Max-Age=0 :}</div>",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(
+            template.get_headers(),
+            vec![
+                ("X-Test".to_string(), "1".to_string()),
+                set_cookie("theme=; Max-Age=0")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bif_cookie_errors() {
+        let sources = [
+            "<div>{:cookie; theme :}</div>",
+            "<div>{:cookie; the=me >> dark :}</div>",
+            "<div>{:cookie; theme >> da rk :}</div>",
+            "<div>{:cookie; theme >> \"dark\" :}</div>",
+            "<div>{:cookie; theme >> dark; Path=/\nDomain=x :}</div>",
+            "<div>{:!cookie; theme >> dark :}</div>",
+        ];
+
+        for src in sources {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA).unwrap();
+            template.set_src_str(src);
+            let result = template.render();
+            assert!(template.has_error(), "src: {}", src);
+            assert_eq!(result, "<div></div>");
+            assert!(template.get_headers().is_empty(), "src: {}", src);
+        }
+    }
+
+    #[test]
+    fn test_bif_cookie_cache() {
+        let src = "<div>{:cache; /60/ >> <p>{:cookie; cached-cookie >> {:;__test-nts:}; Path=/ :}{:;__test-nts:}</p> :}</div>";
+
+        // first write the cache, then read it
+        for _ in 0..2 {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA_CACHE).unwrap();
+            template.set_src_str(src);
+            let result = template.render();
+            assert!(!template.has_error());
+            assert_eq!(result, "<div><p>nts</p></div>");
+            assert_eq!(
+                template.get_headers(),
+                vec![set_cookie("cached-cookie=nts; Path=/")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_bif_cookie_name_from_data() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.merge_schema_value(serde_json::json!({
+            "data": { "cookie-name": "{:exit; 404 :}" }
+        }));
+        template.set_src_str("<div>{:cookie; {:;cookie-name:} >> 1 :}</div>");
+        let result = template.render();
+        assert_eq!(result, "<div></div>");
+        assert_eq!(template.get_status_code(), "200");
+        assert!(template.get_headers().is_empty());
+        assert!(template.get_errors()[0].message.ends_with("{:exit; 404 :}"));
+    }
+}
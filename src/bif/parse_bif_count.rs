@@ -17,10 +17,6 @@ impl<'a> Bif<'a> {
         {:count; name :}
     */
     pub(crate) fn parse_bif_count(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         let is_set = self.extract_params_code(true);
 
         if self.code.contains(BIF_OPEN) {
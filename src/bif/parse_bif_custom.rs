@@ -0,0 +1,33 @@
+use crate::{bif::Bif, bif::BifError, bif_handler::BifContext};
+use std::sync::Arc;
+
+impl<'a> Bif<'a> {
+    /*
+        custom bif, see Template::register_bif
+        {:name; ... :}
+    */
+    pub(crate) fn parse_bif_custom(&mut self) -> Result<(), BifError> {
+        let handler = match self.shared.bif_handlers.get(&self.name) {
+            Some(handler) => Arc::clone(handler),
+            None => return self.parse_bif_unknown(),
+        };
+
+        let has_code = self.extract_params_code(true);
+        let mut ctx = BifContext {
+            bif: self,
+            has_code,
+        };
+
+        match handler.parse(&mut ctx) {
+            Ok(out) => {
+                self.out = out;
+                Ok(())
+            }
+            Err(msg) => Err(self.bif_error(&msg)),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "parse_bif_custom_tests.rs"]
+mod tests;
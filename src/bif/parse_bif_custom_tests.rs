@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::*;
+    use crate::{BifContext, Engine};
+
+    fn price(ctx: &mut BifContext) -> Result<String, String> {
+        let amount: f64 = ctx
+            .parse_code()
+            .parse()
+            .map_err(|_| "amount is not a number")?;
+
+        if ctx.has_flag("round") {
+            return Ok(format!("{} {}", amount.round(), ctx.params()));
+        }
+
+        Ok(format!("{:.2} {}", amount, ctx.params()))
+    }
+
+    fn new_template() -> crate::Template {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.register_bif("price", price).unwrap();
+        template
+    }
+
+    #[test]
+    fn test_bif_custom() {
+        let mut template = new_template();
+        template.merge_schema_value(crate::json!({ "data": { "amount": "10" } }));
+        template.set_src_str("<div>{:price; {:;__test-nts:} >> {:;amount:}.5 :}</div>");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div>10.50 nts</div>");
+    }
+
+    #[test]
+    fn test_bif_custom_flags() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:price; {:flg; round :} EUR >> 9.7 :}</div>");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div>10 EUR</div>");
+    }
+
+    #[test]
+    fn test_bif_custom_error() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:price; EUR >> none :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(template.get_errors()[0].bif_name, "price");
+        assert_eq!(template.get_errors()[0].message, "amount is not a number");
+    }
+
+    #[test]
+    fn test_bif_custom_in_snippet() {
+        let mut template = new_template();
+        template.set_src_str(
+            "{:include; tests/snippets.ntpl :}{:code; {:snippet; test-snippet :}{:price; $ >> 1 :} :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div>test snippet</div>1.00 $");
+    }
+
+    #[test]
+    fn test_bif_custom_context() {
+        let mut template = new_template();
+        template
+            .register_bif("ctx", |ctx: &mut BifContext| {
+                let args = ctx.args().join(",");
+                let modifiers = ctx.modifiers();
+                Ok(format!(
+                    "{}:{}:{}:{}:{}",
+                    ctx.name(),
+                    args,
+                    ctx.get_data("__test-nts"),
+                    modifiers.negate,
+                    ctx.has_code()
+                ))
+            })
+            .unwrap();
+        template.set_src_str("<div>{:!ctx; /a/{:;__test-nts:}/ >> code :}</div>");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div>ctx:,a,nts,:nts:true:true</div>");
+    }
+
+    #[test]
+    fn test_bif_custom_register_builtin() {
+        let mut template = new_template();
+        assert!(template.register_bif("include", price).is_err());
+        assert!(template.register_bif("snip", price).is_err());
+        assert!(template.register_bif("!price", price).is_err());
+        assert!(template.register_bif("", price).is_err());
+    }
+
+    #[test]
+    fn test_bif_custom_unknown() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:cost; 1 :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(template.get_errors()[0].bif_name, "cost");
+    }
+
+    #[test]
+    fn test_bif_custom_engine() {
+        let mut engine = Engine::new().unwrap();
+        engine.merge_schema_str(SCHEMA).unwrap();
+        engine.register_bif("price", price).unwrap();
+        let mut template = engine.context_str("{:price; EUR >> 2 :}").unwrap();
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "2.00 EUR");
+    }
+}
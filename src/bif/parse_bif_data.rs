@@ -1,25 +1,15 @@
 #![doc = include_str!("../../doc/bif-data.md")]
 
 use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*, Value};
-use std::fs;
-use std::path::Path;
 
 impl<'a> Bif<'a> {
     /*
         {:data; file-path :} {:* local data *}
     */
     pub(crate) fn parse_bif_data(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            if !self.flags.contains("|require|") && !self.flags.contains("|inline|") {
-                return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-            }
-        }
+        self.check_flags()?;
 
         if self.flags.contains("|inline|") {
             let mut data: Value = match serde_json::from_str(&self.code) {
@@ -36,10 +26,7 @@ impl<'a> Bif<'a> {
                 .as_object_mut()
                 .and_then(|obj| obj.remove("data"))
                 .unwrap_or(Value::Null);
-            merge_schema_owned(
-                &mut self.shared.get_indir_mut(indir)["data"],
-                merge_data,
-            );
+            merge_schema_owned(&mut self.shared.get_indir_mut(indir)["data"], merge_data);
 
             self.out = UNPRINTABLE.to_string();
 
@@ -56,25 +43,18 @@ impl<'a> Bif<'a> {
             self.file_path = new_child_parse!(self, &self.code, false);
         }
 
-        if let Some(stripped) = self.file_path.strip_prefix('#') {
-            self.file_path = format!("{}{}", self.inherit.current_dir, stripped);
-        }
-
-        let path = Path::new(&self.file_path);
-        if !Path::new(path).exists() {
-            if self.flags.contains("|require|") {
-                return Err(self.bif_error("file not found"));
-            } else {
-                self.out = EMPTY_STRING;
+        let canonical_path = match self.resolve_file(true)? {
+            Some(canonical_path) => canonical_path,
+            None => {
+                if self.flags.contains("|require|") {
+                    return Err(self.bif_error("file not found"));
+                } else {
+                    self.out = EMPTY_STRING;
 
-                return Ok(());
+                    return Ok(());
+                }
             }
-        }
-
-        let canonical_path = fs::canonicalize(path)
-            .unwrap()
-            .to_string_lossy()
-            .into_owned();
+        };
 
         if self.mod_negate && self.inherit.data_files.contains(&canonical_path) {
             self.out = UNPRINTABLE.to_string();
@@ -82,8 +62,8 @@ impl<'a> Bif<'a> {
             return Ok(());
         }
 
+        let file_raw = self.shared.loader.read(&canonical_path).unwrap_or_default();
         self.inherit.data_files.push(canonical_path);
-        let file_raw = fs::read_to_string(&self.file_path).unwrap_or("".to_string());
 
         let mut data: Value = match serde_json::from_str(&file_raw) {
             Ok(value) => value,
@@ -99,10 +79,7 @@ impl<'a> Bif<'a> {
             .as_object_mut()
             .and_then(|obj| obj.remove("data"))
             .unwrap_or(Value::Null);
-        merge_schema_owned(
-            &mut self.shared.get_indir_mut(indir)["data"],
-            merge_data,
-        );
+        merge_schema_owned(&mut self.shared.get_indir_mut(indir)["data"], merge_data);
 
         self.out = UNPRINTABLE.to_string();
 
@@ -140,5 +140,13 @@ mod tests {
         let result = template.render();
         assert!(template.has_error());
         assert_eq!(result, "<div></div>");
+
+        template.set_src_str("<div>{:data; {:flg; require invalid_flag :} >> tests/local-data.json :}{:;local::hello:}</div>");
+        let result = template.render();
+        assert_eq!(
+            template.get_errors()[0].message,
+            "invalid_flag flag not allowed"
+        );
+        assert_eq!(result, "<div></div>");
     }
 }
@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-date.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+use crate::{bif::Bif, bif::BifError, constants::*};
 use chrono::Utc;
 
 impl<'a> Bif<'a> {
@@ -9,15 +9,9 @@ impl<'a> Bif<'a> {
         {:date; %Y-%m-%d %H:%M:%S  :} UTC
     */
     pub(crate) fn parse_bif_date(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         let now = Utc::now();
 
@@ -1,6 +1,7 @@
 #![doc = include_str!("../../doc/bif-debug.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
+use crate::{bif::Bif, bif::BifError, constants::*, utils::*};
+use std::borrow::Cow;
 use std::fs;
 use std::path::Path;
 use std::time::{Duration, SystemTime};
@@ -10,10 +11,6 @@ impl<'a> Bif<'a> {
         {:debug; data->key :}
     */
     pub(crate) fn parse_bif_debug(&mut self) -> Result<(), BifError> {
-        if self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         let debug_enable = self.debug_enable();
 
         if self.mod_negate {
@@ -39,24 +36,29 @@ impl<'a> Bif<'a> {
 
         self.extract_params_code(true);
 
-        let mut schema = &self.shared.schema;
-        let mut key_name = self.code.clone();
-        if key_name.starts_with("local::") {
-            key_name = key_name.strip_prefix("local::").unwrap_or("").to_string();
-            schema = &self.shared.get_indir(&self.inherit.indir);
-        }
-
-        let k = if self.code.is_empty() {
+        if self.code.is_empty() {
             self.out = VERSION.to_string();
             return Ok(());
-        } else if key_name == "full-schema" {
-            "".to_string()
-        } else {
-            format!("/{}", key_name).replace(BIF_ARRAY, "/")
+        }
+
+        let value = match self.code.strip_prefix("local::") {
+            Some(key_name) => {
+                let k = if key_name == "full-schema" {
+                    "".to_string()
+                } else {
+                    format!("/{}", key_name).replace(BIF_ARRAY, "/")
+                };
+                self.shared
+                    .get_indir(&self.inherit.indir)
+                    .pointer(&k)
+                    .map(Cow::Borrowed)
+            }
+            None if self.code == "full-schema" => self.shared.schema_value(""),
+            None => self.shared.schema_value(&self.code),
         };
 
-        self.out = match schema.pointer(&k) {
-            Some(value) => match serde_json::to_string_pretty(value) {
+        self.out = match value {
+            Some(value) => match serde_json::to_string_pretty(&value) {
                 Ok(json_str) => json_str,
                 Err(e) => format!("Error formatting JSON: {}", e),
             },
@@ -7,15 +7,9 @@ impl<'a> Bif<'a> {
         {:declare; name >> words list :}
     */
     pub(crate) fn parse_bif_declare(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if self.inherit.current_file.contains(SNIPPETS_FILES) {
             self.inherit.create_block_schema(self.shared);
@@ -1,9 +1,7 @@
 #![doc = include_str!("../../doc/bif-each.md")]
 
-use crate::{
-    bif::constants::*, bif::Bif, bif::BifError, json, utils::extract_blocks,
-    utils::resolve_pointer, Value,
-};
+use crate::{bif::constants::*, bif::Bif, bif::BifError, compiled::CompiledTemplate, json, Value};
+use std::sync::Arc;
 
 impl<'a> Bif<'a> {
     /*
@@ -12,15 +10,9 @@ impl<'a> Bif<'a> {
         :}
     */
     pub(crate) fn parse_bif_each(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         let mut parts = self.params.split_whitespace();
 
@@ -49,60 +41,75 @@ impl<'a> Bif<'a> {
             return Err(self.bif_error(BIF_ERROR_INSECURE_VARNAME));
         }
 
-        let restore_key = self.shared.schema["data"][&key_name].clone();
-        let restore_val = self.shared.schema["data"][&val_name].clone();
+        let restore_key = self.shared.schema["data"].get(&key_name).cloned();
+        let restore_val = self.shared.schema["data"].get(&val_name).cloned();
 
-        let data_storage = if array_name.starts_with("local::") {
-            &self.shared.get_indir(&self.inherit.indir)["data"]
-        } else {
-            &self.shared.schema["data"]
+        let collection = match self.data_value(&array_name) {
+            Some(data_value) => data_value.into_owned(),
+            None => Value::Null,
         };
 
-        let array_clean = array_name.strip_prefix("local::").unwrap_or(&array_name);
+        // The code is compiled once for all iterations.
+        let compiled = self
+            .compiled_for(&self.code)
+            .unwrap_or_else(|| Arc::new(CompiledTemplate::new(&self.code)));
 
-        let collection = if let Some(data_value) = resolve_pointer(data_storage, array_clean) {
-            data_value.clone()
-        } else {
-            Value::Null
-        };
-
-        let blocks = match extract_blocks(&self.code) {
-            Ok(b) => b,
-            Err(p) => return Err(self.bif_error(&format!("Unmatched block at position {}", p))),
-        };
+        if let Err(p) = compiled.blocks() {
+            return Err(self.bif_error(&format!("Unmatched block at position {}", p)));
+        }
 
         match collection {
             Value::Object(obj) => {
                 for (key, val) in obj.iter() {
-                    self.parse_bif_each_iter(&key_name, &val_name, key, val, &blocks);
+                    self.parse_bif_each_iter(&key_name, &val_name, key, val, &compiled);
                 }
             }
             Value::Array(arr) => {
                 for (idx, val) in arr.iter().enumerate() {
-                    self.parse_bif_each_iter(&key_name, &val_name, &idx.to_string(), val, &blocks);
+                    self.parse_bif_each_iter(
+                        &key_name,
+                        &val_name,
+                        &idx.to_string(),
+                        val,
+                        &compiled,
+                    );
                 }
             }
             _ => {}
         }
 
-        self.shared.schema["data"][&key_name] = restore_key;
-        self.shared.schema["data"][&val_name] = restore_val;
+        self.restore_data(&key_name, restore_key);
+        self.restore_data(&val_name, restore_val);
 
         Ok(())
     }
 
+    // The key is removed if it was not set, so that the value of the base
+    // schema, if any, is not hidden.
+    fn restore_data(&mut self, name: &str, value: Option<Value>) {
+        match value {
+            Some(value) => self.shared.schema["data"][name] = value,
+            None => {
+                if let Some(data) = self.shared.schema["data"].as_object_mut() {
+                    data.remove(name);
+                }
+            }
+        }
+    }
+
     fn parse_bif_each_iter(
         &mut self,
         key_name: &str,
         val_name: &str,
         key: &String,
         val: &Value,
-        blocks: &Vec<(usize, usize)>,
+        compiled: &CompiledTemplate,
     ) {
         self.shared.schema["data"][key_name] = json!(key);
         self.shared.schema["data"][val_name] = json!(val);
 
         let mut child_inherit = self.inherit.clone();
+        self.set_child_origin(&mut child_inherit, compiled.source());
         child_inherit.alias = self.alias.clone();
         if !self.file_path.is_empty() {
             child_inherit.current_file = self.file_path.clone();
@@ -116,7 +123,7 @@ impl<'a> Bif<'a> {
         }
 
         let mut block_parser = crate::block_parser::BlockParser::new(self.shared, child_inherit);
-        let code = block_parser.parse_with_blocks(&self.code, blocks, self.only);
+        let code = block_parser.parse_compiled(compiled, self.only);
 
         if self.mod_scope {
             block_parser.update_indir(&self.inherit.indir);
@@ -100,12 +100,12 @@ mod tests {
             }
         };
         template.merge_schema_str(SCHEMA).unwrap();
-        
+
         // Loop using __test-obj-nts as the iterator value
         template.set_src_str("<div>{:each; __test-arr-nts key __test-obj-nts >> :} __test-obj-nts->level1: {:; __test-obj-nts->level1 :}</div>");
         let result = template.render();
         assert!(!template.has_error());
-        
+
         // After the each block, __test-obj-nts should be restored and its level1 property should be "Ok"
         assert_eq!(result, "<div> __test-obj-nts->level1: Ok</div>");
     }
@@ -121,16 +121,16 @@ mod tests {
             }
         };
         template.merge_schema_str(SCHEMA).unwrap();
-        
+
         template.set_src_str("<div>{:each; __test-arr-nts local::key val >> {:;val:} :}</div>");
         let result1 = template.render();
         assert!(template.has_error());
         assert_eq!(result1, "<div></div>");
-        
+
         // Let's reset the error state and test the second case: key local::val
         let mut template2 = crate::Template::new().unwrap();
         template2.merge_schema_str(SCHEMA).unwrap();
-        
+
         template2.set_src_str("<div>{:each; __test-arr-nts key local::val >> {:;val:} :}</div>");
         let result2 = template2.render();
         assert!(template2.has_error());
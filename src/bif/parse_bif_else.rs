@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-else.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+use crate::{bif::Bif, bif::BifError, constants::*};
 
 impl<'a> Bif<'a> {
     /*
@@ -9,15 +9,9 @@ impl<'a> Bif<'a> {
        {:code; not empty :}{:!else; this is output :}
     */
     pub(crate) fn parse_bif_else(&mut self) -> Result<(), BifError> {
-        if self.mod_filter {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if self.inherit.last_bif_out ^ self.mod_negate {
             self.out = EMPTY_STRING;
@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-exit.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+use crate::{bif::Bif, bif::BifError, constants::*};
 
 impl<'a> Bif<'a> {
     /*
@@ -10,10 +10,6 @@ impl<'a> Bif<'a> {
         {:exit; 301 >> /page :}
     */
     pub(crate) fn parse_bif_exit(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         if self.inherit.in_cache {
             self.out = format!("{}{}{}", "{:!cache;", self.raw.to_string(), ":}");
         } else {
@@ -22,9 +18,7 @@ impl<'a> Bif<'a> {
 
         let has_status_params = self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if self.code.contains(BIF_OPEN) {
             self.code = new_child_parse!(self, &self.code, false);
@@ -0,0 +1,48 @@
+#![doc = include_str!("../../doc/bif-extends.md")]
+
+use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+
+impl<'a> Bif<'a> {
+    /*
+        {:extends; layout.ntpl :}
+    */
+    pub(crate) fn parse_bif_extends(&mut self) -> Result<(), BifError> {
+        self.out = EMPTY_STRING;
+        self.extract_params_code(true);
+
+        self.check_flags()?;
+
+        if self.inherit.extends.is_some() {
+            return Err(self.bif_error(BIF_ERROR_EXTENDS_ALREADY_SET));
+        }
+
+        self.file_path = self.code.clone();
+
+        // For security requires {:allow;
+        if self.file_path.contains(BIF_OPEN) {
+            if !self.contains_allow(&self.file_path) {
+                return Err(self.bif_error(BIF_ERROR_INSECURE_FILE_NAME));
+            }
+            self.file_path = new_child_parse!(self, &self.code, false);
+        }
+
+        let canonical_path = match self.resolve_file(true)? {
+            Some(canonical_path) => canonical_path,
+            None => return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND)),
+        };
+
+        // A layout that extends itself or one of its children.
+        if self.inherit.include_files.contains(&canonical_path) {
+            return Err(self.bif_error(BIF_ERROR_ALREADY_INCLUDED));
+        }
+
+        // The layout is parsed when the current block ends, see BlockParser.
+        self.inherit.extends = Some(canonical_path);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "parse_bif_extends_tests.rs"]
+mod tests;
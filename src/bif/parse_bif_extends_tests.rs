@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::*;
+
+    fn new_template() -> crate::Template {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template
+    }
+
+    #[test]
+    fn test_bif_extends() {
+        let mut template = new_template();
+        template.set_src_str(
+            "{:extends; tests/extends-base.ntpl :}Not in the output{:block; title >> Title :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<title>Title</title>\n<main><p>base</p></main>");
+    }
+
+    #[test]
+    fn test_bif_extends_levels() {
+        let mut template = new_template();
+        template.set_src_path("tests/extends-child.ntpl").unwrap();
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(
+            result,
+            "<title>nts</title>\n<main><div><p>base</p></div><aside></aside></main>"
+        );
+    }
+
+    #[test]
+    fn test_bif_extends_include() {
+        let mut template = new_template();
+        template.set_src_str("<html>{:include; tests/extends-child.ntpl :}</html>");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(
+            result,
+            "<html><title>nts</title>\n<main><div><p>base</p></div><aside></aside></main></html>"
+        );
+    }
+
+    #[test]
+    fn test_bif_extends_not_found() {
+        let mut template = new_template();
+        template
+            .set_src_str("{:extends; tests/not-found.ntpl :}<div>{:block; title >> Title :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div>Title</div>");
+    }
+
+    #[test]
+    fn test_bif_extends_already_set() {
+        let mut template = new_template();
+        template.set_src_str(
+            "{:extends; tests/extends-base.ntpl :}{:extends; tests/extends-layout.ntpl :}{:block; title >> Title :}",
+        );
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<title>Title</title>\n<main><p>base</p></main>");
+    }
+
+    #[test]
+    fn test_bif_extends_loop() {
+        let mut template = new_template();
+        template.set_src_str("{:extends; tests/extends-loop.ntpl :}");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "loop");
+    }
+
+    #[test]
+    fn test_bif_extends_modifiers() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:!extends; tests/extends-base.ntpl :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_bif_extends_insecure() {
+        let mut template = new_template();
+        template.set_src_str("<div>{:extends; {:;__test-nts:} :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+}
@@ -7,15 +7,9 @@ impl<'a> Bif<'a> {
         {:fetch; |url|event|wrapperId|class|id|name| >> code :}
     */
     pub(crate) fn parse_bif_fetch(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         let args = self.extract_args();
 
@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-flg.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+use crate::{bif::Bif, bif::BifError, constants::*};
 
 impl<'a> Bif<'a> {
     /*
@@ -8,15 +8,9 @@ impl<'a> Bif<'a> {
         {:code; {:flg; safe :} >>  <div>...</div> :}
     */
     pub(crate) fn parse_bif_flg(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_upline || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if self.code.contains(BIF_OPEN) {
             self.code = new_child_parse!(self, &self.code, false);
@@ -1,6 +1,7 @@
 #![doc = include_str!("../../doc/bif-for.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, utils::extract_blocks};
+use crate::{bif::constants::*, bif::Bif, bif::BifError, compiled::CompiledTemplate};
+use std::sync::Arc;
 
 impl<'a> Bif<'a> {
     /*
@@ -9,15 +10,9 @@ impl<'a> Bif<'a> {
        :}
     */
     pub(crate) fn parse_bif_for(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         self.params = self.params.replace("..", " ");
         let mut parts = self.params.split_whitespace();
@@ -53,19 +48,23 @@ impl<'a> Bif<'a> {
             }
         };
 
-        let blocks = match extract_blocks(&self.code) {
-            Ok(b) => b,
-            Err(p) => return Err(self.bif_error(&format!("Unmatched block at position {}", p))),
-        };
+        // The code is compiled once for all iterations.
+        let compiled = self
+            .compiled_for(&self.code)
+            .unwrap_or_else(|| Arc::new(CompiledTemplate::new(&self.code)));
+
+        if let Err(p) = compiled.blocks() {
+            return Err(self.bif_error(&format!("Unmatched block at position {}", p)));
+        }
 
         let restore_var = self.get_data(&var_name);
         if from > to {
             for i in (to..=from).rev() {
-                self.parse_bif_for_iter(&var_name, &i.to_string(), &blocks);
+                self.parse_bif_for_iter(&var_name, &i.to_string(), &compiled);
             }
         } else {
             for i in from..=to {
-                self.parse_bif_for_iter(&var_name, &i.to_string(), &blocks);
+                self.parse_bif_for_iter(&var_name, &i.to_string(), &compiled);
             }
         };
         self.set_data(&var_name, &restore_var);
@@ -73,10 +72,11 @@ impl<'a> Bif<'a> {
         Ok(())
     }
 
-    fn parse_bif_for_iter(&mut self, var_name: &str, val: &str, blocks: &Vec<(usize, usize)>) {
+    fn parse_bif_for_iter(&mut self, var_name: &str, val: &str, compiled: &CompiledTemplate) {
         self.set_data(var_name, val);
 
         let mut child_inherit = self.inherit.clone();
+        self.set_child_origin(&mut child_inherit, compiled.source());
         child_inherit.alias = self.alias.clone();
         if !self.file_path.is_empty() {
             child_inherit.current_file = self.file_path.clone();
@@ -90,7 +90,7 @@ impl<'a> Bif<'a> {
         }
 
         let mut block_parser = crate::block_parser::BlockParser::new(self.shared, child_inherit);
-        let code = block_parser.parse_with_blocks(&self.code, blocks, self.only);
+        let code = block_parser.parse_compiled(compiled, self.only);
 
         if self.mod_scope {
             block_parser.update_indir(&self.inherit.indir);
@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-hash.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+use crate::{bif::Bif, bif::BifError, constants::*};
 use md5::{Digest, Md5};
 use rand::RngExt;
 
@@ -10,10 +10,6 @@ impl<'a> Bif<'a> {
         {:hash; text :}
     */
     pub(crate) fn parse_bif_hash(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.code = self.src.trim().to_string();
 
         if self.src.contains(BIF_OPEN) {
@@ -0,0 +1,53 @@
+#![doc = include_str!("../../doc/bif-header.md")]
+
+use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
+
+impl<'a> Bif<'a> {
+    /*
+        {:header; Content-Type >> application/json :}
+        {:header; Cache-Control >> no-store :}
+    */
+    pub(crate) fn parse_bif_header(&mut self) -> Result<(), BifError> {
+        // In a cache block, the header is set again when the cache is read.
+        if self.inherit.in_cache {
+            self.out = format!("{}{}{}", "{:!cache;", self.raw, ":}");
+        } else {
+            self.out = EMPTY_STRING;
+        }
+
+        let has_value = self.extract_params_code(true);
+
+        self.check_flags()?;
+
+        if !has_value {
+            return Err(self.bif_error(BIF_ERROR_ARGS_VALUE_NOT_FOUND));
+        }
+
+        if self.code.contains(BIF_OPEN) {
+            self.code = new_child_parse!(self, &self.code, false);
+        }
+
+        let name = self.params.trim().to_string();
+        let value = self.code.trim().to_string();
+
+        if !is_http_token(&name) {
+            return Err(self.bif_error(&format!("{}: {}", BIF_ERROR_INVALID_HEADER_NAME, name)));
+        }
+
+        if name.eq_ignore_ascii_case("Set-Cookie") {
+            return Err(self.bif_error(BIF_ERROR_SET_COOKIE_HEADER));
+        }
+
+        if value.chars().any(|c| c.is_control() && c != '\t') {
+            return Err(self.bif_error(BIF_ERROR_INVALID_HEADER_VALUE));
+        }
+
+        self.shared.set_header(&name, &value);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "parse_bif_header_tests.rs"]
+mod tests;
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::*;
+
+    fn header(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_bif_header() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:header; Content-Type >> text/plain :}{:header; X-Test >> {:;__test-nts:} :}</div>",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(
+            template.get_headers(),
+            vec![
+                header("Content-Type", "text/plain"),
+                header("X-Test", "nts")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bif_header_replace() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:header; Vary >> Cookie :}{:header; X-Test >> 1 :}{:header; vary >> Accept :}</div>",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(
+            template.get_headers(),
+            vec![header("Vary", "Accept"), header("X-Test", "1")]
+        );
+    }
+
+    #[test]
+    fn test_bif_header_errors() {
+        let sources = [
+            "<div>{:header; Content-Type :}</div>",
+            "<div>{:header; Content Type >> text/plain :}</div>",
+            "<div>{:header;  >> text/plain :}</div>",
+            "<div>{:header; Set-Cookie >> a=b :}</div>",
+            "<div>{:header; X-Test >> a\nb :}</div>",
+            "<div>{:!header; X-Test >> a :}</div>",
+            "<div>{:header; {:flg; some :} X-Test >> a :}</div>",
+        ];
+
+        for src in sources {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA).unwrap();
+            template.set_src_str(src);
+            let result = template.render();
+            assert!(template.has_error(), "src: {}", src);
+            assert_eq!(result, "<div></div>");
+            assert!(template.get_headers().is_empty(), "src: {}", src);
+        }
+    }
+
+    #[test]
+    fn test_bif_header_exit() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str("<div>{:header; Retry-After >> 120 :}{:exit; 503 :}</div>");
+        template.render();
+        assert!(!template.has_error());
+        assert_eq!(template.get_status_code(), "503");
+        assert_eq!(template.get_headers(), vec![header("Retry-After", "120")]);
+    }
+
+    #[test]
+    fn test_bif_header_cache() {
+        let src = "<div>{:cache; /60/ >> <p>{:header; X-Cached-Header >> {:;__test-nts:} :}{:;__test-nts:}</p> :}</div>";
+
+        // first write the cache, then read it
+        for _ in 0..2 {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA_CACHE).unwrap();
+            template.set_src_str(src);
+            let result = template.render();
+            assert!(!template.has_error());
+            assert_eq!(result, "<div><p>nts</p></div>");
+            assert_eq!(
+                template.get_headers(),
+                vec![header("X-Cached-Header", "nts")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_bif_header_render_full() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:header; Location >> /other :}{:header; X-Test >> 1 :}{:redirect; 302 >> /page :}</div>",
+        );
+        let output = template.render_full();
+        assert!(!output.has_error());
+        assert_eq!(
+            output.headers,
+            vec![header("Location", "/page"), header("X-Test", "1")]
+        );
+    }
+
+    #[test]
+    fn test_bif_header_name_from_data() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.merge_schema_value(serde_json::json!({
+            "data": { "header-name": "{:exit; 404 :}" }
+        }));
+        template.set_src_str("<div>{:header; {:;header-name:} >> 1 :}</div>");
+        let result = template.render();
+        assert_eq!(result, "<div></div>");
+        assert_eq!(template.get_status_code(), "200");
+        assert!(template.get_headers().is_empty());
+        assert_eq!(
+            template.get_errors()[0].message,
+            "invalid header name: {:exit; 404 :}"
+        );
+    }
+}
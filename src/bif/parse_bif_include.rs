@@ -1,9 +1,11 @@
 #![doc = include_str!("../../doc/bif-include.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*};
-use std::collections::HashSet;
-use std::fs;
+use crate::{
+    bif::constants::*, bif::Bif, bif::BifError, compiled::compile_file_cached,
+    compiled::CompiledTemplate, compiled::SourceOrigin, constants::*, utils::*,
+};
 use std::path::Path;
+use std::sync::Arc;
 
 impl<'a> Bif<'a> {
     /*
@@ -11,21 +13,9 @@ impl<'a> Bif<'a> {
         {:include; {:flg; require safe noparse :} >> file-path :}
     */
     pub(crate) fn parse_bif_include(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            let flags_allowed: HashSet<&str> = ["require", "safe", "noparse"].into_iter().collect();
-
-            for f in self.flags.split('|').filter(|s| !s.is_empty()) {
-                if !flags_allowed.contains(f) {
-                    return Err(self.bif_error(&format!("{} flag not allowed", f)));
-                }
-            }
-        }
+        self.check_flags()?;
 
         self.file_path = self.code.clone();
 
@@ -37,59 +27,52 @@ impl<'a> Bif<'a> {
             self.file_path = new_child_parse!(self, &self.code, false);
         }
 
-        if let Some(stripped) = self.file_path.strip_prefix('#') {
-            self.file_path = format!("{}{}", self.inherit.current_dir, stripped);
-        }
-
-        let path = Path::new(&self.file_path);
-        if !path.exists() {
-            if self.flags.contains("|require|") {
-                return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND));
-            } else {
-                return Ok(());
+        let canonical_path = match self.resolve_file(true)? {
+            Some(canonical_path) => canonical_path,
+            None => {
+                if self.flags.contains("|require|") {
+                    return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND));
+                } else {
+                    return Ok(());
+                }
             }
-        }
+        };
 
-        if let Some(parent) = path.parent() {
+        if let Some(parent) = Path::new(&self.file_path).parent() {
             self.dir = parent.display().to_string();
         }
 
-        let canonical_path = fs::canonicalize(path)
-            .unwrap()
-            .to_string_lossy()
-            .into_owned();
-
         if self.mod_negate && self.inherit.include_files.contains(&canonical_path) {
             self.out = EMPTY_STRING;
 
             return Ok(());
         }
 
-        if self.flags.contains("|safe|") {
-            self.code = fs::read_to_string(&self.file_path).unwrap_or("".to_string());
-            self.code = escape_chars(&unescape_chars(&self.code, false), false).to_string();
-            self.code = self.code.replace(BIF_OPEN, BIF_SANITIZE_OPEN);
-            self.code = self.code.replace(BIF_CLOSE, BIF_SANITIZE_CLOSE);
-            self.out = self.code.clone();
+        let loader = Arc::clone(&self.shared.loader);
 
-            return Ok(());
-        }
+        if self.flags.contains("|safe|") || self.flags.contains("|noparse|") {
+            self.code = compile_file_cached(loader.as_ref(), &canonical_path, false)
+                .map(|compiled| compiled.source().to_string())
+                .unwrap_or_default();
 
-        if self.flags.contains("|noparse|") {
-            self.code = fs::read_to_string(&self.file_path).unwrap_or("".to_string());
+            if self.flags.contains("|safe|") {
+                self.code = escape_chars(&unescape_chars(&self.code, false), false).to_string();
+                self.code = self.code.replace(BIF_OPEN, BIF_SANITIZE_OPEN);
+                self.code = self.code.replace(BIF_CLOSE, BIF_SANITIZE_CLOSE);
+            }
             self.out = self.code.clone();
 
             return Ok(());
         }
 
-        self.inherit.include_files.push(canonical_path);
-
-        let mut file_raw = fs::read_to_string(&self.file_path).unwrap_or("".to_string());
-        if self.shared.comments.contains("remove") {
-            file_raw = remove_comments(&file_raw);
-        }
+        let remove_comments = self.shared.comments.contains("remove");
+        let compiled = compile_file_cached(loader.as_ref(), &canonical_path, remove_comments)
+            .unwrap_or_else(|_| Arc::new(CompiledTemplate::new("")));
 
-        self.out = new_child_parse!(self, &file_raw, true);
+        self.inherit.include_files.push(canonical_path);
+        self.child_compiled = Some(Arc::clone(&compiled));
+        self.child_origin = Some(SourceOrigin::new(Arc::clone(&compiled), 0));
+        self.out = new_child_parse!(self, compiled.source(), true);
 
         Ok(())
     }
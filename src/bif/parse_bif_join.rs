@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-join.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, Value};
+use crate::{bif::constants::*, bif::Bif, bif::BifError, Value};
 
 impl<'a> Bif<'a> {
     /*
@@ -10,13 +10,9 @@ impl<'a> Bif<'a> {
         {:join; /array/ / :}
     */
     pub(crate) fn parse_bif_join(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.params = self.src.clone();
         let args = self.extract_args();
-        let mut array_name = args
+        let array_name = args
             .get(1)
             .cloned()
             .ok_or_else(|| self.bif_error(BIF_ERROR_ARGS_ARRAY_NOT_FOUND))?;
@@ -35,19 +31,9 @@ impl<'a> Bif<'a> {
             _ => true,
         };
 
-        let data_storage;
-        array_name = format!("{}{}", "/", array_name);
-        array_name = array_name.replace(BIF_ARRAY, "/");
-        if array_name.starts_with("/local::") {
-            array_name = array_name.replace("/local::", "/");
-            data_storage = &self.shared.get_indir(&self.inherit.indir)["data"];
-        } else {
-            data_storage = &self.shared.schema["data"];
-        }
-
         let mut joined = String::new();
-        if let Some(data_value) = data_storage.pointer(&array_name) {
-            match data_value.to_owned() {
+        if let Some(data_value) = self.data_value(&array_name) {
+            match data_value.into_owned() {
                 Value::Object(obj) => {
                     if keys {
                         joined = obj
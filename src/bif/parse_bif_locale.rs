@@ -1,28 +1,19 @@
 #![doc = include_str!("../../doc/bif-locale.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, utils::*, Value};
-use std::fs;
-use std::path::Path;
+use crate::{
+    bif::constants::*, bif::Bif, bif::BifError, compiled::compile_file_cached,
+    compiled::CompiledTemplate, compiled::SourceOrigin, constants::*, utils::*, Value,
+};
+use std::sync::Arc;
 
 impl<'a> Bif<'a> {
     /*
         {:locale; file-path :}
     */
     pub(crate) fn parse_bif_locale(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            if !self.flags.contains("|require|")
-                && !self.flags.contains("|inline|")
-                && !self.flags.contains("|noparse|")
-            {
-                return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-            }
-        }
+        self.check_flags()?;
 
         if self.flags.contains("|inline|") {
             // Parse possible bifs included in json
@@ -57,36 +48,35 @@ impl<'a> Bif<'a> {
             self.file_path = new_child_parse!(self, &self.code, false);
         }
 
-        if let Some(stripped) = self.file_path.strip_prefix('#') {
-            self.file_path = format!("{}{}", self.inherit.current_dir, stripped);
-        }
-
-        let path = Path::new(&self.file_path);
-        if !Path::new(path).exists() {
-            if self.flags.contains("|require|") {
-                return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND));
-            } else {
-                return Ok(());
+        let canonical_path = match self.resolve_file(true)? {
+            Some(canonical_path) => canonical_path,
+            None => {
+                if self.flags.contains("|require|") {
+                    return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND));
+                } else {
+                    return Ok(());
+                }
             }
-        }
+        };
 
-        let canonical_path = fs::canonicalize(path)
-            .unwrap()
-            .to_string_lossy()
-            .into_owned();
         if self.mod_negate && self.inherit.locale_files.contains(&canonical_path) {
             self.out = UNPRINTABLE.to_string();
 
             return Ok(());
         }
 
+        let loader = Arc::clone(&self.shared.loader);
+        let compiled = compile_file_cached(loader.as_ref(), &canonical_path, false)
+            .unwrap_or_else(|_| Arc::new(CompiledTemplate::new("")));
         self.inherit.locale_files.push(canonical_path);
-        let mut file_raw = fs::read_to_string(&self.file_path).unwrap_or("".to_string());
+        let mut file_raw = compiled.source().to_string();
 
         if !self.flags.contains("|noparse|") {
             // Parse possible bifs included in json
             if file_raw.contains(BIF_OPEN) {
-                file_raw = new_child_parse!(self, &file_raw, false);
+                self.child_compiled = Some(Arc::clone(&compiled));
+                self.child_origin = Some(SourceOrigin::new(Arc::clone(&compiled), 0));
+                file_raw = new_child_parse!(self, compiled.source(), false);
             }
         }
 
@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-moveto.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, json};
+use crate::{bif::Bif, bif::BifError, constants::*, json};
 use md5::{Digest, Md5};
 
 impl<'a> Bif<'a> {
@@ -9,10 +9,6 @@ impl<'a> Bif<'a> {
         {:moveto; </tag >> ... :}
     */
     pub(crate) fn parse_bif_moveto(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         if self.inherit.in_cache {
             self.out = format!("{}{}{}", "{:!cache;", self.raw.to_string(), ":}");
         } else {
@@ -21,9 +17,7 @@ impl<'a> Bif<'a> {
 
         self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if self.code.contains(BIF_OPEN) {
             self.code = new_child_parse!(self, &self.code, self.mod_scope);
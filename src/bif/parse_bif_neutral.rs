@@ -1,6 +1,6 @@
 #![doc = include_str!("../../doc/bif-neutral.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError};
+use crate::{bif::Bif, bif::BifError};
 
 /*
     {:neutral; ... :}
@@ -8,10 +8,6 @@ use crate::{bif::constants::*, bif::Bif, bif::BifError};
 
 impl<'a> Bif<'a> {
     pub(crate) fn parse_bif_neutral(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.out = self.raw.to_string();
 
         Ok(())
@@ -1,24 +1,30 @@
 #![doc = include_str!("../../doc/bif-obj.md")]
 
 use crate::{
-    bif::{constants::*, Bif, BifError, PhpExecutor, PythonExecutor},
+    bif::{constants::*, Bif, BifError},
     constants::*,
-    utils::{is_empty_key, resolve_pointer},
+    utils::is_empty_key,
     Value,
 };
+use std::borrow::Cow;
 use std::fs;
 use std::path::Path;
 
+#[cfg(feature = "obj-php")]
+use crate::bif::PhpExecutor;
+#[cfg(feature = "obj-python")]
+use crate::bif::PythonExecutor;
+
+// Runs the script of an obj with an engine.
+type ObjExec<'a> =
+    fn(&Bif<'a>, &Value, &str, Option<&Value>, Option<&Value>) -> Result<Value, BifError>;
+
 impl<'a> Bif<'a> {
     /*
         {:obj; ...  :}
         {:obj; {:flags; inline :} --- >>  <div>...</div>  :}
     */
     pub(crate) fn parse_bif_obj(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         let mut added_bif_code = false;
         if !self.src.contains(BIF_CODE) {
             self.src.push_str(BIF_CODE);
@@ -31,9 +37,8 @@ impl<'a> Bif<'a> {
             self.src.truncate(self.src.len() - BIF_CODE.len());
         }
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.extract_flags();
+        self.check_flags()?;
 
         let obj_raw;
         if self.params.starts_with('{')
@@ -58,16 +63,15 @@ impl<'a> Bif<'a> {
                 self.file_path = new_child_parse!(self, &self.params, false);
             }
 
-            if let Some(stripped) = self.file_path.strip_prefix('#') {
-                self.file_path = format!("{}{}", self.inherit.current_dir, stripped);
-            }
-
-            let path = Path::new(&self.file_path);
-            if !path.exists() {
-                return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND));
-            }
+            let canonical_path = match self.resolve_file(false)? {
+                Some(canonical_path) => canonical_path,
+                None => return Err(self.bif_error(BIF_ERROR_FILE_NOT_FOUND)),
+            };
 
-            obj_raw = fs::read_to_string(&self.file_path)
+            obj_raw = self
+                .shared
+                .loader
+                .read(&canonical_path)
                 .map_err(|e| self.bif_error(&format!("Failed to read file: {}", e)))?;
         }
 
@@ -81,16 +85,20 @@ impl<'a> Bif<'a> {
         if engine != "python" && engine != "php" {
             return Err(self.bif_error(BIF_ERROR_ONLY_PYTHON_ENGINE));
         }
+        let Some(exec) = Self::obj_exec(&engine) else {
+            return Err(self.bif_error(&engine_not_compiled(&engine)));
+        };
 
         if !self.flags.contains("|inline|") {
             self.parse_obj_values(&mut obj, false);
         }
 
-        let mut file_path_obj = obj["file"].as_str().unwrap_or("").to_string();
-
-        if let Some(stripped) = file_path_obj.strip_prefix('#') {
-            file_path_obj = format!("{}{}", self.inherit.current_dir, stripped);
-        }
+        // The script is run by the interpreter, it is always in the filesystem.
+        let file_path_obj = obj["file"].as_str().unwrap_or("");
+        let mut file_path_obj = self
+            .shared
+            .loader
+            .resolve(&self.inherit.current_dir, file_path_obj);
 
         if !Path::new(&file_path_obj).exists() {
             return Err(self.bif_error(BIF_ERROR_OBJ_FILE_NOT_FOUND));
@@ -100,103 +108,30 @@ impl<'a> Bif<'a> {
             .to_string_lossy()
             .into_owned();
 
+        file_path_obj = self.check_allowed_path(file_path_obj)?;
+
         let schema_data = obj
             .get("schema_data")
             .and_then(|v| v.as_str())
             .map(|schema_data_name| {
-                let (schema_root, key_name) = if schema_data_name.starts_with("local::") {
-                    (
-                        &self.shared.get_indir(&self.inherit.indir)["data"],
-                        schema_data_name.strip_prefix("local::").unwrap_or(""),
-                    )
-                } else {
-                    (&self.shared.schema["data"], schema_data_name)
-                };
-
-                resolve_pointer(schema_root, key_name)
-                    .cloned()
+                self.data_value(schema_data_name)
+                    .map(Cow::into_owned)
                     .unwrap_or(Value::Null)
             });
 
         let schema = if obj.get("schema").and_then(|v| v.as_bool()).unwrap_or(false) {
-            Some(&self.shared.schema)
+            self.shared.schema_value("")
         } else {
             None
         };
 
-        let default_python_venv = self.shared.schema["config"]["obj_python_venv"]
-            .as_str()
-            .unwrap_or("");
-        let default_php_venv = self.shared.schema["config"]["obj_php_venv"]
-            .as_str()
-            .unwrap_or("");
-        let default_php_fpm = self.shared.schema["config"]["obj_php_fpm"]
-            .as_str()
-            .unwrap_or("unix:/run/php/php-fpm.sock");
-
-        let result = if engine == "python" {
-            let mut venv_path = obj["venv"]
-                .as_str()
-                .unwrap_or(default_python_venv)
-                .to_string();
-
-            if !venv_path.is_empty() {
-                if let Some(stripped) = venv_path.strip_prefix('#') {
-                    venv_path = format!("{}{}", self.inherit.current_dir, stripped);
-                }
-
-                if !Path::new(&venv_path).exists() {
-                    return Err(self.bif_error("venv path does not exist"));
-                }
-            }
-
-            PythonExecutor::exec_py(
-                &file_path_obj,
-                &obj["params"],
-                obj["callback"].as_str().unwrap_or(DEFAULT_OBJ_CALLBACK),
-                schema,
-                schema_data.as_ref(),
-                if venv_path.is_empty() {
-                    None
-                } else {
-                    Some(venv_path.as_str())
-                },
-            )
-            .map_err(|e| self.bif_error(&e.msg))?
-        } else {
-            let mut php_venv_path = obj["venv"]
-                .as_str()
-                .unwrap_or(default_php_venv)
-                .to_string();
-            if !php_venv_path.is_empty() {
-                if let Some(stripped) = php_venv_path.strip_prefix('#') {
-                    php_venv_path = format!("{}{}", self.inherit.current_dir, stripped);
-                }
-                if !Path::new(&php_venv_path).exists() {
-                    return Err(self.bif_error("venv path does not exist"));
-                }
-            }
-
-            let mut fpm_endpoint = obj["fpm"].as_str().unwrap_or(default_php_fpm).to_string();
-            if let Some(stripped) = fpm_endpoint.strip_prefix('#') {
-                fpm_endpoint = format!("{}{}", self.inherit.current_dir, stripped);
-            }
-
-            PhpExecutor::exec_php(
-                &file_path_obj,
-                &obj["params"],
-                obj["callback"].as_str().unwrap_or(DEFAULT_OBJ_CALLBACK),
-                schema,
-                schema_data.as_ref(),
-                if php_venv_path.is_empty() {
-                    None
-                } else {
-                    Some(php_venv_path.as_str())
-                },
-                &fpm_endpoint,
-            )
-            .map_err(|e| self.bif_error(&e.msg))?
-        };
+        let result = exec(
+            self,
+            &obj,
+            &file_path_obj,
+            schema.as_deref(),
+            schema_data.as_ref(),
+        )?;
 
         let mut code = String::new();
         if !is_empty_key(&result, "data") {
@@ -218,6 +153,104 @@ impl<'a> Bif<'a> {
         Ok(())
     }
 
+    // The executor of a supported engine, None if the crate is built without it.
+    fn obj_exec(engine: &str) -> Option<ObjExec<'a>> {
+        match engine {
+            #[cfg(feature = "obj-python")]
+            "python" => Some(Self::exec_obj_python),
+            #[cfg(feature = "obj-php")]
+            "php" => Some(Self::exec_obj_php),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "obj-python")]
+    fn exec_obj_python(
+        &self,
+        obj: &Value,
+        file_path_obj: &str,
+        schema: Option<&Value>,
+        schema_data: Option<&Value>,
+    ) -> Result<Value, BifError> {
+        let default_python_venv = self.shared.schema["config"]["obj_python_venv"]
+            .as_str()
+            .unwrap_or("");
+        let mut venv_path = obj["venv"]
+            .as_str()
+            .unwrap_or(default_python_venv)
+            .to_string();
+
+        if !venv_path.is_empty() {
+            if let Some(stripped) = venv_path.strip_prefix('#') {
+                venv_path = format!("{}{}", self.inherit.current_dir, stripped);
+            }
+
+            if !Path::new(&venv_path).exists() {
+                return Err(self.bif_error("venv path does not exist"));
+            }
+        }
+
+        PythonExecutor::exec_py(
+            file_path_obj,
+            &obj["params"],
+            obj["callback"].as_str().unwrap_or(DEFAULT_OBJ_CALLBACK),
+            schema,
+            schema_data,
+            if venv_path.is_empty() {
+                None
+            } else {
+                Some(venv_path.as_str())
+            },
+        )
+        .map_err(|e| self.bif_error(&e.msg))
+    }
+
+    #[cfg(feature = "obj-php")]
+    fn exec_obj_php(
+        &self,
+        obj: &Value,
+        file_path_obj: &str,
+        schema: Option<&Value>,
+        schema_data: Option<&Value>,
+    ) -> Result<Value, BifError> {
+        let default_php_venv = self.shared.schema["config"]["obj_php_venv"]
+            .as_str()
+            .unwrap_or("");
+        let default_php_fpm = self.shared.schema["config"]["obj_php_fpm"]
+            .as_str()
+            .unwrap_or("unix:/run/php/php-fpm.sock");
+
+        let mut php_venv_path = obj["venv"].as_str().unwrap_or(default_php_venv).to_string();
+        if !php_venv_path.is_empty() {
+            if let Some(stripped) = php_venv_path.strip_prefix('#') {
+                php_venv_path = format!("{}{}", self.inherit.current_dir, stripped);
+            }
+            if !Path::new(&php_venv_path).exists() {
+                return Err(self.bif_error("venv path does not exist"));
+            }
+        }
+
+        let mut fpm_endpoint = obj["fpm"].as_str().unwrap_or(default_php_fpm).to_string();
+        if let Some(stripped) = fpm_endpoint.strip_prefix('#') {
+            fpm_endpoint = format!("{}{}", self.inherit.current_dir, stripped);
+        }
+
+        PhpExecutor::exec_php(
+            file_path_obj,
+            &obj["params"],
+            obj["callback"].as_str().unwrap_or(DEFAULT_OBJ_CALLBACK),
+            schema,
+            schema_data,
+            if php_venv_path.is_empty() {
+                None
+            } else {
+                Some(php_venv_path.as_str())
+            },
+            &fpm_endpoint,
+        )
+        .map_err(|e| self.bif_error(&e.msg))
+    }
+
     fn parse_obj_values(&mut self, value: &mut Value, is_recursive_call: bool) {
         if let Value::Object(map) = value {
             for (key, val) in map.iter_mut() {
@@ -247,6 +280,11 @@ impl<'a> Bif<'a> {
     }
 }
 
+// The engine is supported, but the crate is built without its feature.
+fn engine_not_compiled(engine: &str) -> String {
+    format!("{}: {}", BIF_ERROR_ENGINE_NOT_COMPILED, engine)
+}
+
 #[cfg(test)]
 #[path = "parse_bif_obj_tests.rs"]
 mod tests;
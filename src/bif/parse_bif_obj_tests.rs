@@ -1,18 +1,27 @@
 #[cfg(test)]
 mod tests {
     use crate::test_helpers::*;
-    use std::env;
+    #[cfg(any(feature = "obj-python", feature = "obj-php"))]
     use std::fs;
-    use std::net::{TcpStream, ToSocketAddrs};
-    use std::os::unix::net::UnixStream;
-    use std::path::Path;
+    #[cfg(any(feature = "obj-python", feature = "obj-php"))]
     use std::process;
-    use std::time::{Duration, SystemTime, UNIX_EPOCH};
-
+    #[cfg(any(feature = "obj-python", feature = "obj-php"))]
+    use std::time::{SystemTime, UNIX_EPOCH};
+    #[cfg(feature = "obj-php")]
+    use {
+        std::env,
+        std::net::{TcpStream, ToSocketAddrs},
+        std::os::unix::net::UnixStream,
+        std::path::Path,
+        std::time::Duration,
+    };
+
+    #[cfg(feature = "obj-php")]
     fn set_php_src(template: &mut crate::Template, src: &str, fpm_endpoint: &str) {
         template.set_src_str(&src.replace("__FPM__", fpm_endpoint));
     }
 
+    #[cfg(feature = "obj-php")]
     fn set_php_src_with_script(
         template: &mut crate::Template,
         src: &str,
@@ -25,10 +34,12 @@ mod tests {
         template.set_src_str(&src);
     }
 
+    #[cfg(feature = "obj-python")]
     fn set_python_src_with_script(template: &mut crate::Template, src: &str, script_path: &str) {
         template.set_src_str(&src.replace("__SCRIPT__", script_path));
     }
 
+    #[cfg(feature = "obj-php")]
     fn can_connect_php_fpm(endpoint: &str) -> bool {
         if let Some(path) = endpoint.strip_prefix("unix:") {
             return UnixStream::connect(path).is_ok();
@@ -47,6 +58,7 @@ mod tests {
         false
     }
 
+    #[cfg(feature = "obj-php")]
     fn default_obj_php_fpm() -> String {
         serde_json::from_str::<crate::Value>(crate::DEFAULT)
             .ok()
@@ -58,6 +70,7 @@ mod tests {
             .unwrap_or_else(|| "unix:/run/php/php-fpm.sock".to_string())
     }
 
+    #[cfg(feature = "obj-php")]
     fn php_fpm_test_endpoint() -> Option<String> {
         if let Ok(value) = env::var("NEUTRALTS_TEST_PHP_FPM") {
             let value = value.trim();
@@ -85,6 +98,7 @@ mod tests {
         None
     }
 
+    #[cfg(feature = "obj-php")]
     fn php_fpm_test_endpoint_or_skip(test_name: &str) -> Option<String> {
         match php_fpm_test_endpoint() {
             Some(endpoint) => Some(endpoint),
@@ -99,6 +113,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "obj-php")]
     fn create_php_test_script() -> String {
         let nanos = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -131,6 +146,7 @@ function main($params = []) {
         path
     }
 
+    #[cfg(feature = "obj-python")]
     fn create_schema_data_test_script() -> String {
         let nanos = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -165,10 +181,12 @@ function main($params = []) {
         path
     }
 
+    #[cfg(any(feature = "obj-python", feature = "obj-php"))]
     fn remove_test_script(path: &str) {
         let _ = fs::remove_file(path);
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj() {
         let mut template = match crate::Template::new() {
@@ -189,6 +207,7 @@ function main($params = []) {
         assert_eq!(result, "<div>Hello from Python!</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_no_scope() {
         let mut template = match crate::Template::new() {
@@ -209,6 +228,7 @@ function main($params = []) {
         assert_eq!(result, "<div></div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_scope() {
         let mut template = match crate::Template::new() {
@@ -229,6 +249,7 @@ function main($params = []) {
         assert_eq!(result, "<div>Hello from Python!</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_objfile() {
         let mut template = match crate::Template::new() {
@@ -282,6 +303,7 @@ function main($params = []) {
         template.set_src_str("<div>{:obj; {:flg; unknown :} { \"file\": \"tests/script.py\" } >> {:;local::py_hello:} :}</div>");
         let result = template.render();
         assert!(template.has_error());
+        assert_eq!(template.get_errors()[0].message, "flags not allowed");
         assert_eq!(result, "<div></div>");
     }
 
@@ -361,6 +383,7 @@ function main($params = []) {
         assert_eq!(result, "<div></div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_false() {
         let mut template = match crate::Template::new() {
@@ -380,12 +403,14 @@ function main($params = []) {
     }
 
     // https://github.com/FranBarInstance/neutralts/issues/2
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_true_first() {
         test_bif_obj_schema_true();
         test_bif_obj_schema_false();
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_true() {
         let mut template = match crate::Template::new() {
@@ -422,6 +447,7 @@ function main($params = []) {
         assert_eq!(result, "<div></div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_template_integration() {
         let mut template = match crate::Template::new() {
@@ -443,6 +469,7 @@ function main($params = []) {
         );
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_with_params() {
         let mut template = match crate::Template::new() {
@@ -461,6 +488,7 @@ function main($params = []) {
         assert_eq!(result, "<div>nts</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_data_scalar() {
         let script_path = create_schema_data_test_script();
@@ -487,6 +515,7 @@ function main($params = []) {
         assert_eq!(result, "<div>scalar|nts</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_data_list_global() {
         let script_path = create_schema_data_test_script();
@@ -513,6 +542,7 @@ function main($params = []) {
         assert_eq!(result, "<div>list</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_data_nested_global_scalar() {
         let script_path = create_schema_data_test_script();
@@ -539,6 +569,7 @@ function main($params = []) {
         assert_eq!(result, "<div>scalar|Ok</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_data_dict_local() {
         let script_path = create_schema_data_test_script();
@@ -565,6 +596,7 @@ function main($params = []) {
         assert_eq!(result, "<div>dict</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_data_nested_local_scalar() {
         let script_path = create_schema_data_test_script();
@@ -591,6 +623,7 @@ function main($params = []) {
         assert_eq!(result, "<div>scalar|Consectetur adipiscing elit.</div>");
     }
 
+    #[cfg(feature = "obj-python")]
     #[test]
     fn test_bif_obj_schema_data_missing_is_none() {
         let script_path = create_schema_data_test_script();
@@ -617,6 +650,7 @@ function main($params = []) {
         assert_eq!(result, "<div>none|true</div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php_exec_conditional() {
         let Some(fpm_endpoint) = php_fpm_test_endpoint_or_skip("test_bif_obj_php_exec_conditional")
@@ -649,6 +683,7 @@ function main($params = []) {
         assert_eq!(result, "<div>Hello from PHP!|yes|scalar</div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php() {
         let Some(fpm_endpoint) = php_fpm_test_endpoint_or_skip("test_bif_obj_php") else {
@@ -675,6 +710,7 @@ function main($params = []) {
         assert_eq!(result, "<div>Hello from PHP!</div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php_no_scope() {
         let Some(fpm_endpoint) = php_fpm_test_endpoint_or_skip("test_bif_obj_php_no_scope") else {
@@ -701,6 +737,7 @@ function main($params = []) {
         assert_eq!(result, "<div></div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php_scope() {
         let Some(fpm_endpoint) = php_fpm_test_endpoint_or_skip("test_bif_obj_php_scope") else {
@@ -727,6 +764,7 @@ function main($params = []) {
         assert_eq!(result, "<div>Hello from PHP!</div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php_schema_false() {
         let Some(fpm_endpoint) = php_fpm_test_endpoint_or_skip("test_bif_obj_php_schema_false")
@@ -754,6 +792,7 @@ function main($params = []) {
         assert_eq!(result, "<div></div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php_schema_true() {
         let Some(fpm_endpoint) = php_fpm_test_endpoint_or_skip("test_bif_obj_php_schema_true")
@@ -781,6 +820,7 @@ function main($params = []) {
         assert_eq!(result, "<div>nts</div>");
     }
 
+    #[cfg(feature = "obj-php")]
     #[test]
     fn test_bif_obj_php_with_params() {
         // let Some(fpm_endpoint) = php_fpm_test_endpoint() else {
@@ -810,4 +850,38 @@ function main($params = []) {
         assert!(!template.has_error());
         assert_eq!(result, "<div>nts</div>");
     }
+
+    #[cfg(not(feature = "obj-python"))]
+    #[test]
+    fn test_bif_obj_python_not_compiled() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:obj; {\"engine\":\"python\",\"file\":\"tests/script.py\"} >> :}</div>",
+        );
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(
+            template.get_errors()[0].message,
+            "engine not compiled in: python"
+        );
+    }
+
+    #[cfg(not(feature = "obj-php"))]
+    #[test]
+    fn test_bif_obj_php_not_compiled() {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(
+            "<div>{:obj; {\"engine\":\"php\",\"file\":\"tests/script.php\"} >> :}</div>",
+        );
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert_eq!(
+            template.get_errors()[0].message,
+            "engine not compiled in: php"
+        );
+    }
 }
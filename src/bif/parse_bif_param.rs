@@ -8,15 +8,9 @@ impl<'a> Bif<'a> {
         Set param:  {:param; param-name >> content to set :}
     */
     pub(crate) fn parse_bif_param(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         let is_set = self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if is_set {
             if self.inherit.alias == "code" {
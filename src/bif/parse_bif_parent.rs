@@ -0,0 +1,21 @@
+#![doc = include_str!("../../doc/bif-block.md")]
+
+use crate::{bif::constants::*, bif::Bif, bif::BifError};
+
+impl<'a> Bif<'a> {
+    /*
+        Parent content of a block:
+        {:block; name >> ... {:parent; :} ... :}
+    */
+    pub(crate) fn parse_bif_parent(&mut self) -> Result<(), BifError> {
+        let mut level = match self.inherit.block_level.clone() {
+            Some(level) => level,
+            None => return Err(self.bif_error(BIF_ERROR_PARENT_OUTSIDE_BLOCK)),
+        };
+
+        level.level += 1;
+        self.out = self.parse_block_level(level);
+
+        Ok(())
+    }
+}
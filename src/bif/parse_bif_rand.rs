@@ -9,10 +9,6 @@ impl<'a> Bif<'a> {
         {:rand; 1..100 :}
     */
     pub(crate) fn parse_bif_rand(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         let mut rng = rand::rng();
         self.code = self.src.trim().to_string();
 
@@ -8,10 +8,6 @@ impl<'a> Bif<'a> {
         {:redirect; js:reload:top >> (none) :}
     */
     pub(crate) fn parse_bif_redirect(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope || self.mod_negate {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         if self.inherit.in_cache {
             self.out = format!("{}{}{}", "{:!cache;", self.raw.to_string(), ":}");
         } else {
@@ -21,9 +17,7 @@ impl<'a> Bif<'a> {
         let status_code;
         let has_status_params = self.extract_params_code(true);
 
-        if !self.flags.is_empty() {
-            return Err(self.bif_error(BIF_ERROR_FLAGS_NOT_ALLOWED));
-        }
+        self.check_flags()?;
 
         if self.code.contains(BIF_OPEN) {
             self.code = new_child_parse!(self, &self.code, false);
@@ -8,10 +8,6 @@ impl<'a> Bif<'a> {
         /from/to/, ~from~to~, |from|to|, ...
     */
     pub(crate) fn parse_bif_replace(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(false);
 
         if self.params.contains("{:flg;") {
@@ -7,10 +7,6 @@ impl<'a> Bif<'a> {
         {:same; /a/b/ >> ... :}
     */
     pub(crate) fn parse_bif_same(&mut self) -> Result<(), BifError> {
-        if self.mod_filter {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.extract_params_code(false);
 
         if self.params.contains("{:flg;") {
@@ -1,7 +1,9 @@
 #![doc = include_str!("../../doc/bif-snippet.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*, json, utils::*};
-use std::collections::HashSet;
+use crate::{
+    bif::Bif, bif::BifError, compiled::compile_str_cached, constants::*, json, utils::*, Value,
+};
+use serde_json::Map;
 
 impl<'a> Bif<'a> {
     /*
@@ -12,81 +14,220 @@ impl<'a> Bif<'a> {
         {:snippet; snippet-name >>
             content to set
         :}
+
+        Set snippet with arguments, optional defaults:
+        {:snippet; snippet-name title body="default" >>
+            {:;local::title:} {:;local::body:}
+        :}
+
+        Play snippet with arguments:
+        {:snippet; snippet-name >> title=... body="..." :}
     */
     pub(crate) fn parse_bif_snippet(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
+        self.alias = "snippet".to_string();
+
+        let is_set = self.extract_params_code(false);
+
+        // The declared arguments are not parsed with the params, each
+        // default value is parsed on its own.
+        let (params, declared) = split_declared(&self.params);
+        let declared = declared.to_string();
+        self.params = params.to_string();
+        if self.params.contains(BIF_OPEN) {
+            self.shared.flags = EMPTY_STRING;
+            self.params = new_child_parse!(self, &self.params, false);
+            self.flags = self.shared.flags.clone();
         }
 
-        self.alias = "snippet".to_string();
+        self.check_flags()?;
 
-        let is_set = self.extract_params_code(true);
+        if !is_set {
+            // parse snippet name if need
+            if self.code.contains(BIF_OPEN) {
+                self.code = new_child_parse!(self, &self.code, false);
+            }
+            let snip_name = self.code.clone();
 
-        if !self.flags.is_empty() {
-            let flags_allowed: HashSet<&str> = ["static"].into_iter().collect();
+            return self.play_snippet(&snip_name, "");
+        }
 
-            for f in self.flags.split('|').filter(|s| !s.is_empty()) {
-                if !flags_allowed.contains(f) {
-                    return Err(self.bif_error(&format!("{} flag not allowed", f)));
-                }
+        let snip_name = self.params.clone();
+
+        // A snippet with arguments is played with them, not set again.
+        if declared.is_empty() && self.snippet_args(&snip_name).is_some() {
+            let args = self.code.clone();
+
+            return self.play_snippet(&snip_name, &args);
+        }
+
+        // Set snippets in snippet files and inside snippets
+        if !self.inherit.current_file.contains(SNIPPETS_FILES) && self.inherit.alias != "snippet" {
+            return Err(self.bif_error("snippet cannot be set here"));
+        }
+
+        let mut defaults = Map::new();
+        for (name, value) in split_named_args(&declared).map_err(|e| self.bif_error(&e))? {
+            let mut value = value.unwrap_or_default();
+
+            // Defaults are parsed where the snippet is set.
+            if value.contains(BIF_OPEN) {
+                value = new_child_parse!(self, &value, false);
             }
+            defaults.insert(name, json!(value));
         }
 
-        if is_set {
-            // Set snippets in snippet files and inside snippets
-            if self.inherit.current_file.contains(SNIPPETS_FILES) || self.inherit.alias == "snippet"
-            {
-                if self.flags.contains("|static|") {
-                    self.code = new_child_parse!(self, &self.code, self.mod_scope);
-                } else {
-                    // required regardless of mod_scope or static
-                    self.inherit.create_block_schema(self.shared);
-                }
-                self.shared.get_indir_mut(&self.inherit.indir)["snippets"][&self.params] =
-                    json!(&self.code);
+        if self.flags.contains("|static|") {
+            self.code = new_child_parse!(self, &self.code, self.mod_scope);
+        } else {
+            // required regardless of mod_scope or static
+            self.inherit.create_block_schema(self.shared);
+
+            // Where it is set, to report the errors inside the snippet.
+            if let Some(origin) = self.origin_for(&self.code) {
+                self.shared
+                    .snippet_origins
+                    .insert(snip_name.clone(), origin);
+            }
+        }
 
-                // The directory inside the snippet is that of the template that created it.
-                self.shared.get_indir_mut(&self.inherit.indir)["snippets_set_dir"]
-                    [&self.params] = json!(&self.inherit.current_dir);
+        let indir = self.shared.get_indir_mut(&self.inherit.indir);
+        indir["snippets"][&snip_name] = json!(&self.code);
 
-                self.out = EMPTY_STRING;
+        // The directory inside the snippet is that of the template that created it.
+        indir["snippets_set_dir"][&snip_name] = json!(&self.inherit.current_dir);
 
-                Ok(())
-            } else {
-                return Err(self.bif_error("snippet cannot be set here"));
+        if declared.is_empty() {
+            if let Some(args) = indir["snippets_args"].as_object_mut() {
+                args.remove(&snip_name);
             }
         } else {
-            // parse snippet name if need
-            if self.code.contains(BIF_OPEN) {
-                self.code = new_child_parse!(self, &self.code, false);
+            indir["snippets_args"][&snip_name] = Value::Object(defaults);
+        }
+
+        self.out = EMPTY_STRING;
+
+        Ok(())
+    }
+
+    // Arguments and defaults of a snippet, None if it has no arguments.
+    fn snippet_args(&self, snip_name: &str) -> Option<Map<String, Value>> {
+        self.shared.get_indir(&self.inherit.indir)["snippets_args"][snip_name]
+            .as_object()
+            .cloned()
+    }
+
+    fn play_snippet(&mut self, snip_name: &str, call_args: &str) -> Result<(), BifError> {
+        let mut args = self.snippet_args(snip_name).unwrap_or_default();
+
+        for (name, value) in split_named_args(call_args).map_err(|e| self.bif_error(&e))? {
+            if !args.contains_key(&name) {
+                return Err(self.bif_error(&format!("unknown argument: {}", name)));
             }
-            let snip_name = self.code.clone();
 
-            self.code = get_from_key(
-                &self.shared.get_indir(&self.inherit.indir)["snippets"],
-                &self.code,
+            let Some(mut value) = value else {
+                return Err(self.bif_error(&format!("argument without value: {}", name)));
+            };
+
+            // Arguments are parsed where the snippet is played.
+            if value.contains(BIF_OPEN) {
+                value = new_child_parse!(self, &value, false);
+            }
+            args.insert(name, json!(value));
+        }
+
+        self.code = get_from_key(
+            &self.shared.get_indir(&self.inherit.indir)["snippets"],
+            snip_name,
+        );
+
+        if self.code.contains(BIF_OPEN) {
+            // The directory inside the snippet is that of the template that created it.
+            let set_dir = get_from_key(
+                &self.shared.get_indir(&self.inherit.indir)["snippets_set_dir"],
+                snip_name,
             );
 
-            if self.code.contains(BIF_OPEN) {
-                // The directory inside the snippet is that of the template that created it.
-                let set_dir = get_from_key(
-                    &self.shared.get_indir(&self.inherit.indir)["snippets_set_dir"],
-                    &snip_name,
-                );
-
-                if !set_dir.is_empty() {
-                    self.inherit.current_dir = set_dir;
-                }
+            if !set_dir.is_empty() {
+                self.inherit.current_dir = set_dir;
+            }
+
+            // Snippets are compiled on first use.
+            self.child_compiled = Some(compile_str_cached(&self.code));
+            self.child_origin = self.shared.snippet_origins.get(snip_name).cloned();
+
+            // The arguments are local data only while the snippet is parsed.
+            let prev_locals = self.set_locals(args);
+
+            // auto mod_scope in snippets for snippets inside snippets
+            self.code = new_child_parse!(self, &self.code, self.code.contains("{:snip"));
+
+            self.set_locals(prev_locals);
+        }
 
-                // auto mod_scope in snippets for snippets inside snippets
-                self.code = new_child_parse!(self, &self.code, self.code.contains("{:snip"));
+        self.out = self.code.to_string();
+
+        Ok(())
+    }
+
+    // Sets local data and returns the previous values, null if they did not exist.
+    fn set_locals(&mut self, locals: Map<String, Value>) -> Map<String, Value> {
+        let mut prev = Map::new();
+
+        if locals.is_empty() {
+            return prev;
+        }
+
+        self.inherit.create_block_schema(self.shared);
+        let data = &mut self.shared.get_indir_mut(&self.inherit.indir)["data"];
+
+        for (name, value) in locals {
+            let old = data.get(&name).cloned().unwrap_or(Value::Null);
+            match (&value, data.as_object_mut()) {
+                (Value::Null, Some(data)) => {
+                    data.remove(&name);
+                }
+                _ => data[&name] = value,
             }
+            prev.insert(name, old);
+        }
 
-            self.out = self.code.to_string();
+        prev
+    }
+}
 
-            Ok(())
+// Splits "{:flg; ... :} snippet-name title body=..." into the flags with the
+// name and the declared arguments, at the first whitespace outside of bifs
+// after the name has started.
+fn split_declared(params: &str) -> (&str, &str) {
+    let mut level = 0;
+    let mut has_name = false;
+    let mut i = 0;
+
+    while i < params.len() {
+        let part = &params[i..];
+        if part.starts_with(BIF_OPEN) {
+            if level == 0 && !part.starts_with("{:flg;") {
+                has_name = true;
+            }
+            level += 1;
+            i += BIF_OPEN.len();
+        } else if part.starts_with(BIF_CLOSE) && level > 0 {
+            level -= 1;
+            i += BIF_CLOSE.len();
+        } else {
+            let c = part.chars().next().unwrap_or_default();
+            if level == 0 && c.is_whitespace() {
+                if has_name {
+                    return (params[..i].trim(), params[i..].trim());
+                }
+            } else if level == 0 {
+                has_name = true;
+            }
+            i += c.len_utf8();
         }
     }
+
+    (params.trim(), "")
 }
 
 #[cfg(test)]
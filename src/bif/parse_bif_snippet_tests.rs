@@ -164,4 +164,97 @@ mod tests {
         assert!(template.has_error());
         assert_eq!(result, "<div></div>");
     }
+
+    fn new_template_args() -> crate::Template {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template
+    }
+
+    #[test]
+    fn test_bif_snippet_args() {
+        let mut template = new_template_args();
+        template.set_src_str(
+            "{:include; tests/snippets-args.ntpl :}{:snippet; card >> title={:;__test-nts:} body=\"Hello {:;__test-nts:}\" :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(
+            result,
+            "<div class=\"card\"><h2>nts</h2><p>Hello nts</p></div>"
+        );
+    }
+
+    #[test]
+    fn test_bif_snippet_args_defaults() {
+        let mut template = new_template_args();
+        template.set_src_str("{:include; tests/snippets-args.ntpl :}{:snippet; card :}");
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "<div class=\"card\"><h2></h2><p>No body</p></div>");
+    }
+
+    #[test]
+    fn test_bif_snippet_args_defaults_with_spaces() {
+        let mut template = new_template_args();
+        template.set_src_str(
+            "{:include; tests/snippets-args.ntpl :}{:snippet; card-hello >> title=T :}",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, "[T|Hello nts]");
+    }
+
+    #[test]
+    fn test_bif_snippet_args_nested() {
+        let mut template = new_template_args();
+        template.set_src_str(
+            "{:include; tests/snippets-args.ntpl :}{:snippet; card-list >> title=List :}[{:;local::title:}]",
+        );
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(
+            result,
+            "<div class=\"card\"><h2>List 1</h2><p>No body</p></div><div class=\"card\"><h2>List 2</h2><p>Two</p></div>[]"
+        );
+    }
+
+    #[test]
+    fn test_bif_snippet_args_unknown() {
+        let mut template = new_template_args();
+        template.set_src_str(
+            "<div>{:include; tests/snippets-args.ntpl :}{:snippet; card >> name=none :}</div>",
+        );
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_bif_snippet_args_not_set_here() {
+        let mut template = new_template_args();
+        template.set_src_str("<div>{:snippet; card title >> {:;local::title:} :}</div>");
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+    }
+
+    #[test]
+    fn test_split_named_args() {
+        let args = crate::utils::split_named_args(
+            " title=\"Hello World\" body={:; text :} footer tag={:code; \" :}",
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                ("title".to_string(), Some("Hello World".to_string())),
+                ("body".to_string(), Some("{:; text :}".to_string())),
+                ("footer".to_string(), None),
+                ("tag".to_string(), Some("{:code; \" :}".to_string())),
+            ]
+        );
+        assert!(crate::utils::split_named_args("title=\"none").is_err());
+        assert!(crate::utils::split_named_args("=none").is_err());
+    }
 }
@@ -7,10 +7,6 @@ impl<'a> Bif<'a> {
        {:sum; /a/b/ :}
     */
     pub(crate) fn parse_bif_sum(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_negate || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         self.params = self.src.clone();
 
         if self.params.contains("{:flg;") {
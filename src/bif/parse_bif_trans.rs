@@ -1,16 +1,12 @@
 #![doc = include_str!("../../doc/bif-trans.md")]
 
-use crate::{bif::constants::*, bif::Bif, bif::BifError, constants::*};
+use crate::{bif::Bif, bif::BifError, constants::*};
 
 impl<'a> Bif<'a> {
     /*
        {:trans; ... :}
     */
     pub(crate) fn parse_bif_trans(&mut self) -> Result<(), BifError> {
-        if self.mod_filter || self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         // For performance, we avoid calling BlockParser::new if it is not necessary
         if self.src.contains(BIF_OPEN) {
             self.src = new_child_parse!(self, &self.src, self.mod_scope);
@@ -8,10 +8,6 @@ impl<'a> Bif<'a> {
         {:;:}
     */
     pub(crate) fn parse_bif_var(&mut self) -> Result<(), BifError> {
-        if self.mod_scope {
-            return Err(self.bif_error(BIF_ERROR_MODIFIER_NOT_ALLOWED));
-        }
-
         // Unprintable: {:;:} / {:; :}
         if self.src.is_empty() {
             // "bif.alias" is used and not "bif.name" because in "var" or "unprintable"
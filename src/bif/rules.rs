@@ -0,0 +1,97 @@
+//! The modifiers and flags that each bif accepts, the bifs check them when
+//! they are parsed and the checker without rendering, see `Template::check`.
+
+use crate::{bif::constants::*, compiled::Modifiers, constants::*};
+
+// The modifiers and flags each bif accepts. The upline modifier (^) is accepted
+// by all but flg. Flags are None when the bif checks them itself.
+pub(crate) const BIF_RULES: [(&str, &str, Option<&[&str]>); 44] = [
+    ("", "&!^", None),
+    ("allow", "!^", Some(&["partial", "replace", "casein"])),
+    ("array", "!^+", Some(&[])),
+    ("block", "^", Some(&[])),
+    ("bool", "!^+", Some(&[])),
+    ("cache", "!^", None),
+    ("coalesce", "^+", None),
+    (
+        "code",
+        "^+",
+        Some(&[
+            "safe",
+            "encode_tags",
+            "encode_bifs",
+            "noparse",
+            "encode_tags_after",
+        ]),
+    ),
+    ("contains", "!^+", Some(&[])),
+    ("cookie", "^", Some(&[])),
+    ("count", "^", None),
+    ("data", "!^", Some(&["require", "inline"])),
+    ("date", "^", Some(&[])),
+    ("debug", "&!^", None),
+    ("declare", "^", Some(&[])),
+    ("defined", "!^+", Some(&[])),
+    ("each", "^+", Some(&[])),
+    ("else", "!^+", Some(&[])),
+    ("eval", "!^+", Some(&[])),
+    ("exit", "!^", Some(&[])),
+    ("extends", "^", Some(&[])),
+    ("fetch", "^", Some(&[])),
+    ("filled", "!^+", Some(&[])),
+    ("flg", "", Some(&[])),
+    ("for", "^+", Some(&[])),
+    ("hash", "^", None),
+    ("header", "^", Some(&[])),
+    ("include", "!^", Some(&["require", "safe", "noparse"])),
+    ("join", "^", None),
+    ("lang", "^", None),
+    ("locale", "!^", Some(&["require", "inline", "noparse"])),
+    ("moveto", "^", Some(&[])),
+    ("neutral", "^", None),
+    ("obj", "^+", Some(&[])),
+    ("param", "^", Some(&[])),
+    ("parent", "^", None),
+    ("rand", "^", None),
+    ("redirect", "^", Some(&[])),
+    ("replace", "^", Some(&[])),
+    ("same", "!^+", Some(&[])),
+    ("snip", "^", Some(&["static"])),
+    ("snippet", "^", Some(&["static"])),
+    ("sum", "^", Some(&[])),
+    ("trans", "!^", None),
+];
+
+// The modifiers and flags accepted by a bif, None if it is not a bif of the
+// engine, e.g. a custom bif.
+pub(crate) fn bif_rules(name: &str) -> Option<(&'static str, Option<&'static [&'static str]>)> {
+    BIF_RULES
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, modifiers, flags)| (*modifiers, *flags))
+}
+
+pub(crate) fn modifiers_allowed(allowed: &str, modifiers: Modifiers) -> bool {
+    (!modifiers.filter || allowed.contains(BIF_MOD_FILTER))
+        && (!modifiers.negate || allowed.contains(BIF_MOD_NEGATE))
+        && (!modifiers.upline || allowed.contains(BIF_MOD_UPLINE))
+        && (!modifiers.scope || allowed.contains(BIF_MOD_SCOPE))
+}
+
+// The errors of the flags that are not allowed, one for each flag, or one
+// for all if the bif does not accept flags.
+pub(crate) fn flag_errors<'f>(
+    allowed: Option<&[&str]>,
+    flags: impl IntoIterator<Item = &'f str>,
+) -> Vec<String> {
+    let mut flags = flags.into_iter().filter(|f| !f.is_empty()).peekable();
+
+    match allowed {
+        Some([]) if flags.peek().is_some() => vec![BIF_ERROR_FLAGS_NOT_ALLOWED.to_string()],
+        Some(allowed) => flags
+            .filter(|f| !allowed.contains(f))
+            .map(|f| format!("{} flag not allowed", f))
+            .collect(),
+        None => Vec::new(),
+    }
+}
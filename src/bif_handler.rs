@@ -0,0 +1,170 @@
+//! Custom bifs.
+//!
+//! Bifs that are not built-in can be added to a `Template` or to an `Engine`
+//! with `register_bif`. When the bif is found in the template, its params
+//! are parsed, as in the built-in bifs, and the handler is called:
+//!
+//! ```text
+//!   {:price; {:;currency:} >> {:;amount:} :}
+//!      |           |               |
+//!      |           |               ·-------> ctx.code(), not parsed, ctx.parse_code()
+//!      |           ·-----------------------> ctx.params(), already parsed
+//!      ·-----------------------------------> registered name
+//! ```
+//!
+//! The handler returns the output of the bif, or an error message that is
+//! reported as any other bif error.
+//!
+//! # Example
+//!
+//! ```
+//! use neutralts::{BifContext, Template};
+//!
+//! let mut template = Template::new().unwrap();
+//! template
+//!     .register_bif("price", |ctx: &mut BifContext| {
+//!         let amount: f64 = ctx.parse_code().parse().map_err(|_| "not a number")?;
+//!         Ok(format!("{:.2} {}", amount, ctx.params()))
+//!     })
+//!     .unwrap();
+//! template.merge_schema_value(serde_json::json!({ "data": { "amount": "9.5" } }));
+//! template.set_src_str("{:price; EUR >> {:;amount:} :}");
+//! assert_eq!(template.render(), "9.50 EUR");
+//! ```
+
+use crate::{bif::Bif, compiled::Modifiers, constants::*};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Bifs that are not in BIF_LIST
+pub(crate) const BIF_LIST_EXTRA: [&str; 3] = ["snip", "obj", "debug"];
+
+pub(crate) type BifHandlers = Arc<HashMap<String, Arc<dyn BifHandler>>>;
+
+/// A custom bif, see `Template::register_bif`.
+///
+/// It is implemented for closures `Fn(&mut BifContext) -> Result<String, String>`.
+pub trait BifHandler: Send + Sync {
+    /// Parses the bif.
+    ///
+    /// # Returns
+    ///
+    /// The output of the bif, or an error message.
+    fn parse(&self, ctx: &mut BifContext) -> Result<String, String>;
+}
+
+impl<F> BifHandler for F
+where
+    F: Fn(&mut BifContext) -> Result<String, String> + Send + Sync,
+{
+    fn parse(&self, ctx: &mut BifContext) -> Result<String, String> {
+        self(ctx)
+    }
+}
+
+/// The bif being parsed, it gives access to the same parts used by the
+/// built-in bifs.
+pub struct BifContext<'b, 'a> {
+    pub(crate) bif: &'b mut Bif<'a>,
+    pub(crate) has_code: bool,
+}
+
+impl BifContext<'_, '_> {
+    /// Bif name without modifiers.
+    pub fn name(&self) -> &str {
+        &self.bif.name
+    }
+
+    /// Bif modifiers, the handler decides which ones are allowed.
+    pub fn modifiers(&self) -> Modifiers {
+        self.bif.modifiers()
+    }
+
+    /// Bif params, already parsed, without the flags.
+    pub fn params(&self) -> &str {
+        &self.bif.params
+    }
+
+    /// Bif code, not parsed, see `parse_code`.
+    pub fn code(&self) -> &str {
+        &self.bif.code
+    }
+
+    /// Whether the params/code separator was found.
+    pub fn has_code(&self) -> bool {
+        self.has_code
+    }
+
+    /// Flags set in params with {:flg; ... :}
+    pub fn flags(&self) -> Vec<&str> {
+        self.bif
+            .flags
+            .split('|')
+            .filter(|f| !f.is_empty())
+            .collect()
+    }
+
+    /// Whether a flag is set.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.bif.flags.contains(&format!("|{}|", flag))
+    }
+
+    /// Bif arguments in params, each one parsed, see the bif "fetch".
+    ///
+    /// ```text
+    /// {:bif; |arg1|arg2| >> ... :} -> ["", "arg1", "arg2", ""]
+    /// ```
+    pub fn args(&mut self) -> Vec<String> {
+        self.bif.extract_args()
+    }
+
+    /// Parses a source as a child block of this bif, the block has its own
+    /// scope unless the scope modifier (+) is used.
+    pub fn parse(&mut self, source: &str) -> String {
+        if !source.contains(BIF_OPEN) {
+            return source.to_string();
+        }
+
+        let bif = &mut *self.bif;
+        new_child_parse!(bif, source, bif.mod_scope)
+    }
+
+    /// Parses the bif code.
+    pub fn parse_code(&mut self) -> String {
+        let code = self.bif.code.clone();
+        self.parse(&code)
+    }
+
+    /// Get a value from the schema data, or from the local data with the
+    /// prefix "local::", as in the bif var: {:;name:}
+    pub fn get_data(&self, name: &str) -> String {
+        self.bif.get_data(name)
+    }
+
+    /// The file being parsed, empty if it is the template source.
+    pub fn current_file(&self) -> &str {
+        &self.bif.inherit.current_file
+    }
+
+    /// The directory of the file being parsed.
+    pub fn current_dir(&self) -> &str {
+        &self.bif.inherit.current_dir
+    }
+}
+
+// Checks that the name can be used for a custom bif.
+pub(crate) fn check_bif_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("Invalid bif name: {}", name));
+    }
+
+    if BIF_LIST.contains(&name) || BIF_LIST_EXTRA.contains(&name) {
+        return Err(format!("{} is a built-in bif", name));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,380 @@
+//! Neutral TS command line.
+//!
+//! ```text
+//! neutralts render [--schema FILE]... [--errors FILE] TEMPLATE
+//! neutralts check [--schema FILE]... TEMPLATE...
+//! neutralts serve [--root DIR] [--max-connections N] ADDRESS
+//! neutralts cache purge [--max-size SIZE] [--dry-run] DIR
+//! neutralts cache invalidate DIR TAG...
+//! neutralts cache inspect DIR
+//! ```
+//!
+//! The schema files are JSON, or MessagePack if the extension is ".msgpack".
+
+use neutralts::cache::{self, PurgeOptions};
+use neutralts::{CacheBackend, FileCache, Template};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage:
+    neutralts render [--schema FILE]... [--errors FILE] TEMPLATE
+    neutralts check [--schema FILE]... TEMPLATE...
+    neutralts serve [--root DIR] [--max-connections N] ADDRESS
+    neutralts cache purge [--max-size SIZE] [--dry-run] DIR
+    neutralts cache invalidate DIR TAG...
+    neutralts cache inspect DIR
+
+Commands:
+    render   Render a template to the standard output, exit 1 if there are
+             errors or the status code is 5xx
+    check    Check templates without rendering them, exit 1 if there are problems
+    serve    Start the IPC server on HOST:PORT or unix:PATH, requires the
+             \"ipc\" feature. It renders the templates in --root, by default
+             the working directory, answering --max-connections at a time
+             (default 64)
+    cache    Manage the file cache, DIR is config.cache_dir/config.cache_prefix
+             and must contain \"neutral-cache\":
+               purge        Remove the expired files, and the oldest ones
+                            while the size is over --max-size (e.g. 500M,
+                            suffix K, M, G)
+               invalidate   Remove the fragments with any of the tags
+               inspect      List the fragments with the TTL, age and size
+
+Options:
+    -s, --schema FILE    Schema file, JSON or MessagePack (.msgpack), can be repeated
+    -e, --errors FILE    Write the render errors as JSON, \"-\" for the standard error
+    -n, --dry-run        Print what would be removed from the cache
+    -r, --root DIR       Directory of the templates rendered by the server
+    -h, --help           Print this help";
+
+// Exit code of usage errors, 1 is for templates with problems and for
+// files that cannot be read or parsed.
+const EXIT_USAGE: u8 = 2;
+
+struct Args {
+    schemas: Vec<String>,
+    errors: Option<String>,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("render") => parse_args(&args[1..]).and_then(|args| render(&args)),
+        Some("check") => parse_args(&args[1..]).and_then(|args| check(&args)),
+        Some("serve") => serve(&args[1..]),
+        Some("cache") => cache_command(&args[1..]),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("unknown command: {}", command)),
+        None => Err("a command is required".to_string()),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("neutralts: {}\n\n{}", e, USAGE);
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args {
+        schemas: Vec::new(),
+        errors: None,
+        files: Vec::new(),
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-s" | "--schema" => match args.next() {
+                Some(file) => parsed.schemas.push(file.clone()),
+                None => return Err(format!("{} requires a file", arg)),
+            },
+            "-e" | "--errors" => match args.next() {
+                Some(file) => parsed.errors = Some(file.clone()),
+                None => return Err(format!("{} requires a file", arg)),
+            },
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => parsed.files.push(arg.clone()),
+        }
+    }
+
+    if parsed.files.is_empty() {
+        return Err("a template file is required".to_string());
+    }
+
+    Ok(parsed)
+}
+
+fn new_template(args: &Args, file: &str) -> Result<Template, String> {
+    let mut template = Template::new()?;
+
+    for schema in &args.schemas {
+        if schema.ends_with(".msgpack") {
+            template.merge_schema_msgpack_path(schema)
+        } else {
+            template.merge_schema_path(schema)
+        }
+        .map_err(|e| format!("{}: {}", schema, e))?;
+    }
+    template
+        .set_src_path(file)
+        .map_err(|e| format!("{}: {}", file, e))?;
+
+    Ok(template)
+}
+
+fn render(args: &Args) -> Result<ExitCode, String> {
+    let [file] = args.files.as_slice() else {
+        return Err("only one template file can be rendered".to_string());
+    };
+
+    let mut template = match new_template(args, file) {
+        Ok(template) => template,
+        Err(e) => return Ok(failure(&e)),
+    };
+    let out = template.render();
+
+    if let Err(e) = io::stdout()
+        .write_all(out.as_bytes())
+        .and_then(|_| io::stdout().flush())
+    {
+        return Ok(failure(&e.to_string()));
+    }
+
+    if let Some(errors_file) = &args.errors {
+        let errors = serde_json::to_string_pretty(&template.get_error()).unwrap_or_default();
+
+        if errors_file == "-" {
+            eprintln!("{}", errors);
+        } else if let Err(e) = fs::write(errors_file, errors + "\n") {
+            return Ok(failure(&format!("{}: {}", errors_file, e)));
+        }
+    }
+
+    let status = template.get_status_code().parse::<u16>().unwrap_or(500);
+
+    Ok(if template.has_error() || (500..600).contains(&status) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn check(args: &Args) -> Result<ExitCode, String> {
+    let mut has_errors = false;
+
+    for file in &args.files {
+        let mut template = match new_template(args, file) {
+            Ok(template) => template,
+            Err(e) => return Ok(failure(&e)),
+        };
+
+        for error in template.check() {
+            println!("{}", error);
+            has_errors = true;
+        }
+    }
+
+    Ok(if has_errors {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+#[cfg(feature = "ipc")]
+fn serve(args: &[String]) -> Result<ExitCode, String> {
+    use neutralts::ipc::{Server, ServerOptions};
+
+    let mut root = ".";
+    let mut max_connections = None;
+    let mut addresses = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-r" | "--root" => match args.next() {
+                Some(dir) => root = dir,
+                None => return Err(format!("{} requires a directory", arg)),
+            },
+            "--max-connections" => match args.next().map(|n| n.parse::<usize>()) {
+                Some(Ok(n)) if n > 0 => max_connections = Some(n),
+                _ => return Err(format!("{} requires a positive number", arg)),
+            },
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => addresses.push(arg),
+        }
+    }
+
+    let [address] = addresses.as_slice() else {
+        return Err("an address is required".to_string());
+    };
+
+    let mut options = match ServerOptions::default().with_root(root) {
+        Ok(options) => options,
+        Err(e) => return Ok(failure(&format!("{}: {}", root, e))),
+    };
+    if let Some(max_connections) = max_connections {
+        options.max_connections = max_connections;
+    }
+
+    let server = match address.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => Server::bind_unix(path),
+        #[cfg(not(unix))]
+        Some(_) => return Err("Unix sockets are not supported".to_string()),
+        None => Server::bind_tcp(address.as_str()),
+    };
+    let mut server = match server {
+        Ok(server) => server,
+        Err(e) => return Ok(failure(&format!("{}: {}", address, e))),
+    };
+    server.set_options(options);
+
+    if let Err(e) = server.serve() {
+        return Ok(failure(&e.to_string()));
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(not(feature = "ipc"))]
+fn serve(_args: &[String]) -> Result<ExitCode, String> {
+    Err("built without the \"ipc\" feature".to_string())
+}
+
+fn cache_command(args: &[String]) -> Result<ExitCode, String> {
+    match args.first().map(String::as_str) {
+        Some("purge") => cache_purge(&args[1..]),
+        Some("invalidate") => cache_invalidate(&args[1..]),
+        Some("inspect") => cache_inspect(&args[1..]),
+        Some(command) => Err(format!("unknown cache command: {}", command)),
+        None => Err("a cache command is required".to_string()),
+    }
+}
+
+fn cache_purge(args: &[String]) -> Result<ExitCode, String> {
+    let mut options = PurgeOptions::default();
+    let mut dirs = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-size" => match args.next() {
+                Some(size) => options.max_size = Some(parse_size(size)?),
+                None => return Err(format!("{} requires a size", arg)),
+            },
+            "-n" | "--dry-run" => options.dry_run = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => dirs.push(arg.as_str()),
+        }
+    }
+
+    let [dir] = dirs.as_slice() else {
+        return Err("a cache directory is required".to_string());
+    };
+
+    let stats = match cache::purge(dir, "", &options) {
+        Ok(stats) => stats,
+        Err(e) => return Ok(failure(&format!("{}: {}", dir, e))),
+    };
+    let removed = if options.dry_run {
+        "would be removed"
+    } else {
+        "removed"
+    };
+
+    println!("files: {} ({} bytes)", stats.files, stats.bytes);
+    println!("expired: {}", stats.expired);
+    println!("evicted: {}", stats.evicted);
+    println!(
+        "{}: {} files ({} bytes), {} directories",
+        removed,
+        stats.expired + stats.evicted,
+        stats.removed_bytes,
+        stats.dirs_removed
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn cache_invalidate(args: &[String]) -> Result<ExitCode, String> {
+    let [dir, tags @ ..] = args else {
+        return Err("a cache directory is required".to_string());
+    };
+    if tags.is_empty() {
+        return Err("a tag is required".to_string());
+    }
+
+    let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+
+    match FileCache::new(dir).invalidate_tags(&tags) {
+        Ok(removed) => {
+            println!("removed: {} fragments", removed);
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => Ok(failure(&e)),
+    }
+}
+
+fn cache_inspect(args: &[String]) -> Result<ExitCode, String> {
+    let [dir] = args else {
+        return Err("a cache directory is required".to_string());
+    };
+
+    let entries = match cache::inspect(dir) {
+        Ok(entries) => entries,
+        Err(e) => return Ok(failure(&format!("{}: {}", dir, e))),
+    };
+
+    println!("{:<64} {:>8} {:>8} {:>10}", "KEY", "TTL", "AGE", "SIZE");
+    for entry in &entries {
+        println!(
+            "{:<64} {:>8} {:>8} {:>10}{}",
+            entry.key,
+            entry.ttl,
+            entry.age,
+            entry.size,
+            if entry.expired { " expired" } else { "" }
+        );
+    }
+    println!(
+        "entries: {} ({} bytes), expired: {}",
+        entries.len(),
+        entries.iter().map(|entry| entry.size).sum::<u64>(),
+        entries.iter().filter(|entry| entry.expired).count()
+    );
+
+    Ok(ExitCode::SUCCESS)
+}
+
+// Prints an error that is not of usage, e.g.: a file cannot be read.
+fn failure(message: &str) -> ExitCode {
+    eprintln!("neutralts: {}", message);
+    ExitCode::FAILURE
+}
+
+// Bytes, or with the suffix K, M or G: 500M
+fn parse_size(size: &str) -> Result<u64, String> {
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&size[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&size[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size: {}", size))
+}
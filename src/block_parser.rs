@@ -1,5 +1,20 @@
-use crate::{bif::Bif, constants::*, shared::Shared, utils::extract_blocks};
-use std::rc::Rc;
+use crate::{
+    bif::Bif, compiled::compile_file_cached, compiled::CompiledBlock, compiled::CompiledTemplate,
+    compiled::SourceOrigin, constants::*, render_error::*, shared::Shared, utils::extract_blocks,
+};
+use std::path::Path;
+use std::sync::Arc;
+
+// A block being rendered and which of its contents, from the most derived
+// template to the default content in the layout, see the bif "parent".
+#[derive(Clone)]
+pub(crate) struct BlockLevel {
+    pub(crate) name: String,
+    pub(crate) level: usize,
+    pub(crate) default: String,
+    pub(crate) default_dir: String,
+    pub(crate) default_origin: Option<SourceOrigin>,
+}
 
 pub(crate) struct BlockInherit {
     pub(crate) indir: String,
@@ -15,6 +30,13 @@ pub(crate) struct BlockInherit {
     pub(crate) data_files: Vec<String>,
     pub(crate) in_cache: bool,
     pub(crate) in_only: bool,
+    // Where the source being parsed comes from, if it is known, and the
+    // position of the nearest bif with a known position.
+    pub(crate) origin: Option<SourceOrigin>,
+    pub(crate) fallback: Option<SourceOrigin>,
+    // Layout set with {:extends; ... :}, canonical path.
+    pub(crate) extends: Option<String>,
+    pub(crate) block_level: Option<BlockLevel>,
 }
 
 impl Clone for BlockInherit {
@@ -33,6 +55,10 @@ impl Clone for BlockInherit {
             data_files: self.data_files.clone(),
             in_cache: self.in_cache,
             in_only: self.in_only,
+            origin: self.origin.clone(),
+            fallback: self.fallback.clone(),
+            extends: self.extends.clone(),
+            block_level: self.block_level.clone(),
         }
     }
 }
@@ -53,6 +79,10 @@ impl BlockInherit {
             data_files: Vec::new(),
             in_cache: false,
             in_only: false,
+            origin: None,
+            fallback: None,
+            extends: None,
+            block_level: None,
         }
     }
 
@@ -77,7 +107,7 @@ impl BlockInherit {
         // it does not need to be cloned again.
         if prev_id != block_id {
             if let Some(parent_rc) = shared.indir_store.get(&prev_id) {
-                let parent_rc = Rc::clone(parent_rc);
+                let parent_rc = Arc::clone(parent_rc);
                 shared.indir_store.insert(block_id.clone(), parent_rc);
             }
         }
@@ -86,6 +116,28 @@ impl BlockInherit {
 
         block_id
     }
+
+    // File, line and column of a bif, if its position is not known that of
+    // the nearest bif with a known position is used.
+    pub(crate) fn location(&self, origin: Option<&SourceOrigin>) -> (String, usize, usize) {
+        match origin.or(self.fallback.as_ref()) {
+            Some(origin) => {
+                let (line, column) = origin.line_column();
+                (origin.doc.file_path().to_string(), line, column)
+            }
+            None => (self.current_file.clone(), 0, 0),
+        }
+    }
+}
+
+// Receives the output of the root block as each of its bifs is parsed,
+// instead of all of it at the end, see Template::render_to.
+pub(crate) trait SegmentSink {
+    fn segment(&mut self, shared: &mut Shared, segment: &str);
+
+    // The output received so far is not part of the result, e.g. the
+    // template has set a layout with {:extends; ... :}
+    fn discard(&mut self);
 }
 
 pub(crate) struct BlockParser<'a> {
@@ -121,7 +173,7 @@ impl<'a> BlockParser<'a> {
 
     pub(crate) fn update_indir(&mut self, indir: &String) {
         if let Some(child_rc) = self.shared.indir_store.get(&self.inherit.indir) {
-            let child_rc = Rc::clone(child_rc);
+            let child_rc = Arc::clone(child_rc);
             self.shared.indir_store.insert(indir.clone(), child_rc);
         }
     }
@@ -143,6 +195,18 @@ impl<'a> BlockParser<'a> {
         self.shared.status_param = format!("Unmatched block at position {}", p);
         eprintln!("Unmatched block at position {}", p);
 
+        let origin = self.inherit.origin.as_ref().map(|o| o.at(p));
+        let (file, line, column) = self.inherit.location(origin.as_ref());
+        self.shared.errors.push(RenderError {
+            kind: RenderErrorKind::UnmatchedBlock,
+            bif_name: String::new(),
+            message: "Unmatched block".to_string(),
+            file,
+            line,
+            column,
+            snippet: String::new(),
+        });
+
         if let Some(text) = STATUS_CODES.get(self.shared.status_code.as_str()) {
             self.shared.status_text = text.to_string();
         } else {
@@ -150,18 +214,83 @@ impl<'a> BlockParser<'a> {
         }
     }
 
+    // Parse a compiled template, same as parse but without extracting the blocks
+    // and splitting the bifs again.
+    pub(crate) fn parse_compiled(&mut self, compiled: &'a CompiledTemplate, only: &str) -> String {
+        self.parse_compiled_sink(compiled, only, None)
+    }
+
+    // Same as parse_compiled, but the output is sent to the sink as it is parsed.
+    pub(crate) fn parse_compiled_to(
+        &mut self,
+        compiled: &'a CompiledTemplate,
+        only: &str,
+        sink: &mut dyn SegmentSink,
+    ) {
+        self.parse_compiled_sink(compiled, only, Some(sink));
+    }
+
+    fn parse_compiled_sink(
+        &mut self,
+        compiled: &'a CompiledTemplate,
+        only: &str,
+        sink: Option<&mut dyn SegmentSink>,
+    ) -> String {
+        let blocks = match compiled.blocks() {
+            Ok(b) => b,
+            Err(p) => {
+                self.handle_unmatched_block(p);
+                return EMPTY_STRING;
+            }
+        };
+
+        self.parse_blocks(
+            compiled.source(),
+            blocks.len(),
+            |i| {
+                let span = blocks[i].span();
+                (span.start, span.end, Some(&blocks[i]))
+            },
+            only,
+            sink,
+        )
+    }
+
     pub(crate) fn parse_with_blocks(
         &mut self,
         raw_source: &'a str,
         blocks: &[(usize, usize)],
         only: &str,
     ) -> String {
+        self.parse_blocks(
+            raw_source,
+            blocks.len(),
+            |i| (blocks[i].0, blocks[i].1, None),
+            only,
+            None,
+        )
+    }
+
+    fn parse_blocks<F>(
+        &mut self,
+        raw_source: &'a str,
+        len: usize,
+        block_at: F,
+        only: &str,
+        mut sink: Option<&mut dyn SegmentSink>,
+    ) -> String
+    where
+        F: Fn(usize) -> (usize, usize, Option<&'a CompiledBlock>),
+    {
         let mut prev_end = 0;
         let mut out = String::new();
-        for (start, end) in blocks {
-            let start = *start;
-            let end = *end;
-            let is_comment = raw_source[start..end].starts_with(BIF_COMMENT_OPEN);
+        let is_extended = self.inherit.extends.is_some();
+        for i in 0..len {
+            let (start, end, compiled) = block_at(i);
+            let is_comment = match compiled {
+                Some(c) => c.is_comment(),
+                None => raw_source[start..end].starts_with(BIF_COMMENT_OPEN),
+            };
             let is_short_circuit_coalesce =
                 self.inherit.last_coalesce_out && self.inherit.alias == "coalesce";
 
@@ -174,19 +303,71 @@ impl<'a> BlockParser<'a> {
             }
 
             if !is_comment && !is_short_circuit_coalesce {
+                let origin = self.inherit.origin.as_ref().map(|o| o.at(start));
                 let mut bif = Bif::new(
                     &raw_source[start..end],
                     self.shared,
                     &mut self.inherit,
                     only,
+                    compiled,
+                    origin,
                 );
                 out += &bif.parse();
             }
 
             prev_end = end;
+
+            if let Some(sink) = sink.as_mut() {
+                self.send_segment(&mut **sink, &mut out, is_extended);
+            }
         }
         out += &raw_source[prev_end..];
 
+        if let Some(sink) = sink.as_mut() {
+            self.send_segment(&mut **sink, &mut out, is_extended);
+        }
+
+        // The template has set a layout, its output is discarded and
+        // the layout is parsed with the blocks set.
+        if !is_extended {
+            if let Some(layout) = self.inherit.extends.take() {
+                return self.parse_layout(&layout, only, sink);
+            }
+        }
+
         out.trim().to_string()
     }
+
+    fn send_segment(&mut self, sink: &mut dyn SegmentSink, out: &mut String, is_extended: bool) {
+        if !is_extended && self.inherit.extends.is_some() {
+            sink.discard();
+        } else {
+            sink.segment(self.shared, out);
+        }
+        out.clear();
+    }
+
+    fn parse_layout(
+        &mut self,
+        path: &str,
+        only: &str,
+        sink: Option<&mut dyn SegmentSink>,
+    ) -> String {
+        let loader = Arc::clone(&self.shared.loader);
+        let remove_comments = self.shared.comments.contains("remove");
+        let compiled = compile_file_cached(loader.as_ref(), path, remove_comments)
+            .unwrap_or_else(|_| Arc::new(CompiledTemplate::new("")));
+
+        let mut inherit = self.inherit.clone();
+        inherit.alias = "extends".to_string();
+        inherit.current_file = path.to_string();
+        if let Some(parent) = Path::new(path).parent() {
+            inherit.current_dir = parent.display().to_string();
+        }
+        inherit.include_files.push(path.to_string());
+        inherit.origin = Some(SourceOrigin::new(Arc::clone(&compiled), 0));
+
+        let mut block = BlockParser::new(self.shared, inherit);
+        block.parse_compiled_sink(&compiled, only, sink)
+    }
 }
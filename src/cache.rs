@@ -0,0 +1,954 @@
+//! Cache backends.
+//!
+//! The fragments of the bif cache are stored with a `CacheBackend`:
+//!
+//! ```text
+//!                      .---------------------.
+//!                      |                     |----> FileCache: cache_dir/cache_prefix (default)
+//!  {:cache; ... :} --->|    CacheBackend     |----> MemoryCache: in-process LRU
+//!                      |                     |----> ... or your own, e.g.: a shared store
+//!                      ·---------------------·
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use neutralts::{MemoryCache, Template};
+//! use std::sync::Arc;
+//!
+//! // Shared by all the templates of the process.
+//! let cache = Arc::new(MemoryCache::new(1000));
+//!
+//! let mut template = Template::new().unwrap();
+//! template.set_cache_backend(Arc::clone(&cache));
+//! template.set_src_str("{:cache; /300/ >> <div>...</div> :}");
+//! template.render();
+//! assert_eq!(cache.len(), 1);
+//! ```
+//!
+//! The expired files of the `FileCache` are removed with `purge_expired`, or
+//! with `neutralts cache purge DIR`. For safety, the directory must contain
+//! "neutral-cache", the default `config.cache_prefix`.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The cache directory must contain it to be purged.
+pub const CACHE_SAFE_NAME: &str = "neutral-cache";
+
+// Writes of all the templates of the process, for config.cache_purge_every.
+static CACHE_WRITES: AtomicU64 = AtomicU64::new(0);
+static SWEEPING: AtomicBool = AtomicBool::new(false);
+
+// Fragments being rendered in the process, by "key-ttl", see `begin_fill`.
+static FILLING: Mutex<BTreeMap<String, Arc<Fill>>> = Mutex::new(BTreeMap::new());
+
+// Sequence of the temporary files of the `FileCache`.
+static TEMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+// Directory of the tag index of the `FileCache`, one file per tag.
+const TAGS_DIR: &str = "tags";
+
+// Temporary files older than this are left by a crash, the purge removes them.
+const TEMP_FILE_TTL: u64 = 3600;
+
+// Max time a render waits for another one that renders the same fragment,
+// then it renders the fragment itself.
+pub(crate) const FILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) type SharedCache = Arc<dyn CacheBackend>;
+
+/// Stores the fragments of the bif cache.
+///
+/// The key is the cache id, a SHA-256 in hexadecimal, and the TTL is the
+/// `expires` of the block in seconds: `{:cache; /ttl/ >> ... :}`. The same
+/// key with another TTL is another fragment.
+pub trait CacheBackend: Send + Sync {
+    /// Gets a fragment.
+    ///
+    /// # Returns
+    ///
+    /// The fragment, `None` if it is not stored or it has expired, or an
+    /// error message if it cannot be read.
+    fn get(&self, key: &str, ttl: u64) -> Result<Option<String>, String>;
+
+    /// Stores a fragment.
+    ///
+    /// # Returns
+    ///
+    /// An error message if it cannot be stored, the block is rendered anyway.
+    fn set(&self, key: &str, ttl: u64, content: &str) -> Result<(), String>;
+
+    /// Gets a fragment even if it has expired.
+    ///
+    /// It is served while another render of the process renders the same
+    /// fragment again. By default it is `None`, and the render waits for it.
+    fn get_stale(&self, _key: &str, _ttl: u64) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    /// Adds a stored fragment to the index of its tags, see `invalidate_tags`.
+    ///
+    /// The tags are set with `{:cache; {:flg; tags product-42 catalog :} /ttl/ >> ... :}`,
+    /// by default they are ignored.
+    fn tag(&self, _key: &str, _ttl: u64, _tags: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Removes the fragments with any of the tags.
+    ///
+    /// # Returns
+    ///
+    /// The number of fragments removed, or an error message. By default it
+    /// is an error, the backend does not support tags.
+    fn invalidate_tags(&self, _tags: &[&str]) -> Result<u64, String> {
+        Err("the cache backend does not support tags".to_string())
+    }
+}
+
+impl<T: CacheBackend + ?Sized> CacheBackend for Arc<T> {
+    fn get(&self, key: &str, ttl: u64) -> Result<Option<String>, String> {
+        (**self).get(key, ttl)
+    }
+
+    fn set(&self, key: &str, ttl: u64, content: &str) -> Result<(), String> {
+        (**self).set(key, ttl, content)
+    }
+
+    fn get_stale(&self, key: &str, ttl: u64) -> Result<Option<String>, String> {
+        (**self).get_stale(key, ttl)
+    }
+
+    fn tag(&self, key: &str, ttl: u64, tags: &[String]) -> Result<(), String> {
+        (**self).tag(key, ttl, tags)
+    }
+
+    fn invalidate_tags(&self, tags: &[&str]) -> Result<u64, String> {
+        (**self).invalidate_tags(tags)
+    }
+}
+
+/// Counters of the bif cache in a render, see `Template::get_cache_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Fragments served from the cache.
+    pub hits: u64,
+    /// Fragments not in the cache, or expired, they are rendered.
+    pub misses: u64,
+    /// Expired fragments served while another render fills them.
+    pub stale: u64,
+    /// Fragments stored.
+    pub writes: u64,
+    /// Fragments that could not be read, they are rendered.
+    pub read_errors: u64,
+    /// Fragments or tags that could not be stored.
+    pub write_errors: u64,
+    /// Size of the fragments served from the cache, including the stale ones.
+    pub bytes_served: u64,
+}
+
+/// Stores the fragments in files, it is the default backend.
+///
+/// Each fragment is the file `dir/<3 hex>/<key>-<ttl>`, it expires by its
+/// modification time. The directory is `config.cache_dir`, the temporary
+/// directory if it is empty, and `config.cache_prefix`.
+///
+/// The files are written to a temporary file and renamed, a reader never
+/// gets a fragment partially written. The tag index is `dir/tags/<sha256 of
+/// the tag>`, with a line `<key>-<ttl>` for each fragment.
+pub struct FileCache {
+    dir: PathBuf,
+}
+
+impl FileCache {
+    /// A file cache in a directory, it is created when needed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        FileCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub(crate) fn from_config(cache_dir: &str, cache_prefix: &str) -> Self {
+        let mut dir = if cache_dir.is_empty() {
+            std::env::temp_dir()
+        } else {
+            PathBuf::from(cache_dir)
+        };
+
+        if !cache_prefix.is_empty() {
+            dir.push(cache_prefix);
+        }
+
+        FileCache { dir }
+    }
+
+    /// The file of a fragment.
+    pub fn path(&self, key: &str, ttl: u64) -> PathBuf {
+        let subdir = key.get(0..3).unwrap_or(key);
+
+        self.dir.join(subdir).join(format!("{}-{}", key, ttl))
+    }
+
+    // The index file of a tag.
+    fn tag_path(&self, tag: &str) -> PathBuf {
+        self.dir
+            .join(TAGS_DIR)
+            .join(format!("{:x}", Sha256::digest(tag.as_bytes())))
+    }
+
+    // Moves the index of a tag aside and reads it, `None` if there is none.
+    // The fragments tagged meanwhile go to a new index, not to the one read.
+    fn take_index(&self, tag_path: &Path) -> io::Result<Option<String>> {
+        let aside = tag_path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            tag_path.file_name().unwrap_or_default().to_string_lossy(),
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        match fs::rename(tag_path, &aside) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let index = fs::read_to_string(&aside);
+        remove_cache_file(&aside, false)?;
+
+        index.map(Some)
+    }
+
+    // The file of a line of a tag index, `None` if it is not valid.
+    fn indexed_path(&self, line: &str) -> Option<PathBuf> {
+        let path = self.dir.join(line.get(0..3)?).join(line);
+        cache_file_ttl(&path)?;
+
+        Some(path)
+    }
+}
+
+impl CacheBackend for FileCache {
+    fn get(&self, key: &str, ttl: u64) -> Result<Option<String>, String> {
+        let file_path = self.path(key, ttl);
+
+        if !file_path.exists() || file_expired(&file_path, ttl) {
+            return Ok(None);
+        }
+
+        match fs::read_to_string(&file_path) {
+            Ok(content) => Ok(Some(content)),
+            Err(_) => Err(format!("Failed to read cache {}", file_path.display())),
+        }
+    }
+
+    fn set(&self, key: &str, ttl: u64, content: &str) -> Result<(), String> {
+        let file_path = self.path(key, ttl);
+
+        if let Some(cache_dir) = file_path.parent() {
+            fs::create_dir_all(cache_dir).map_err(|e| {
+                format!(
+                    "Failed to create cache directory {}: {}",
+                    cache_dir.display(),
+                    e
+                )
+            })?;
+        }
+
+        // In the same directory, the rename is atomic.
+        let temp_path = file_path.with_file_name(format!(
+            ".{}-{}.{}.{}.tmp",
+            key,
+            ttl,
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut file = fs::File::create(&temp_path)
+            .map_err(|e| format!("Failed to create file {}: {}", temp_path.display(), e))?;
+
+        let result = file
+            .write_all(content.as_bytes())
+            .map_err(|e| format!("Failed to write to cache {}: {}", file_path.display(), e))
+            .and_then(|_| {
+                fs::rename(&temp_path, &file_path)
+                    .map_err(|e| format!("Failed to write to cache {}: {}", file_path.display(), e))
+            });
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+
+        result
+    }
+
+    fn get_stale(&self, key: &str, ttl: u64) -> Result<Option<String>, String> {
+        let file_path = self.path(key, ttl);
+
+        if !file_path.exists() {
+            return Ok(None);
+        }
+
+        match fs::read_to_string(&file_path) {
+            Ok(content) => Ok(Some(content)),
+            Err(_) => Err(format!("Failed to read cache {}", file_path.display())),
+        }
+    }
+
+    fn tag(&self, key: &str, ttl: u64, tags: &[String]) -> Result<(), String> {
+        let line = format!("{}-{}\n", key, ttl);
+        let tags_dir = self.dir.join(TAGS_DIR);
+
+        fs::create_dir_all(&tags_dir).map_err(|e| {
+            format!(
+                "Failed to create cache directory {}: {}",
+                tags_dir.display(),
+                e
+            )
+        })?;
+
+        for tag in tags {
+            let tag_path = self.tag_path(tag);
+
+            if let Ok(index) = fs::read_to_string(&tag_path) {
+                if index.lines().any(|l| l == line.trim_end()) {
+                    continue;
+                }
+            }
+
+            // Appends of a line are not interleaved with other processes.
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&tag_path)
+                .and_then(|mut file| file.write_all(line.as_bytes()))
+                .map_err(|e| format!("Failed to write to cache {}: {}", tag_path.display(), e))?;
+        }
+
+        Ok(())
+    }
+
+    fn invalidate_tags(&self, tags: &[&str]) -> Result<u64, String> {
+        let mut removed = 0;
+
+        for tag in tags {
+            let tag_path = self.tag_path(tag);
+            let index = match self.take_index(&tag_path) {
+                Ok(Some(index)) => index,
+                Ok(None) => continue,
+                Err(e) => {
+                    return Err(format!(
+                        "Failed to read cache {}: {}",
+                        tag_path.display(),
+                        e
+                    ))
+                }
+            };
+
+            for file_path in index.lines().filter_map(|line| self.indexed_path(line)) {
+                if fs::remove_file(&file_path).is_ok() {
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+// Whether the file modification time is older than the TTL.
+pub(crate) fn file_expired(file_path: &Path, ttl: u64) -> bool {
+    let modified = match fs::metadata(file_path).and_then(|m| m.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return true,
+    };
+
+    match SystemTime::now().duration_since(modified) {
+        Ok(age) => age.as_secs() > ttl,
+        // Modified in the future, e.g.: the clock has gone back.
+        Err(_) => false,
+    }
+}
+
+/// Stores the fragments in memory, up to a number of entries.
+///
+/// When it is full, the least recently used fragment is removed. Share it
+/// between templates with an `Arc`, each process has its own.
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<MemoryInner>,
+}
+
+#[derive(Default)]
+struct MemoryInner {
+    entries: HashMap<String, MemoryEntry>,
+    // Last use of each entry, the first is the least recently used.
+    lru: BTreeMap<u64, String>,
+    tick: u64,
+    // Entries of each tag.
+    tags: HashMap<String, HashSet<String>>,
+}
+
+struct MemoryEntry {
+    content: String,
+    stored: Instant,
+    tick: u64,
+    tags: Vec<String>,
+}
+
+impl MemoryInner {
+    fn touch(&mut self, entry_key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+
+        if let Some(entry) = self.entries.get_mut(entry_key) {
+            self.lru.remove(&entry.tick);
+            entry.tick = tick;
+            self.lru.insert(tick, entry_key.to_string());
+        }
+    }
+
+    fn remove(&mut self, entry_key: &str) -> bool {
+        let Some(entry) = self.entries.remove(entry_key) else {
+            return false;
+        };
+        self.lru.remove(&entry.tick);
+
+        for tag in entry.tags {
+            if let Some(keys) = self.tags.get_mut(&tag) {
+                keys.remove(entry_key);
+                if keys.is_empty() {
+                    self.tags.remove(&tag);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl MemoryCache {
+    /// A memory cache of up to `capacity` fragments.
+    pub fn new(capacity: usize) -> Self {
+        MemoryCache {
+            capacity,
+            inner: Mutex::new(MemoryInner::default()),
+        }
+    }
+
+    /// The number of fragments stored, including the expired ones not yet removed.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all the fragments.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.tags.clear();
+    }
+
+    // A poisoned lock only means a panic while holding it, the map is still valid.
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get(&self, key: &str, ttl: u64) -> Result<Option<String>, String> {
+        let entry_key = format!("{}-{}", key, ttl);
+        let mut inner = self.lock();
+
+        let content = match inner.entries.get(&entry_key) {
+            Some(entry) if entry.stored.elapsed().as_secs() <= ttl => entry.content.clone(),
+            Some(_) => {
+                inner.remove(&entry_key);
+                return Ok(None);
+            }
+            None => return Ok(None),
+        };
+        inner.touch(&entry_key);
+
+        Ok(Some(content))
+    }
+
+    fn set(&self, key: &str, ttl: u64, content: &str) -> Result<(), String> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+
+        let entry_key = format!("{}-{}", key, ttl);
+        let mut inner = self.lock();
+
+        inner.remove(&entry_key);
+        while inner.entries.len() >= self.capacity {
+            let Some((_, oldest)) = inner.lru.first_key_value() else {
+                break;
+            };
+            let oldest = oldest.clone();
+            inner.remove(&oldest);
+        }
+
+        inner.entries.insert(
+            entry_key.clone(),
+            MemoryEntry {
+                content: content.to_string(),
+                stored: Instant::now(),
+                tick: 0,
+                tags: Vec::new(),
+            },
+        );
+        inner.touch(&entry_key);
+
+        Ok(())
+    }
+
+    fn tag(&self, key: &str, ttl: u64, tags: &[String]) -> Result<(), String> {
+        let entry_key = format!("{}-{}", key, ttl);
+        let mut inner = self.lock();
+
+        let Some(entry) = inner.entries.get_mut(&entry_key) else {
+            return Ok(());
+        };
+        let new_tags: Vec<String> = tags
+            .iter()
+            .filter(|tag| !entry.tags.contains(tag))
+            .cloned()
+            .collect();
+        entry.tags.extend(new_tags.iter().cloned());
+
+        for tag in new_tags {
+            inner.tags.entry(tag).or_default().insert(entry_key.clone());
+        }
+
+        Ok(())
+    }
+
+    fn invalidate_tags(&self, tags: &[&str]) -> Result<u64, String> {
+        let mut inner = self.lock();
+        let mut removed = 0;
+
+        for tag in tags {
+            let Some(keys) = inner.tags.remove(*tag) else {
+                continue;
+            };
+            for entry_key in keys {
+                if inner.remove(&entry_key) {
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+// A fragment being rendered, see `begin_fill`.
+pub(crate) struct Fill {
+    thread: ThreadId,
+    done: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Fill {
+    /// Waits until the fragment is stored, or the render fails, up to `timeout`.
+    pub(crate) fn wait(&self, timeout: Duration) {
+        // A render of the same thread never ends while waiting.
+        if self.thread == thread::current().id() {
+            return;
+        }
+
+        let done = self.done.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = self
+            .cond
+            .wait_timeout_while(done, timeout, |done| !*done)
+            .unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// The render that fills a fragment, the others wait while it is alive.
+pub(crate) struct FillGuard {
+    key: String,
+    fill: Arc<Fill>,
+}
+
+impl Drop for FillGuard {
+    fn drop(&mut self) {
+        FILLING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+        *self.fill.done.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.fill.cond.notify_all();
+    }
+}
+
+// Single flight: the first render of a missing fragment gets the guard and
+// renders it, the others get the fill in progress to wait for it or serve
+// the stale copy, instead of rendering the same fragment at the same time.
+pub(crate) fn begin_fill(key: &str, ttl: u64) -> Result<FillGuard, Arc<Fill>> {
+    let key = format!("{}-{}", key, ttl);
+    let mut filling = FILLING.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(fill) = filling.get(&key) {
+        return Err(Arc::clone(fill));
+    }
+
+    let fill = Arc::new(Fill {
+        thread: thread::current().id(),
+        done: Mutex::new(false),
+        cond: Condvar::new(),
+    });
+    filling.insert(key.clone(), Arc::clone(&fill));
+
+    Ok(FillGuard { key, fill })
+}
+
+/// Options of `purge`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PurgeOptions {
+    /// Max size in bytes of the files kept, the oldest are removed first.
+    pub max_size: Option<u64>,
+    /// Only count the files that would be removed.
+    pub dry_run: bool,
+}
+
+/// What `purge` has found and removed, or would remove in a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PurgeStats {
+    /// Cache files found.
+    pub files: u64,
+    /// Size of the cache files found.
+    pub bytes: u64,
+    /// Expired files removed.
+    pub expired: u64,
+    /// Files removed to keep the size under `max_size`.
+    pub evicted: u64,
+    /// Size of the files removed.
+    pub removed_bytes: u64,
+    /// Empty subdirectories removed.
+    pub dirs_removed: u64,
+}
+
+/// Removes the expired files of a file cache, see `purge`.
+///
+/// # Example
+///
+/// ```no_run
+/// let stats = neutralts::cache::purge_expired("/var/cache", "neutral-cache").unwrap();
+/// println!("{} expired files removed", stats.expired);
+/// ```
+pub fn purge_expired(cache_dir: &str, prefix: &str) -> io::Result<PurgeStats> {
+    purge(cache_dir, prefix, &PurgeOptions::default())
+}
+
+/// Removes the expired files of a file cache, and the oldest ones while
+/// the size is over `options.max_size`.
+///
+/// Only the files named `<hex>-<ttl>` in subdirectories of 3 hex characters
+/// are removed, as they are written by `FileCache`, then the empty
+/// subdirectories. The tag indexes keep only the fragments that remain.
+///
+/// # Arguments
+///
+/// * `cache_dir` - `config.cache_dir`, the temporary directory if it is empty.
+/// * `prefix` - `config.cache_prefix`.
+///
+/// # Returns
+///
+/// The statistics, or an error if the directory does not contain
+/// "neutral-cache" or it cannot be read.
+pub fn purge(cache_dir: &str, prefix: &str, options: &PurgeOptions) -> io::Result<PurgeStats> {
+    let dir = cache_path(cache_dir, prefix)?;
+    let mut stats = PurgeStats::default();
+    // Files kept: modified time, size and path.
+    let mut kept: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+    let mut subdirs = Vec::new();
+
+    for subdir in cache_subdirs(&dir)? {
+        for file in fs::read_dir(&subdir)? {
+            let file = file?.path();
+            let Some(ttl) = cache_file_ttl(&file) else {
+                if is_temp_file(&file) && file_expired(&file, TEMP_FILE_TTL) {
+                    remove_cache_file(&file, options.dry_run)?;
+                }
+                continue;
+            };
+            let Ok(metadata) = fs::metadata(&file) else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+            stats.files += 1;
+            stats.bytes += metadata.len();
+
+            if file_expired(&file, ttl) {
+                remove_cache_file(&file, options.dry_run)?;
+                stats.expired += 1;
+                stats.removed_bytes += metadata.len();
+            } else {
+                kept.push((modified, metadata.len(), file));
+            }
+        }
+        subdirs.push(subdir);
+    }
+
+    if let Some(max_size) = options.max_size {
+        let mut size: u64 = kept.iter().map(|(_, len, _)| len).sum();
+        kept.sort();
+
+        for (_, len, file) in kept {
+            if size <= max_size {
+                break;
+            }
+            remove_cache_file(&file, options.dry_run)?;
+            size -= len;
+            stats.evicted += 1;
+            stats.removed_bytes += len;
+        }
+    }
+
+    if !options.dry_run {
+        for subdir in subdirs {
+            // Fails if it is not empty.
+            if fs::remove_dir(&subdir).is_ok() {
+                stats.dirs_removed += 1;
+            }
+        }
+        purge_tags(&dir)?;
+    }
+
+    Ok(stats)
+}
+
+// Compacts the tag indexes, the lines of the fragments that no longer exist
+// are removed, and the index too if none exists. The lines are appended
+// back, after the ones of the renders that tag a fragment meanwhile.
+fn purge_tags(dir: &Path) -> io::Result<()> {
+    let cache = FileCache::new(dir);
+    let tags_dir = dir.join(TAGS_DIR);
+
+    let Ok(entries) = fs::read_dir(&tags_dir) else {
+        return Ok(());
+    };
+
+    for tag_path in entries {
+        let tag_path = tag_path?.path();
+        if is_temp_file(&tag_path) {
+            if file_expired(&tag_path, TEMP_FILE_TTL) {
+                remove_cache_file(&tag_path, false)?;
+            }
+            continue;
+        }
+
+        let Some(index) = cache.take_index(&tag_path)? else {
+            continue;
+        };
+        let mut seen = HashSet::new();
+        let mut kept = String::new();
+        for line in index.lines() {
+            let in_use = cache
+                .indexed_path(line)
+                .is_some_and(|file_path| file_path.exists());
+            if in_use && seen.insert(line) {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+
+        if !kept.is_empty() {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&tag_path)?
+                .write_all(kept.as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A fragment of a file cache, see `inspect`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CacheEntry {
+    /// The cache id.
+    pub key: String,
+    /// The `expires` of the block in seconds.
+    pub ttl: u64,
+    /// Seconds since it was stored.
+    pub age: u64,
+    /// Size in bytes.
+    pub size: u64,
+    /// The age is over the TTL, it is no longer served.
+    pub expired: bool,
+    pub path: PathBuf,
+}
+
+/// Lists the fragments of a file cache, by key and TTL.
+///
+/// # Arguments
+///
+/// * `cache_dir` - The directory of the `FileCache`, `config.cache_dir` and
+///   `config.cache_prefix`.
+///
+/// # Example
+///
+/// ```no_run
+/// for entry in neutralts::cache::inspect("/tmp/neutral-cache").unwrap() {
+///     println!("{} {}/{}s {} bytes", entry.key, entry.age, entry.ttl, entry.size);
+/// }
+/// ```
+pub fn inspect<P: AsRef<Path>>(cache_dir: P) -> io::Result<Vec<CacheEntry>> {
+    let now = SystemTime::now();
+    let mut entries = Vec::new();
+
+    for subdir in cache_subdirs(cache_dir.as_ref())? {
+        for file in fs::read_dir(&subdir)? {
+            let path = file?.path();
+            let Some(ttl) = cache_file_ttl(&path) else {
+                continue;
+            };
+            // Removed in the meantime.
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .map_or(0, |age| age.as_secs());
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let key = name.rsplit_once('-').map_or("", |(key, _)| key).to_string();
+
+            entries.push(CacheEntry {
+                key,
+                ttl,
+                age,
+                size: metadata.len(),
+                expired: age > ttl,
+                path,
+            });
+        }
+    }
+    entries.sort_by(|a, b| (&a.key, a.ttl).cmp(&(&b.key, b.ttl)));
+
+    Ok(entries)
+}
+
+// The directory of a file cache, if it is safe to remove its files.
+pub(crate) fn cache_path(cache_dir: &str, prefix: &str) -> io::Result<PathBuf> {
+    let mut dir = if cache_dir.is_empty() {
+        std::env::temp_dir()
+    } else {
+        PathBuf::from(cache_dir)
+    };
+    if !prefix.is_empty() {
+        dir.push(prefix);
+    }
+
+    if !dir.to_string_lossy().contains(CACHE_SAFE_NAME) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid directory {}, for security reasons it must contain '{}'",
+                dir.display(),
+                CACHE_SAFE_NAME
+            ),
+        ));
+    }
+
+    Ok(dir)
+}
+
+// The subdirectories of 3 hex characters of a file cache.
+fn cache_subdirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut subdirs = Vec::new();
+
+    for subdir in fs::read_dir(dir)? {
+        let subdir = subdir?.path();
+        if subdir.is_dir() && is_cache_subdir(&subdir) {
+            subdirs.push(subdir);
+        }
+    }
+
+    Ok(subdirs)
+}
+
+fn is_cache_subdir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.len() == 3 && name.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// The TTL of a file named <hex>-<ttl>.
+pub(crate) fn cache_file_ttl(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?;
+    let (key, ttl) = name.rsplit_once('-')?;
+
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    if ttl.is_empty() || !ttl.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    ttl.parse().ok()
+}
+
+// A temporary file of `FileCache::set`.
+fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(".tmp"))
+}
+
+fn remove_cache_file(file: &Path, dry_run: bool) -> io::Result<()> {
+    if dry_run {
+        return Ok(());
+    }
+
+    match fs::remove_file(file) {
+        // Removed by another process in the meantime.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+// Counts a cache write and, every `every` writes of the process, purges the
+// cache in the background, see config.cache_purge_every.
+pub(crate) fn sweep_on_write(cache_dir: &str, prefix: &str, every: u64, max_size: u64) {
+    if every == 0 || !(CACHE_WRITES.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(every) {
+        return;
+    }
+    if SWEEPING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let cache_dir = cache_dir.to_string();
+    let prefix = prefix.to_string();
+    let options = PurgeOptions {
+        max_size: (max_size > 0).then_some(max_size),
+        dry_run: false,
+    };
+
+    thread::spawn(move || {
+        // Opportunistic, the errors are found by the next sweep or by hand.
+        let _ = purge(&cache_dir, &prefix, &options);
+        SWEEPING.store(false, Ordering::Release);
+    });
+}
+
+#[cfg(test)]
+#[path = "cache_tests.rs"]
+mod tests;
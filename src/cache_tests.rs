@@ -0,0 +1,677 @@
+#[cfg(test)]
+mod tests {
+    use crate::cache::*;
+    use crate::test_helpers::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    const KEY: &str = "abcdef0123456789";
+
+    // Stand-in for a store shared by several servers, e.g.: Redis.
+    #[derive(Default)]
+    struct RemoteStore {
+        entries: Mutex<HashMap<String, String>>,
+        gets: Mutex<Vec<String>>,
+        fail: bool,
+    }
+
+    impl CacheBackend for RemoteStore {
+        fn get(&self, key: &str, ttl: u64) -> Result<Option<String>, String> {
+            let key = format!("{}:{}", key, ttl);
+            self.gets.lock().unwrap().push(key.clone());
+            if self.fail {
+                return Err("store unavailable".to_string());
+            }
+            Ok(self.entries.lock().unwrap().get(&key).cloned())
+        }
+
+        fn set(&self, key: &str, ttl: u64, content: &str) -> Result<(), String> {
+            if self.fail {
+                return Err("store unavailable".to_string());
+            }
+            let key = format!("{}:{}", key, ttl);
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key, content.to_string());
+            Ok(())
+        }
+    }
+
+    fn temp_cache_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "neutral-cache-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_file_cache() {
+        let dir = temp_cache_dir("file");
+        let cache = FileCache::new(&dir);
+
+        assert_eq!(
+            cache.path(KEY, 60),
+            dir.join("abc").join(format!("{}-60", KEY))
+        );
+        assert_eq!(cache.get(KEY, 60), Ok(None));
+
+        cache.set(KEY, 60, "<div>cached</div>").unwrap();
+        assert_eq!(
+            cache.get(KEY, 60),
+            Ok(Some("<div>cached</div>".to_string()))
+        );
+        assert_eq!(cache.get(KEY, 120), Ok(None));
+
+        // Expired by the modification time.
+        let file = fs::File::options()
+            .write(true)
+            .open(cache.path(KEY, 60))
+            .unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(61))
+            .unwrap();
+        assert_eq!(cache.get(KEY, 60), Ok(None));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_cache_from_config() {
+        let cache = FileCache::from_config("/tmp", "neutral-cache");
+        assert_eq!(
+            cache.path(KEY, 5).to_str().unwrap(),
+            format!("/tmp/neutral-cache/abc/{}-5", KEY)
+        );
+
+        let cache = FileCache::from_config("/tmp", "");
+        assert_eq!(
+            cache.path(KEY, 5).to_str().unwrap(),
+            format!("/tmp/abc/{}-5", KEY)
+        );
+    }
+
+    #[test]
+    fn test_memory_cache() {
+        let cache = MemoryCache::new(10);
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(KEY, 60), Ok(None));
+
+        cache.set(KEY, 60, "foo").unwrap();
+        cache.set(KEY, 120, "bar").unwrap();
+        assert_eq!(cache.get(KEY, 60), Ok(Some("foo".to_string())));
+        assert_eq!(cache.get(KEY, 120), Ok(Some("bar".to_string())));
+        assert_eq!(cache.len(), 2);
+
+        cache.set(KEY, 60, "baz").unwrap();
+        assert_eq!(cache.get(KEY, 60), Ok(Some("baz".to_string())));
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_memory_cache_lru() {
+        let cache = MemoryCache::new(2);
+
+        cache.set("a", 60, "a").unwrap();
+        cache.set("b", 60, "b").unwrap();
+        // "a" is used, "b" is now the least recently used.
+        assert!(cache.get("a", 60).unwrap().is_some());
+        cache.set("c", 60, "c").unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b", 60), Ok(None));
+        assert!(cache.get("a", 60).unwrap().is_some());
+        assert!(cache.get("c", 60).unwrap().is_some());
+
+        let cache = MemoryCache::new(0);
+        cache.set("a", 60, "a").unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_memory_cache_expires() {
+        let cache = MemoryCache::new(10);
+
+        cache.set(KEY, 0, "foo").unwrap();
+        assert_eq!(cache.get(KEY, 0), Ok(Some("foo".to_string())));
+
+        thread::sleep(Duration::from_millis(1100));
+        assert_eq!(cache.get(KEY, 0), Ok(None));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_cache_backend_memory_template() {
+        let cache = Arc::new(MemoryCache::new(10));
+        let src = "<div>{:cache; /60/ >> {:;inject:} :}</div>";
+
+        for _ in 0..2 {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA_CACHE).unwrap();
+            template.set_cache_backend(Arc::clone(&cache));
+            template.set_src_str(src);
+            let result = template.render();
+            assert!(!template.has_error());
+            assert_eq!(result, "<div>&#123;:exit; 403 :&#125;</div>");
+        }
+
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_backend_custom() {
+        let store = Arc::new(RemoteStore::default());
+        let src = "<div>{:cache; /60/my-id/1/ >> {:code; {:;__test-nts:} :} :}</div>";
+
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA_CACHE).unwrap();
+        template.set_cache_backend(Arc::clone(&store));
+        template.set_src_str(src);
+        assert_eq!(template.render(), "<div>nts</div>");
+        assert_eq!(store.entries.lock().unwrap().len(), 1);
+
+        // Another server, the fragment comes from the store.
+        store
+            .entries
+            .lock()
+            .unwrap()
+            .values_mut()
+            .for_each(|v| *v = "from store".to_string());
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA_CACHE).unwrap();
+        template.set_cache_backend(Arc::clone(&store));
+        template.set_src_str(src);
+        assert_eq!(template.render(), "<div>from store</div>");
+
+        let gets = store.gets.lock().unwrap();
+        assert_eq!(gets.len(), 2);
+        assert!(gets[0].ends_with(":60"));
+    }
+
+    #[test]
+    fn test_cache_backend_custom_errors() {
+        let store = RemoteStore {
+            fail: true,
+            ..Default::default()
+        };
+
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA_CACHE).unwrap();
+        template.set_cache_backend(store);
+        template.set_src_str("<div>{:cache; /60/ >> {:;__test-nts:} :}</div>");
+        assert_eq!(template.render(), "<div>nts</div>");
+        assert!(template.has_error());
+        assert_eq!(template.get_errors()[0].bif_name, "cache");
+        assert_eq!(template.get_errors()[0].message, "store unavailable");
+    }
+
+    #[test]
+    fn test_cache_backend_engine() {
+        let cache = Arc::new(MemoryCache::new(10));
+        let mut engine = crate::Engine::new().unwrap();
+        engine.merge_schema_value(serde_json::from_str(SCHEMA_CACHE).unwrap());
+        engine.set_cache_backend(Arc::clone(&cache));
+
+        let mut template = engine.context_str("{:cache; /60/ >> foo :}").unwrap();
+        assert_eq!(template.render(), "foo");
+        assert_eq!(cache.len(), 1);
+    }
+
+    // A cache file modified `age` seconds ago.
+    fn cache_file(dir: &std::path::Path, key: &str, ttl: u64, age: u64, content: &str) {
+        let path = FileCache::new(dir).path(key, ttl);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let dir = temp_cache_dir("purge");
+        cache_file(&dir, "abc111", 60, 120, "expired");
+        cache_file(&dir, "abc222", 60, 10, "valid");
+        cache_file(&dir, "def333", 60, 61, "expired");
+        // Not cache files, they are kept.
+        fs::write(dir.join("abc").join("notes.txt"), "keep").unwrap();
+        fs::create_dir_all(dir.join("xyz")).unwrap();
+        fs::write(dir.join("xyz").join("abc444-1"), "keep").unwrap();
+
+        let stats = purge_expired(dir.to_str().unwrap(), "").unwrap();
+        assert_eq!(
+            stats,
+            PurgeStats {
+                files: 3,
+                bytes: 19,
+                expired: 2,
+                evicted: 0,
+                removed_bytes: 14,
+                dirs_removed: 1,
+            }
+        );
+        assert!(dir.join("abc").join("abc222-60").exists());
+        assert!(dir.join("abc").join("notes.txt").exists());
+        assert!(!dir.join("abc").join("abc111-60").exists());
+        assert!(!dir.join("def").exists());
+        assert!(dir.join("xyz").join("abc444-1").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_max_size() {
+        let dir = temp_cache_dir("purge-size");
+        cache_file(&dir, "aaa1", 600, 30, "0123456789");
+        cache_file(&dir, "bbb2", 600, 20, "0123456789");
+        cache_file(&dir, "ccc3", 600, 10, "0123456789");
+        cache_file(&dir, "ddd4", 5, 10, "0123456789");
+
+        let options = PurgeOptions {
+            max_size: Some(15),
+            dry_run: true,
+        };
+        let stats = purge(dir.to_str().unwrap(), "", &options).unwrap();
+        assert_eq!((stats.expired, stats.evicted), (1, 2));
+        assert_eq!(stats.removed_bytes, 30);
+        assert_eq!(stats.dirs_removed, 0);
+        assert!(dir.join("ddd").join("ddd4-5").exists());
+
+        let options = PurgeOptions {
+            max_size: Some(15),
+            dry_run: false,
+        };
+        let stats = purge(dir.to_str().unwrap(), "", &options).unwrap();
+        assert_eq!((stats.expired, stats.evicted), (1, 2));
+        assert_eq!(stats.dirs_removed, 3);
+        // The newest one is kept.
+        assert!(dir.join("ccc").join("ccc3-600").exists());
+        assert!(!dir.join("aaa").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_safe_dir() {
+        let error = purge_expired("/tmp", "").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("neutral-cache"));
+
+        let error = purge_expired("/tmp/not-found", "neutral-cache-none").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_purge_on_write() {
+        let dir = temp_cache_dir("sweep");
+        cache_file(&dir, "abc111", 60, 120, "expired");
+
+        let mut schema: serde_json::Value = serde_json::from_str(SCHEMA_CACHE).unwrap();
+        schema["config"]["cache_dir"] = dir.to_str().unwrap().into();
+        schema["config"]["cache_prefix"] = "".into();
+        schema["config"]["cache_purge_every"] = 1.into();
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_value(schema);
+        template.set_src_str("{:cache; /60/ >> foo :}");
+        assert_eq!(template.render(), "foo");
+        assert!(!template.has_error());
+
+        // In the background.
+        for _ in 0..50 {
+            if !dir.join("abc").join("abc111-60").exists() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(!dir.join("abc").join("abc111-60").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // A slow store, the renders of the same fragment overlap.
+    #[derive(Default)]
+    struct SlowStore {
+        entries: Mutex<HashMap<String, String>>,
+        sets: Mutex<u32>,
+        stale: Option<String>,
+    }
+
+    impl CacheBackend for SlowStore {
+        fn get(&self, key: &str, _ttl: u64) -> Result<Option<String>, String> {
+            thread::sleep(Duration::from_millis(50));
+            Ok(self.entries.lock().unwrap().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, _ttl: u64, content: &str) -> Result<(), String> {
+            thread::sleep(Duration::from_millis(300));
+            *self.sets.lock().unwrap() += 1;
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), content.to_string());
+            Ok(())
+        }
+
+        fn get_stale(&self, _key: &str, _ttl: u64) -> Result<Option<String>, String> {
+            Ok(self.stale.clone())
+        }
+    }
+
+    fn render_concurrently(store: &Arc<SlowStore>, src: &str) -> Vec<String> {
+        let barrier = Arc::new(std::sync::Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let store = Arc::clone(store);
+                let barrier = Arc::clone(&barrier);
+                let src = src.to_string();
+                thread::spawn(move || {
+                    let mut template = crate::Template::new().unwrap();
+                    template.merge_schema_str(SCHEMA_CACHE).unwrap();
+                    template.set_cache_backend(store);
+                    template.set_src_str(&src);
+                    barrier.wait();
+                    template.render()
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_cache_single_flight_wait() {
+        let store = Arc::new(SlowStore::default());
+        let outs = render_concurrently(&store, "{:cache; /60/ >> wait :}");
+
+        assert!(outs.iter().all(|out| out == "wait"));
+        assert_eq!(*store.sets.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_cache_single_flight_stale() {
+        let store = Arc::new(SlowStore {
+            stale: Some("stale".to_string()),
+            ..Default::default()
+        });
+        let outs = render_concurrently(&store, "{:cache; /60/ >> fresh :}");
+
+        assert_eq!(outs.iter().filter(|out| *out == "fresh").count(), 1);
+        assert_eq!(outs.iter().filter(|out| *out == "stale").count(), 7);
+        assert_eq!(*store.sets.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_file_cache_atomic_write() {
+        let dir = temp_cache_dir("atomic");
+        let cache = Arc::new(FileCache::new(&dir));
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let cache = Arc::clone(&cache);
+                thread::spawn(move || {
+                    let content = i.to_string().repeat(100_000);
+                    for _ in 0..10 {
+                        cache.set(KEY, 60, &content).unwrap();
+                        // Never partially written.
+                        let read = cache.get(KEY, 60).unwrap().unwrap();
+                        assert_eq!(read.len(), 100_000);
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        // No temporary files left.
+        assert_eq!(fs::read_dir(dir.join("abc")).unwrap().count(), 1);
+
+        // Expired, but served while another render fills it.
+        let file = fs::File::options()
+            .write(true)
+            .open(cache.path(KEY, 60))
+            .unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(61))
+            .unwrap();
+        assert_eq!(cache.get(KEY, 60), Ok(None));
+        assert_eq!(cache.get_stale(KEY, 60).unwrap().unwrap().len(), 100_000);
+        assert_eq!(cache.get_stale(KEY, 120), Ok(None));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_temp_files() {
+        let dir = temp_cache_dir("purge-temp");
+        cache_file(&dir, "abc111", 60, 10, "valid");
+        let old_temp = dir.join("abc").join(".abc111-60.1.0.tmp");
+        let new_temp = dir.join("abc").join(".abc111-60.1.1.tmp");
+        fs::write(&old_temp, "crash").unwrap();
+        fs::write(&new_temp, "writing").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&old_temp)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3601))
+            .unwrap();
+
+        let stats = purge_expired(dir.to_str().unwrap(), "").unwrap();
+        assert_eq!((stats.files, stats.expired), (1, 0));
+        assert!(!old_temp.exists());
+        assert!(new_temp.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_cache_tags() {
+        let dir = temp_cache_dir("tags");
+        let cache = FileCache::new(&dir);
+        let tags = |t: &[&str]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        cache.set("abc1", 60, "product").unwrap();
+        cache
+            .tag("abc1", 60, &tags(&["product-42", "catalog"]))
+            .unwrap();
+        cache.tag("abc1", 60, &tags(&["product-42"])).unwrap();
+        cache.set("def2", 60, "list").unwrap();
+        cache.tag("def2", 60, &tags(&["catalog"])).unwrap();
+        assert_eq!(fs::read_dir(dir.join("tags")).unwrap().count(), 2);
+
+        assert_eq!(cache.invalidate_tags(&["product-42"]), Ok(1));
+        assert_eq!(cache.get("abc1", 60), Ok(None));
+        assert!(cache.get("def2", 60).unwrap().is_some());
+        assert_eq!(cache.invalidate_tags(&["product-42", "none"]), Ok(0));
+        assert_eq!(cache.invalidate_tags(&["catalog"]), Ok(1));
+        assert_eq!(cache.get("def2", 60), Ok(None));
+        assert_eq!(fs::read_dir(dir.join("tags")).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_memory_cache_tags() {
+        let cache = MemoryCache::new(2);
+        let tags = vec!["catalog".to_string()];
+
+        cache.set("a", 60, "a").unwrap();
+        cache.tag("a", 60, &tags).unwrap();
+        cache.set("b", 60, "b").unwrap();
+        cache.tag("b", 60, &tags).unwrap();
+        cache.set("c", 60, "c").unwrap();
+
+        // "a" is no longer stored, "c" has no tags.
+        assert_eq!(cache.invalidate_tags(&["catalog"]), Ok(1));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("c", 60).unwrap().is_some());
+        assert_eq!(cache.invalidate_tags(&["catalog"]), Ok(0));
+    }
+
+    #[test]
+    fn test_cache_tags_template() {
+        let dir = temp_cache_dir("tags-template");
+        let mut schema: serde_json::Value = serde_json::from_str(SCHEMA_CACHE).unwrap();
+        schema["config"]["cache_dir"] = dir.to_str().unwrap().into();
+        schema["data"]["id"] = "42".into();
+        let render = |src: &str| {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_value(schema.clone());
+            template.set_src_str(src);
+            let out = template.render();
+            assert!(!template.has_error(), "{:?}", template.get_error());
+            (template, out)
+        };
+        let product = "{:cache; {:flg; tags product-{:;id:} catalog :} /60/ >> {:date; %f :} :}";
+
+        let (_, first) = render(product);
+        assert_eq!(render(product).1, first);
+
+        let (template, _) = render("");
+        assert_eq!(template.invalidate_cache_tags(&["product-41"]), Ok(0));
+        assert_eq!(template.invalidate_cache_tags(&["product-42"]), Ok(1));
+        assert_ne!(render(product).1, first);
+
+        // Tags of a custom backend.
+        let store = RemoteStore::default();
+        let mut template = crate::Template::new().unwrap();
+        template.set_cache_backend(store);
+        assert_eq!(
+            template.invalidate_cache_tags(&["catalog"]),
+            Err("the cache backend does not support tags".to_string())
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_tags() {
+        let dir = temp_cache_dir("purge-tags");
+        let cache = FileCache::new(&dir);
+        cache_file(&dir, "abc111", 60, 10, "valid");
+        cache_file(&dir, "def222", 60, 120, "expired");
+        cache.tag("abc111", 60, &["a".to_string()]).unwrap();
+        cache.tag("def222", 60, &["b".to_string()]).unwrap();
+        for tag in fs::read_dir(dir.join("tags")).unwrap() {
+            fs::File::options()
+                .write(true)
+                .open(tag.unwrap().path())
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(3601))
+                .unwrap();
+        }
+
+        purge_expired(dir.to_str().unwrap(), "").unwrap();
+        assert_eq!(fs::read_dir(dir.join("tags")).unwrap().count(), 1);
+        assert_eq!(cache.invalidate_tags(&["a"]), Ok(1));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_tags_compact() {
+        let dir = temp_cache_dir("purge-tags-compact");
+        let cache = FileCache::new(&dir);
+        cache_file(&dir, "abc111", 60, 10, "valid");
+        cache_file(&dir, "def222", 60, 120, "expired");
+        cache
+            .tag("abc111", 60, &["a".to_string(), "b".to_string()])
+            .unwrap();
+        cache.tag("def222", 60, &["a".to_string()]).unwrap();
+
+        // The index in use is compacted, not only the dead ones.
+        purge_expired(dir.to_str().unwrap(), "").unwrap();
+        let index = fs::read_to_string(cache.tag_path("a")).unwrap();
+        assert_eq!(index, "abc111-60\n");
+        assert_eq!(fs::read_dir(dir.join("tags")).unwrap().count(), 2);
+
+        // A fragment tagged after the index is taken goes to a new one.
+        assert_eq!(cache.invalidate_tags(&["a"]), Ok(1));
+        cache_file(&dir, "abc333", 60, 10, "new");
+        cache.tag("abc333", 60, &["a".to_string()]).unwrap();
+        assert_eq!(
+            fs::read_to_string(cache.tag_path("a")).unwrap(),
+            "abc333-60\n"
+        );
+        assert_eq!(fs::read_dir(dir.join("tags")).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_inspect() {
+        let dir = temp_cache_dir("inspect");
+        cache_file(&dir, "def222", 60, 120, "expired");
+        cache_file(&dir, "abc111", 60, 10, "valid");
+        cache_file(&dir, "abc111", 300, 10, "other ttl");
+        fs::write(dir.join("abc").join("notes.txt"), "not a fragment").unwrap();
+
+        let entries = inspect(&dir).unwrap();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|e| (e.key.as_str(), e.ttl, e.size, e.expired))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("abc111", 60, 5, false),
+                ("abc111", 300, 9, false),
+                ("def222", 60, 7, true),
+            ]
+        );
+        assert!((10..15).contains(&entries[0].age));
+        assert_eq!(entries[2].path, dir.join("def").join("def222-60"));
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(inspect(&dir).is_err());
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let cache = Arc::new(MemoryCache::new(10));
+        let src = "{:cache; /60/ >> foo :}{:cache; /60/ >> {:;__test-nts:} :}";
+        let render = || {
+            let mut template = crate::Template::new().unwrap();
+            template.merge_schema_str(SCHEMA_CACHE).unwrap();
+            template.set_cache_backend(Arc::clone(&cache));
+            template.set_src_str(src);
+            assert_eq!(template.render(), "foonts");
+            template.get_cache_stats().clone()
+        };
+
+        let stats = render();
+        assert_eq!((stats.hits, stats.misses, stats.writes), (0, 2, 2));
+        assert_eq!(stats.bytes_served, 0);
+
+        let stats = render();
+        assert_eq!(
+            stats,
+            CacheStats {
+                hits: 2,
+                bytes_served: 6,
+                ..Default::default()
+            }
+        );
+
+        let mut template = crate::Template::new().unwrap();
+        template.set_cache_backend(RemoteStore {
+            fail: true,
+            ..Default::default()
+        });
+        template.set_src_str(src);
+        template.render();
+        assert_eq!(template.get_cache_stats().read_errors, 2);
+
+        let mut template = crate::Template::new().unwrap();
+        // Not a directory, it cannot be written.
+        template.set_cache_backend(FileCache::new("/dev/null/neutral-cache"));
+        template.set_src_str(src);
+        template.render();
+        let stats = template.get_cache_stats();
+        assert_eq!((stats.misses, stats.writes, stats.write_errors), (2, 0, 2));
+    }
+}
@@ -0,0 +1,353 @@
+//! Static check of templates, see `Template::check`.
+//!
+//! The template and the files it includes are compiled and walked without
+//! parsing them, every bif is checked, also those in the code paths that
+//! a render does not reach:
+//!
+//! ```text
+//!  {:bool; logged >>
+//!      {:&include; user.ntpl :}      modifier not allowed
+//!  :}{:else;
+//!      {:code; {:flg; raw :} >> ...  raw flag not allowed
+//!  :}
+//! ```
+//!
+//! Only the included files whose name does not depend on the schema are
+//! checked.
+
+use crate::{
+    bif::constants::*, bif::rules::*, compiled::compile_file_cached, compiled::CompiledBlock,
+    compiled::CompiledTemplate, constants::*, render_error::*, shared::Shared,
+};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+// Where the blocks being checked are.
+#[derive(Clone)]
+struct Scope {
+    doc: Arc<CompiledTemplate>,
+    // Position in the document of the source of the blocks.
+    offset: usize,
+    current_file: String,
+    current_dir: String,
+    in_snippet: bool,
+}
+
+pub(crate) struct Checker<'a> {
+    shared: &'a Shared,
+    errors: Vec<RenderError>,
+    checked_files: HashSet<String>,
+    // Snippets set with arguments, {:snippet; name >> args :} plays them.
+    snippets_args: HashSet<String>,
+}
+
+impl<'a> Checker<'a> {
+    pub(crate) fn new(shared: &'a Shared) -> Self {
+        Checker {
+            shared,
+            errors: Vec::new(),
+            checked_files: HashSet::new(),
+            snippets_args: HashSet::new(),
+        }
+    }
+
+    // Checks a template and the files it includes.
+    pub(crate) fn check(
+        mut self,
+        doc: Arc<CompiledTemplate>,
+        current_file: &str,
+        current_dir: &str,
+    ) -> Vec<RenderError> {
+        let scope = Scope {
+            doc: Arc::clone(&doc),
+            offset: 0,
+            current_file: current_file.to_string(),
+            current_dir: current_dir.to_string(),
+            in_snippet: false,
+        };
+
+        if let Some(canonical_path) = self.shared.loader.canonicalize(current_file) {
+            self.checked_files.insert(canonical_path);
+        }
+        self.check_tree(&doc, &scope);
+
+        self.errors
+    }
+
+    fn check_tree(&mut self, tree: &CompiledTemplate, scope: &Scope) {
+        let blocks = match tree.blocks() {
+            Ok(blocks) => blocks,
+            Err(p) => {
+                self.add_error(
+                    scope,
+                    p,
+                    RenderErrorKind::UnmatchedBlock,
+                    "",
+                    "Unmatched block",
+                    "",
+                );
+                return;
+            }
+        };
+
+        for block in blocks {
+            self.check_block(block, scope);
+        }
+    }
+
+    fn check_block(&mut self, block: &CompiledBlock, scope: &Scope) {
+        if block.is_comment() {
+            return;
+        }
+
+        let span = block.span();
+        let raw = &scope.doc.source()[scope.offset + span.start..scope.offset + span.end];
+
+        if !block.is_delimited() {
+            self.add_error(
+                scope,
+                span.start,
+                RenderErrorKind::Delimiter,
+                "",
+                "The delimiter was not found",
+                raw,
+            );
+            return;
+        }
+
+        let name = block.name();
+        let is_custom = self.shared.bif_handlers.contains_key(name);
+        let rules = bif_rules(name);
+        let bif_error = |checker: &mut Self, msg: &str| {
+            checker.add_error(scope, span.start, RenderErrorKind::Bif, name, msg, raw);
+        };
+
+        let flags = flags_of(block);
+
+        if let Some((modifiers, flags_allowed)) = rules {
+            if !modifiers_allowed(modifiers, block.modifiers()) {
+                bif_error(self, BIF_ERROR_MODIFIER_NOT_ALLOWED);
+            }
+
+            for error in flag_errors(flags_allowed, flags.iter().map(String::as_str)) {
+                bif_error(self, &error);
+            }
+        } else if !is_custom {
+            bif_error(self, BIF_ERROR_UNKNOWN_BIF);
+        }
+
+        let mut in_snippet = scope.in_snippet;
+
+        match name {
+            "count" => bif_error(self, BIF_ERROR_BIF_DEPRECATED),
+            // Only the first word of the flags is a flag, the rest are the tags.
+            "cache" if !flags.is_empty() => {
+                if block.modifiers().negate {
+                    bif_error(self, BIF_ERROR_FLAGS_NOT_ALLOWED);
+                } else if flags[0] != "tags" {
+                    bif_error(self, &format!("{} flag not allowed", flags[0]));
+                }
+            }
+            "declare" if !scope.current_file.contains(SNIPPETS_FILES) => {
+                bif_error(self, BIF_ERROR_DECLARE_SET_HERE);
+            }
+            "snippet" | "snip" if block.has_code() => {
+                let params = params_without_flags(block);
+                let (snip_name, declared) = match params.split_once(char::is_whitespace) {
+                    Some((snip_name, declared)) => (snip_name, declared.trim()),
+                    None => (params.as_str(), ""),
+                };
+
+                let is_play = declared.is_empty() && self.snippets_args.contains(snip_name);
+                if !is_play {
+                    if !declared.is_empty() {
+                        self.snippets_args.insert(snip_name.to_string());
+                    }
+                    if !scope.current_file.contains(SNIPPETS_FILES) && !scope.in_snippet {
+                        bif_error(self, "snippet cannot be set here");
+                    }
+                    in_snippet = true;
+                }
+            }
+            _ => (),
+        }
+
+        let src_offset = scope.offset + span.start + src_position(raw);
+
+        if let Some(tree) = block.params_tree() {
+            let mut params_scope = scope.clone();
+            params_scope.offset = src_offset + block.params_span().start;
+            self.check_tree(tree, &params_scope);
+        }
+
+        // The code of these bifs is not parsed.
+        let noparse = name == "neutral"
+            || (name == "code" && flags.iter().any(|f| f == "noparse"))
+            || (name == "include" && flags.iter().any(|f| f == "safe" || f == "noparse"));
+
+        if let Some(tree) = block.code_tree().filter(|_| !noparse) {
+            let mut code_scope = scope.clone();
+            code_scope.offset = src_offset + block.code_span().start;
+            code_scope.in_snippet = in_snippet;
+            self.check_tree(tree, &code_scope);
+        }
+
+        if (name == "include" && !noparse) || name == "extends" {
+            self.check_file(
+                block,
+                scope,
+                name == "extends" || flags.iter().any(|f| f == "require"),
+            );
+        }
+    }
+
+    // Checks an included file, only if its name is not set by bifs.
+    fn check_file(&mut self, block: &CompiledBlock, scope: &Scope, required: bool) {
+        let file_path = block.code();
+        if file_path.contains(BIF_OPEN) {
+            return;
+        }
+
+        let span = block.span();
+        let raw = &scope.doc.source()[scope.offset + span.start..scope.offset + span.end];
+        let (file_path, canonical_path) =
+            self.shared
+                .resolve_path(&scope.current_dir, file_path, true);
+
+        let canonical_path = match canonical_path {
+            Some(canonical_path) => canonical_path,
+            None => {
+                if required {
+                    self.add_error(
+                        scope,
+                        span.start,
+                        RenderErrorKind::Bif,
+                        block.name(),
+                        BIF_ERROR_FILE_NOT_FOUND,
+                        raw,
+                    );
+                }
+                return;
+            }
+        };
+
+        if !self.shared.is_allowed_path(&canonical_path) {
+            let msg = format!("{}: {}", BIF_ERROR_PATH_NOT_ALLOWED, canonical_path);
+            self.add_error(
+                scope,
+                span.start,
+                RenderErrorKind::Bif,
+                block.name(),
+                &msg,
+                raw,
+            );
+            return;
+        }
+
+        if !self.checked_files.insert(canonical_path.clone()) {
+            return;
+        }
+
+        let remove_comments = self.shared.comments.contains("remove");
+        let doc = match compile_file_cached(
+            self.shared.loader.as_ref(),
+            &canonical_path,
+            remove_comments,
+        ) {
+            Ok(doc) => doc,
+            Err(e) => {
+                let msg = format!("Failed to read file: {}", e);
+                self.add_error(
+                    scope,
+                    span.start,
+                    RenderErrorKind::Bif,
+                    block.name(),
+                    &msg,
+                    raw,
+                );
+                return;
+            }
+        };
+
+        let file_scope = Scope {
+            doc: Arc::clone(&doc),
+            offset: 0,
+            current_dir: Path::new(&file_path)
+                .parent()
+                .map(|parent| parent.display().to_string())
+                .unwrap_or_default(),
+            current_file: file_path,
+            in_snippet: false,
+        };
+        self.check_tree(&doc, &file_scope);
+    }
+
+    fn add_error(
+        &mut self,
+        scope: &Scope,
+        position: usize,
+        kind: RenderErrorKind,
+        bif_name: &str,
+        message: &str,
+        raw: &str,
+    ) {
+        let (line, column) = scope.doc.line_column(scope.offset + position);
+
+        self.errors.push(RenderError {
+            kind,
+            bif_name: bif_name.to_string(),
+            message: message.to_string(),
+            file: scope.doc.file_path().to_string(),
+            line,
+            column,
+            snippet: RenderError::shorten(raw),
+        });
+    }
+}
+
+// The flags set with {:flg; ... :} in the params of a bif.
+fn flags_of(block: &CompiledBlock) -> Vec<String> {
+    let Some(Ok(blocks)) = block.params_tree().map(|tree| tree.blocks()) else {
+        return Vec::new();
+    };
+
+    blocks
+        .iter()
+        .filter(|b| b.is_delimited() && b.name() == "flg" && !b.code().contains(BIF_OPEN))
+        .flat_map(|b| b.code().split_whitespace().map(str::to_string))
+        .collect()
+}
+
+// The params of a bif without the {:flg; ... :}
+fn params_without_flags(block: &CompiledBlock) -> String {
+    let params = block.params();
+    let Some(Ok(blocks)) = block.params_tree().map(|tree| tree.blocks()) else {
+        return params.to_string();
+    };
+
+    let mut result = String::new();
+    let mut last = 0;
+    for flg in blocks
+        .iter()
+        .filter(|b| b.is_delimited() && b.name() == "flg")
+    {
+        result.push_str(&params[last..flg.span().start]);
+        last = flg.span().end;
+    }
+    result.push_str(&params[last..]);
+
+    result.trim().to_string()
+}
+
+// Position of the bif src, after the name separator, in the bif.
+fn src_position(raw: &str) -> usize {
+    let name_end = raw.find(BIF_NAME).map_or(0, |p| p + BIF_NAME.len());
+    let after_name = &raw[name_end..];
+
+    name_end + after_name.len() - after_name.trim_start().len()
+}
+
+#[cfg(test)]
+#[path = "check_tests.rs"]
+mod tests;
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod tests {
+    use crate::bif::constants::*;
+    use crate::bif::rules::BIF_RULES;
+    use crate::bif_handler::BIF_LIST_EXTRA;
+    use crate::constants::*;
+    use crate::render_error::RenderErrorKind;
+    use crate::test_helpers::*;
+    use crate::{MemoryLoader, RenderError};
+
+    fn check(src: &str) -> Vec<RenderError> {
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.set_src_str(src);
+        template.check()
+    }
+
+    fn messages(errors: &[RenderError]) -> Vec<String> {
+        errors
+            .iter()
+            .map(|e| format!("{}: {}", e.bif_name, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_rules_for_all_bifs() {
+        for name in BIF_LIST.iter().chain(BIF_LIST_EXTRA.iter()) {
+            assert!(
+                BIF_RULES.iter().any(|(n, _, _)| n == name),
+                "no rules for {}",
+                name
+            );
+        }
+    }
+
+    // The errors of modifiers and flags of a render and of the check.
+    fn rule_errors(src: &str) -> (Vec<String>, Vec<String>) {
+        let is_rule_error = |e: &RenderError| {
+            e.message == BIF_ERROR_MODIFIER_NOT_ALLOWED
+                || e.message == BIF_ERROR_FLAGS_NOT_ALLOWED
+                || e.message.ends_with(" flag not allowed")
+        };
+        let mut template = crate::Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template
+            .merge_schema_str(r#"{"config": {"cache_disable": true}}"#)
+            .unwrap();
+        template.set_src_str(src);
+        template.render();
+        let rendered: Vec<RenderError> = template
+            .get_errors()
+            .iter()
+            .filter(|e| is_rule_error(e))
+            .cloned()
+            .collect();
+        let checked: Vec<RenderError> = check(src).into_iter().filter(is_rule_error).collect();
+
+        (messages(&rendered), messages(&checked))
+    }
+
+    // The parse_bif_* functions report the errors of BIF_RULES.
+    #[test]
+    fn test_check_rules_match_render() {
+        for (name, _, flags) in BIF_RULES {
+            // Params that reach the checks of modifiers and flags at runtime.
+            let params = match name {
+                "allow" => "_test-nts",
+                "cache" => "/60/",
+                _ => "x",
+            };
+
+            for modifier in ["", "&", "!", "^", "+"] {
+                let src = match name {
+                    "" => format!("{{:{};__test-nts:}}", modifier),
+                    _ => format!("{{:{}{}; {} >> x :}}", modifier, name, params),
+                };
+                let (rendered, checked) = rule_errors(&src);
+                assert_eq!(rendered, checked, "{}", src);
+            }
+
+            let mut test_flags = vec!["not-a-flag"];
+            test_flags.extend(flags.unwrap_or_default());
+            for flag in test_flags {
+                let src = format!("{{:{}; {{:flg; {} :}} {} >> x :}}", name, flag, params);
+                let (rendered, checked) = rule_errors(&src);
+                assert_eq!(rendered, checked, "{}", src);
+            }
+        }
+
+        for src in [
+            "{:cache; {:flg; tags a b :} /60/ >> x :}",
+            "{:!cache; {:flg; tags a :} >> x :}",
+        ] {
+            let (rendered, checked) = rule_errors(src);
+            assert_eq!(rendered, checked, "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_check_ok() {
+        let errors = check(
+            "<div>{:;__test-nts:}{:;:}{:code; {:flg; safe :} >> {:!;__test-nts:} :}\
+             {:^bool; x >> {:+each; __test-arr_nts k v >> {:;local::v:} :} :}{:else; - :}</div>",
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_check_unreached_code() {
+        // the render does not parse the code of bool, the check does
+        let src = "<div>{:bool; not-defined >> {:&include; file.ntpl :}{:unknown-bif; :} :}</div>";
+        let mut template = crate::Template::new().unwrap();
+        template.set_src_str(src);
+        template.render();
+        assert!(!template.has_error());
+
+        let errors = check(src);
+        assert_eq!(
+            messages(&errors),
+            vec!["include: modifier not allowed", "unknown-bif: unknown bif"]
+        );
+        assert_eq!(errors[0].kind, RenderErrorKind::Bif);
+        assert_eq!((errors[0].line, errors[0].column), (1, 29));
+        assert_eq!(errors[0].snippet, "{:&include; file.ntpl :}");
+        assert_eq!((errors[1].line, errors[1].column), (1, 53));
+    }
+
+    #[test]
+    fn test_check_flags() {
+        let errors = check(
+            "{:code; {:flg; safe other :} >> x :}\n\
+             {:date; {:flg; safe :} >> %Y :}\n\
+             {:include; {:flg; noparse :} >> tests/include.txt :}",
+        );
+        assert_eq!(
+            messages(&errors),
+            vec!["code: other flag not allowed", "date: flags not allowed"]
+        );
+        assert_eq!((errors[1].line, errors[1].column), (2, 1));
+    }
+
+    #[test]
+    fn test_check_unmatched_and_delimiter() {
+        let errors = check("<div>\n{:code; {:;x:} :} :}\n{:code; {:name:} :}</div>");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, RenderErrorKind::UnmatchedBlock);
+        assert_eq!((errors[0].line, errors[0].column), (2, 19));
+
+        let errors = check("<div>\n{:code; {:name:} :}</div>");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, RenderErrorKind::Delimiter);
+        assert_eq!((errors[0].line, errors[0].column), (2, 9));
+    }
+
+    #[test]
+    fn test_check_count_deprecated() {
+        let errors = check("{:count; name >> 1 :}");
+        assert_eq!(messages(&errors), vec!["count: Bif 'count' is deprecated"]);
+    }
+
+    #[test]
+    fn test_check_custom_bif() {
+        let mut template = crate::Template::new().unwrap();
+        template
+            .register_bif("price", |_ctx: &mut crate::BifContext| Ok(String::new()))
+            .unwrap();
+        template.set_src_str("{:&price; {:flg; any :} >> 1 :}{:prices; :}");
+        assert_eq!(messages(&template.check()), vec!["prices: unknown bif"]);
+    }
+
+    #[test]
+    fn test_check_includes() {
+        let mut loader = MemoryLoader::new();
+        loader.insert("index.ntpl", "{:include; header.ntpl :}\n{:include; snippets.ntpl :}\n{:snippet; card >> title=x :}{:include; {:;file:} :}{:include; index.ntpl :}");
+        loader.insert(
+            "header.ntpl",
+            "<h1>\n  {:!code; x :}</h1>{:snippet; head >> x :}",
+        );
+        loader.insert(
+            "snippets.ntpl",
+            "{:snippet; card title >> {:;local::title:}{:snippet; inner >> {:date; :} :} :}{:declare; list >> a b :}",
+        );
+
+        let mut template = crate::Template::new().unwrap();
+        template.set_loader(loader);
+        template.set_src_path("index.ntpl").unwrap();
+        let errors = template.check();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "code: modifier not allowed",
+                "snippet: snippet cannot be set here"
+            ]
+        );
+        assert_eq!(errors[0].file, "header.ntpl");
+        assert_eq!((errors[0].line, errors[0].column), (2, 3));
+    }
+
+    #[test]
+    fn test_check_declare_outside_snippets() {
+        let errors = check("{:declare; list >> a b :}");
+        assert_eq!(
+            messages(&errors),
+            vec!["declare: declare cannot be set here"]
+        );
+    }
+
+    #[test]
+    fn test_check_include_require() {
+        let errors =
+            check("{:include; not-found.ntpl :}{:include; {:flg; require :} >> not-found.ntpl :}");
+        assert_eq!(messages(&errors), vec!["include: file not found"]);
+    }
+}
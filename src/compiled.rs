@@ -0,0 +1,608 @@
+//! Compiled templates.
+//!
+//! A `CompiledTemplate` is the parsed form of a template source: the same level
+//! blocks found by `extract_blocks`, each one already split into name, modifiers,
+//! params and code, and the nested blocks of params and code compiled in turn.
+//!
+//! ```text
+//!  CompiledTemplate
+//!  |-- source: "<div>{:code; {:;name:} :}</div>"
+//!  ·-- blocks
+//!      ·-- CompiledBlock: {:code; {:;name:} :}
+//!          |-- name: "code"
+//!          |-- modifiers
+//!          |-- params: ""
+//!          ·-- code: CompiledTemplate
+//!              |-- source: "{:;name:}"
+//!              ·-- blocks
+//!                  ·-- CompiledBlock: {:;name:}
+//! ```
+//!
+//! It is built once and it can be rendered against many schemas, see
+//! `Template::set_src_compiled`. Included files and snippets are compiled on
+//! first use and stored in a process-wide cache.
+
+use crate::{constants::*, loader::TemplateLoader, utils::*};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Max entries in the cache of compiled sources (snippets), when it is reached
+// the least recently used is removed.
+const STR_CACHE_MAX: usize = 2048;
+
+/// Bif modifiers.
+///
+/// ```text
+///   .------ modifiers
+///   |
+///   v
+///  {:!snippet; ...
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub filter: bool,
+    pub negate: bool,
+    pub upline: bool,
+    pub scope: bool,
+}
+
+impl Modifiers {
+    /// Splits the modifiers of a bif name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The bif name as it is written in the template, e.g.: "!snippet".
+    ///
+    /// # Returns
+    ///
+    /// The modifiers and the name without modifiers.
+    pub fn split(name: &str) -> (Modifiers, &str) {
+        let mut modifiers = Modifiers::default();
+        let mut index = 0;
+
+        while index < name.len() {
+            match &name[index..index + 1] {
+                BIF_MOD_FILTER => modifiers.filter = true,
+                BIF_MOD_NEGATE => modifiers.negate = true,
+                BIF_MOD_UPLINE => modifiers.upline = true,
+                BIF_MOD_SCOPE => modifiers.scope = true,
+                _ => break,
+            }
+            index += 1;
+        }
+
+        (modifiers, &name[index..])
+    }
+}
+
+/// A bif of a compiled template.
+#[derive(Debug)]
+pub struct CompiledBlock {
+    span: Range<usize>,
+    is_comment: bool,
+    delimited: bool,
+    full_name: String,
+    name: String,
+    modifiers: Modifiers,
+    src: String,
+    has_code: bool,
+    params: Range<usize>,
+    code: Range<usize>,
+    params_tree: Option<Arc<CompiledTemplate>>,
+    code_tree: Option<Arc<CompiledTemplate>>,
+}
+
+impl CompiledBlock {
+    fn new(source: &str, start: usize, end: usize) -> Self {
+        let raw = &source[start..end];
+        let mut block = CompiledBlock {
+            span: start..end,
+            is_comment: raw.starts_with(BIF_COMMENT_OPEN),
+            delimited: false,
+            full_name: String::new(),
+            name: String::new(),
+            modifiers: Modifiers::default(),
+            src: String::new(),
+            has_code: false,
+            params: 0..0,
+            code: 0..0,
+            params_tree: None,
+            code_tree: None,
+        };
+
+        if block.is_comment {
+            return block;
+        }
+
+        let bif = strip_prefix_suffix(raw, BIF_OPEN, BIF_CLOSE);
+        let (full_name, src) = match bif.split_once(BIF_NAME) {
+            Some(parts) => parts,
+            None => return block,
+        };
+
+        let (modifiers, name) = Modifiers::split(full_name);
+        block.delimited = true;
+        block.full_name = full_name.to_string();
+        block.name = name.to_string();
+        block.modifiers = modifiers;
+        block.src = src.trim().to_string();
+
+        // Same split as Bif::extract_params_code
+        match get_code_position(&block.src) {
+            Some(pos) => {
+                block.has_code = true;
+                block.params = trimmed_range(&block.src, 0..pos);
+                block.code = trimmed_range(&block.src, pos + BIF_CODE.len()..block.src.len());
+            }
+            None => {
+                block.code = 0..block.src.len();
+            }
+        }
+
+        if block.params().contains(BIF_OPEN) {
+            block.params_tree = Some(Arc::new(CompiledTemplate::new(block.params())));
+        }
+
+        if block.code().contains(BIF_OPEN) {
+            block.code_tree = Some(Arc::new(CompiledTemplate::new(block.code())));
+        }
+
+        block
+    }
+
+    /// Position of the bif in the source of the template that contains it.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Whether it is a comment: {:* ... *:}
+    pub fn is_comment(&self) -> bool {
+        self.is_comment
+    }
+
+    /// Whether the name separator was found, a bif without it is an error.
+    pub fn is_delimited(&self) -> bool {
+        self.delimited
+    }
+
+    /// Bif name including modifiers, e.g.: "!snippet"
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+
+    /// Bif name without modifiers, e.g.: "snippet"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Bif modifiers.
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Bif src, everything after the name separator.
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    /// Whether the params/code separator was found.
+    pub fn has_code(&self) -> bool {
+        self.has_code
+    }
+
+    /// Bif params, empty if there is no params/code separator.
+    pub fn params(&self) -> &str {
+        &self.src[self.params.clone()]
+    }
+
+    /// Bif code.
+    pub fn code(&self) -> &str {
+        &self.src[self.code.clone()]
+    }
+
+    /// Position of params in src.
+    pub fn params_span(&self) -> Range<usize> {
+        self.params.clone()
+    }
+
+    /// Position of code in src.
+    pub fn code_span(&self) -> Range<usize> {
+        self.code.clone()
+    }
+
+    /// Compiled params, only if params contains bifs.
+    pub fn params_tree(&self) -> Option<&Arc<CompiledTemplate>> {
+        self.params_tree.as_ref()
+    }
+
+    /// Compiled code, only if code contains bifs.
+    pub fn code_tree(&self) -> Option<&Arc<CompiledTemplate>> {
+        self.code_tree.as_ref()
+    }
+}
+
+/// A template source parsed into a tree of blocks.
+#[derive(Debug)]
+pub struct CompiledTemplate {
+    source: String,
+    file_path: String,
+    blocks: Result<Vec<CompiledBlock>, usize>,
+    // Source before removing comments and the removed spans, used to report
+    // line and column of the original source.
+    original: Option<String>,
+    removed: Vec<(usize, usize)>,
+}
+
+impl CompiledTemplate {
+    /// Compiles a template source.
+    ///
+    /// Compiling never fails, an unmatched block is reported when the template
+    /// is rendered, in the same way as the non-compiled source.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The template source.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::{CompiledTemplate, Template};
+    /// use std::sync::Arc;
+    ///
+    /// let compiled = Arc::new(CompiledTemplate::new("{:code; Hello :}"));
+    ///
+    /// for _ in 0..2 {
+    ///     let mut template = Template::new().unwrap();
+    ///     template.set_src_compiled(compiled.clone());
+    ///     assert_eq!(template.render(), "Hello");
+    /// }
+    /// ```
+    pub fn new(source: &str) -> Self {
+        let blocks = extract_blocks(source).map(|blocks| {
+            blocks
+                .into_iter()
+                .map(|(start, end)| CompiledBlock::new(source, start, end))
+                .collect()
+        });
+
+        CompiledTemplate {
+            source: source.to_string(),
+            file_path: String::new(),
+            blocks,
+            original: None,
+            removed: Vec::new(),
+        }
+    }
+
+    // Compiles a template source removing comments first if required,
+    // the positions are still reported in the original source.
+    pub(crate) fn from_source(source: &str, remove_comments: bool) -> Self {
+        if !remove_comments {
+            return Self::new(source);
+        }
+
+        let spans = comments_spans(source);
+        if spans.is_empty() {
+            return Self::new(source);
+        }
+
+        let mut stripped = String::with_capacity(source.len());
+        let mut removed = Vec::with_capacity(spans.len());
+        let mut prev_end = 0;
+
+        for (start, end) in spans {
+            stripped.push_str(&source[prev_end..start]);
+            removed.push((stripped.len(), end - start));
+            prev_end = end;
+        }
+        stripped.push_str(&source[prev_end..]);
+
+        let mut compiled = Self::new(&stripped);
+        compiled.original = Some(source.to_string());
+        compiled.removed = removed;
+
+        compiled
+    }
+
+    pub(crate) fn set_file_path(&mut self, file_path: &str) {
+        self.file_path = file_path.to_string();
+    }
+
+    /// Compiles a template file.
+    ///
+    /// The file path is kept, it is used as the current file for relative
+    /// paths with "#" when the template is rendered.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - The path of the file containing the template content.
+    /// * `remove_comments` - Remove comments before compiling, see `config.comments`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the compiled template or an error message if the file cannot be read.
+    pub fn from_file(file_path: &str, remove_comments: bool) -> Result<Self, String> {
+        let source = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+        let mut compiled = Self::from_source(&source, remove_comments);
+        compiled.file_path = file_path.to_string();
+
+        Ok(compiled)
+    }
+
+    /// The template source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The template file path, empty if it was not compiled from a file.
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    // The source before removing comments.
+    pub(crate) fn original_source(&self) -> &str {
+        self.original.as_deref().unwrap_or(&self.source)
+    }
+
+    /// Same level blocks, or the position of an unmatched block.
+    pub fn blocks(&self) -> Result<&[CompiledBlock], usize> {
+        match &self.blocks {
+            Ok(blocks) => Ok(blocks),
+            Err(p) => Err(*p),
+        }
+    }
+
+    /// Line and column, starting at 1, of a position in the source.
+    ///
+    /// If the comments were removed when compiling, the line and column are
+    /// those of the source before removing them. The column is counted in
+    /// characters.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte position in `source()`, e.g. the start of a block span.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::CompiledTemplate;
+    ///
+    /// let compiled = CompiledTemplate::new("<div>\n  {:;name:}\n</div>");
+    /// let span = compiled.blocks().unwrap()[0].span();
+    /// assert_eq!(compiled.line_column(span.start), (2, 3));
+    /// ```
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.source.len());
+        let text = match &self.original {
+            Some(original) => {
+                for (at, len) in &self.removed {
+                    if *at > offset {
+                        break;
+                    }
+                    offset += len;
+                }
+                original
+            }
+            None => &self.source,
+        };
+
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let before = &text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        (line, column)
+    }
+
+    /// Empties the cache of compiled files and snippets.
+    ///
+    /// Files are recompiled if they are modified, it is not necessary to call
+    /// this function for that, only to release memory.
+    pub fn clear_cache() {
+        file_cache()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        str_cache()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+// Where a source being parsed comes from: a compiled template, that is a file
+// or the template source, and the position in it.
+#[derive(Debug, Clone)]
+pub(crate) struct SourceOrigin {
+    pub(crate) doc: Arc<CompiledTemplate>,
+    pub(crate) offset: usize,
+}
+
+impl SourceOrigin {
+    pub(crate) fn new(doc: Arc<CompiledTemplate>, offset: usize) -> Self {
+        SourceOrigin { doc, offset }
+    }
+
+    // The origin of the text that starts "offset" bytes later.
+    pub(crate) fn at(&self, offset: usize) -> Self {
+        SourceOrigin {
+            doc: Arc::clone(&self.doc),
+            offset: self.offset + offset,
+        }
+    }
+
+    // Whether the text at this position is the given source.
+    pub(crate) fn is_source_of(&self, source: &str) -> bool {
+        self.doc
+            .source()
+            .get(self.offset..self.offset + source.len())
+            .is_some_and(|s| s == source)
+    }
+
+    pub(crate) fn line_column(&self) -> (usize, usize) {
+        self.doc.line_column(self.offset)
+    }
+}
+
+fn trimmed_range(src: &str, range: Range<usize>) -> Range<usize> {
+    let part = &src[range.clone()];
+    let start = range.start + (part.len() - part.trim_start().len());
+    let end = start + part.trim().len();
+
+    start..end
+}
+
+struct FileEntry {
+    version: Option<u64>,
+    compiled: Arc<CompiledTemplate>,
+}
+
+type FileCache = Mutex<HashMap<(String, bool), FileEntry>>;
+
+fn file_cache() -> &'static FileCache {
+    static CACHE: OnceLock<FileCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// The compiled sources, the key is shared by the map and the LRU list.
+struct StrCache {
+    capacity: usize,
+    entries: HashMap<Arc<str>, StrEntry>,
+    // Last use of each entry, the first is the least recently used.
+    lru: BTreeMap<u64, Arc<str>>,
+    tick: u64,
+}
+
+struct StrEntry {
+    compiled: Arc<CompiledTemplate>,
+    tick: u64,
+}
+
+impl StrCache {
+    fn new(capacity: usize) -> Self {
+        StrCache {
+            capacity,
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, source: &str) -> Option<Arc<CompiledTemplate>> {
+        let entry = self.entries.get_mut(source)?;
+        self.tick += 1;
+
+        if let Some(key) = self.lru.remove(&entry.tick) {
+            self.lru.insert(self.tick, key);
+        }
+        entry.tick = self.tick;
+
+        Some(Arc::clone(&entry.compiled))
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+    }
+
+    fn insert(&mut self, source: &str, compiled: Arc<CompiledTemplate>) {
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.lru.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        self.tick += 1;
+        let key: Arc<str> = Arc::from(source);
+        self.lru.insert(self.tick, Arc::clone(&key));
+        self.entries.insert(
+            key,
+            StrEntry {
+                compiled,
+                tick: self.tick,
+            },
+        );
+    }
+}
+
+fn str_cache() -> &'static Mutex<StrCache> {
+    static CACHE: OnceLock<Mutex<StrCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(StrCache::new(STR_CACHE_MAX)))
+}
+
+// Compile a file on first use, it is compiled again if it has been modified.
+// The path is the canonical path given by the loader.
+pub(crate) fn compile_file_cached(
+    loader: &dyn TemplateLoader,
+    path: &str,
+    remove_comments: bool,
+) -> Result<Arc<CompiledTemplate>, String> {
+    let version = loader.version(path);
+    let key = (path.to_string(), remove_comments);
+    let mut source = None;
+
+    {
+        let cache = file_cache().lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = cache.get(&key) {
+            // Without version, the file is read and compared.
+            let is_valid = match version {
+                Some(_) => entry.version == version,
+                None => {
+                    let content = loader.read(path)?;
+                    let is_same =
+                        entry.version.is_none() && entry.compiled.original_source() == content;
+                    source = Some(content);
+                    is_same
+                }
+            };
+
+            if is_valid {
+                return Ok(Arc::clone(&entry.compiled));
+            }
+        }
+    }
+
+    let source = match source {
+        Some(source) => source,
+        None => loader.read(path)?,
+    };
+    let mut compiled = CompiledTemplate::from_source(&source, remove_comments);
+    compiled.set_file_path(path);
+    let compiled = Arc::new(compiled);
+
+    let mut cache = file_cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache.insert(
+        key,
+        FileEntry {
+            version,
+            compiled: Arc::clone(&compiled),
+        },
+    );
+
+    Ok(compiled)
+}
+
+// Compile a source on first use, used for snippets.
+pub(crate) fn compile_str_cached(source: &str) -> Arc<CompiledTemplate> {
+    let mut cache = str_cache().lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(compiled) = cache.get(source) {
+        return compiled;
+    }
+
+    let compiled = Arc::new(CompiledTemplate::new(source));
+    cache.insert(source, Arc::clone(&compiled));
+
+    compiled
+}
+
+#[cfg(test)]
+#[path = "compiled_tests.rs"]
+mod tests;
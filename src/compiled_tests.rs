@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+    use crate::compiled::*;
+
+    #[test]
+    fn test_str_cache_lru() {
+        let mut cache = StrCache::new(2);
+        cache.insert("one", Arc::new(CompiledTemplate::new("one")));
+        cache.insert("two", Arc::new(CompiledTemplate::new("two")));
+        assert!(cache.get("one").is_some());
+
+        // The least recently used is removed, not the whole cache.
+        cache.insert("three", Arc::new(CompiledTemplate::new("three")));
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.lru.len(), 2);
+        assert!(cache.get("two").is_none());
+        assert_eq!(cache.get("one").unwrap().source(), "one");
+        assert_eq!(cache.get("three").unwrap().source(), "three");
+    }
+}
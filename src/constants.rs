@@ -220,22 +220,25 @@ pub const BIF_ALLOWED: [&str; 2] = ["{:allow;", "{:!allow;"];
 pub const SNIPPETS_FILES: &str = "snippet";
 
 /// bif list
-pub const BIF_LIST: [&str; 36] = [
-    "", "allow", "array", "bool", "cache", "coalesce", "code", "contains", "count", "data", "date",
-    "declare", "defined", "each", "else", "eval", "exit", "fetch", "filled", "flg", "for", "hash",
-    "include", "join", "lang", "locale", "moveto", "neutral", "param", "rand", "redirect",
-    "replace", "same", "snippet", "sum", "trans",
+pub const BIF_LIST: [&str; 41] = [
+    "", "allow", "array", "block", "bool", "cache", "coalesce", "code", "contains", "cookie",
+    "count", "data", "date", "declare", "defined", "each", "else", "eval", "exit", "extends",
+    "fetch", "filled", "flg", "for", "hash", "header", "include", "join", "lang", "locale",
+    "moveto", "neutral", "param", "parent", "rand", "redirect", "replace", "same", "snippet",
+    "sum", "trans",
 ];
 
 /// bif alias list because some bifs have no name
-pub const BIF_ALIAS_LIST: [&str; 37] = [
+pub const BIF_ALIAS_LIST: [&str; 42] = [
     "allow",
     "array",
+    "block",
     "bool",
     "cache",
     "coalesce",
     "code",
     "contains",
+    "cookie",
     "count",
     "data",
     "date",
@@ -245,11 +248,13 @@ pub const BIF_ALIAS_LIST: [&str; 37] = [
     "else",
     "eval",
     "exit",
+    "extends",
     "fetch",
     "filled",
     "flg",
     "for",
     "hash",
+    "header",
     "include",
     "join",
     "lang",
@@ -257,6 +262,7 @@ pub const BIF_ALIAS_LIST: [&str; 37] = [
     "moveto",
     "neutral",
     "param",
+    "parent",
     "rand",
     "redirect",
     "replace",
@@ -19,10 +19,20 @@ pub const DEFAULT: &str = r#"{
         "cache_on_get": true,
         "cache_on_cookies": true,
         "cache_disable": false,
+        "_comment_:cache_purge_every": "removes the expired cache files every N cache writes, 0 never",
+        "cache_purge_every": 0,
+        "_comment_:cache_max_size": "max size in bytes of the cache files kept by the purge, 0 no limit",
+        "cache_max_size": 0,
+        "_comment_:cache_vary": "CONTEXT paths of the cache id, e.g.: COOKIES->theme",
+        "cache_vary": ["POST", "GET", "COOKIES", "HOST", "ROUTE"],
         "filter_all": false,
         "disable_js": false,
         "debug_expire": 3600,
-        "debug_file": ""
+        "debug_file": "",
+        "_comment_:allowed_roots": "directories of the files used by include, locale, data and obj, empty any",
+        "allowed_roots": [],
+        "_comment_:template_dirs": "directories where relative paths are searched, in order",
+        "template_dirs": []
     },
     "data": {
         "CONTEXT": {
@@ -103,6 +113,7 @@ pub const DEFAULT: &str = r#"{
             "__hello-nts": "<div>{:trans; ref:greeting-nts :}</div>"
         },
         "snippets_set_dir": {},
+        "blocks": {},
         "declare": {
             "any": "*",
             "traversal": "/* \\\\* *\\.\\.*"
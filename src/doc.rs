@@ -20,6 +20,8 @@
 //!
 #![doc = include_str!("../doc/bif-array.md")]
 //!
+#![doc = include_str!("../doc/bif-block.md")]
+//!
 #![doc = include_str!("../doc/bif-bool.md")]
 //!
 #![doc = include_str!("../doc/bif-cache.md")]
@@ -30,6 +32,8 @@
 //!
 #![doc = include_str!("../doc/bif-contains.md")]
 //!
+#![doc = include_str!("../doc/bif-cookie.md")]
+//!
 #![doc = include_str!("../doc/bif-count.md")]
 //!
 #![doc = include_str!("../doc/bif-data.md")]
@@ -50,6 +54,8 @@
 //!
 #![doc = include_str!("../doc/bif-exit.md")]
 //!
+#![doc = include_str!("../doc/bif-extends.md")]
+//!
 #![doc = include_str!("../doc/bif-fetch.md")]
 //!
 #![doc = include_str!("../doc/bif-filled.md")]
@@ -60,6 +66,8 @@
 //!
 #![doc = include_str!("../doc/bif-hash.md")]
 //!
+#![doc = include_str!("../doc/bif-header.md")]
+//!
 #![doc = include_str!("../doc/bif-include.md")]
 //!
 #![doc = include_str!("../doc/bif-join.md")]
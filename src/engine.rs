@@ -0,0 +1,248 @@
+//! Thread-safe template engine.
+//!
+//! ```text
+//!                         .-------------------------------.
+//!                         │            Engine             │
+//!                         |-------------------------------|
+//!                         │  base schema: Arc<Value>      │
+//!                         │  loader, bifs, cache backend  │
+//!                         ·-------------------------------·
+//!                            |            |            |
+//!            thread 1 <------·            |            ·------> thread n
+//!       .---------------.         .---------------.        .---------------.
+//!       │   Template    │         │   Template    │        │   Template    │
+//!       │ (per request) │         │ (per request) │        │ (per request) │
+//!       ·---------------·         ·---------------·        ·---------------·
+//! ```
+//!
+//! The engine is `Send + Sync`, it is created once and shared between threads,
+//! for example in an `Arc<Engine>`. Each request gets its own render context,
+//! a `Template` that shares the base schema and the compiled template.
+
+use crate::{
+    bif_handler::*, cache::*, compiled::compile_file_cached, compiled::CompiledTemplate, loader::*,
+    shared::BaseSchema, template::default_schema_template, template::Template, utils::*,
+};
+use serde_json::Value;
+use std::fs;
+use std::sync::Arc;
+
+/// A thread-safe engine that holds the base schema and the compiled templates.
+///
+/// # Example
+///
+/// ```
+/// use neutralts::Engine;
+/// use serde_json::json;
+/// use std::sync::Arc;
+///
+/// let mut engine = Engine::new().unwrap();
+/// engine.merge_schema_value(json!({ "data": { "site": "MySite" } }));
+/// let engine = Arc::new(engine);
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|n| {
+///         let engine = Arc::clone(&engine);
+///         std::thread::spawn(move || {
+///             let mut template = engine.context_str("{:;site:}-{:;n:}").unwrap();
+///             template.merge_schema_value(json!({ "data": { "n": n } }));
+///             template.render_once()
+///         })
+///     })
+///     .collect();
+///
+/// for (n, handle) in handles.into_iter().enumerate() {
+///     assert_eq!(handle.join().unwrap(), format!("MySite-{}", n));
+/// }
+/// ```
+pub struct Engine {
+    schema: BaseSchema,
+    bif_handlers: BifHandlers,
+    loader: SharedLoader,
+    cache: Option<SharedCache>,
+}
+
+impl Engine {
+    /// Constructs a new `Engine` instance with the default schema.
+    pub fn new() -> Result<Self, String> {
+        Ok(Engine {
+            schema: BaseSchema::new(default_schema_template()?),
+            bif_handlers: BifHandlers::default(),
+            loader: default_loader(),
+            cache: None,
+        })
+    }
+
+    /// Constructs a new `Engine` instance from a JSON schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - A JSON value merged over the default schema.
+    pub fn from_value(schema: Value) -> Result<Self, String> {
+        let mut engine = Self::new()?;
+        engine.merge_schema_value(schema);
+
+        Ok(engine)
+    }
+
+    /// Merges the provided JSON value with the base schema.
+    ///
+    /// The render contexts already created keep the previous base schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The JSON Value to be merged with the base schema.
+    pub fn merge_schema_value(&mut self, schema: Value) {
+        self.schema.merge(schema);
+    }
+
+    /// Merges the schema from a JSON string with the base schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - A reference to the JSON string of the schema content.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error message if:
+    /// - The file's content is not a valid JSON string.
+    pub fn merge_schema_str(&mut self, schema: &str) -> Result<(), String> {
+        let schema_value: Value =
+            serde_json::from_str(schema).map_err(|_| "Is not a valid JSON string".to_string())?;
+        self.merge_schema_value(schema_value);
+
+        Ok(())
+    }
+
+    /// Merges the schema from a JSON file with the base schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema_path` - A reference to the path of the file containing the schema content.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error message if:
+    /// - The file cannot be read.
+    /// - The file's content is not a valid JSON string.
+    pub fn merge_schema_path(&mut self, schema_path: &str) -> Result<(), String> {
+        let schema_bytes = fs::read(schema_path).map_err(|e| e.to_string())?;
+        let schema_value: Value = serde_json::from_slice(&schema_bytes)
+            .map_err(|_| "Is not a valid JSON file".to_string())?;
+        self.merge_schema_value(schema_value);
+
+        Ok(())
+    }
+
+    /// Registers a custom bif for all the render contexts, see `Template::register_bif`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The bif name, it cannot be the name of a built-in bif.
+    /// * `handler` - The bif handler, e.g. a closure.
+    pub fn register_bif<H>(&mut self, name: &str, handler: H) -> Result<(), String>
+    where
+        H: BifHandler + 'static,
+    {
+        check_bif_name(name)?;
+        Arc::make_mut(&mut self.bif_handlers).insert(name.to_string(), Arc::new(handler));
+
+        Ok(())
+    }
+
+    /// Sets the loader used to read the template files, see `TemplateLoader`.
+    ///
+    /// # Arguments
+    ///
+    /// * `loader` - The loader, e.g. `MemoryLoader` or `EmbeddedLoader`.
+    pub fn set_loader<L>(&mut self, loader: L)
+    where
+        L: TemplateLoader + 'static,
+    {
+        self.loader = Arc::new(loader);
+    }
+
+    /// Sets the backend of the bif cache for all the templates, see `CacheBackend`.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The backend, e.g. `MemoryCache`.
+    pub fn set_cache_backend<C>(&mut self, backend: C)
+    where
+        C: CacheBackend + 'static,
+    {
+        self.cache = Some(Arc::new(backend));
+    }
+
+    /// Retrieves the base schema.
+    pub fn get_schema(&self) -> Arc<Value> {
+        Arc::clone(&self.schema.schema)
+    }
+
+    /// Compiles a template file, or gets it if it has already been compiled
+    /// and has not been modified since, like the files of `{:include; ... :}`.
+    ///
+    /// Comments are removed according to `config.comments` of the base schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A reference to the path of the file containing the template content.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the compiled template or an error message if the file cannot be read.
+    pub fn compile(&self, file_path: &str) -> Result<Arc<CompiledTemplate>, String> {
+        let remove_comments =
+            get_from_key(&self.schema.schema["config"], "comments").contains("remove");
+        let path = self
+            .loader
+            .canonicalize(file_path)
+            .unwrap_or_else(|| file_path.to_string());
+
+        compile_file_cached(self.loader.as_ref(), &path, remove_comments)
+    }
+
+    /// Creates a render context for a template file.
+    ///
+    /// It is cheap, the base schema and the compiled template are shared.
+    /// The request data is merged into the context with `merge_schema_*`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A reference to the path of the file containing the template content.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Template` or an error message if the file cannot be read.
+    pub fn context(&self, file_path: &str) -> Result<Template, String> {
+        let compiled = self.compile(file_path)?;
+
+        self.context_compiled(compiled)
+    }
+
+    /// Creates a render context for a template source.
+    ///
+    /// The source is compiled on each call, use `context` or
+    /// `context_compiled` for templates that are rendered many times.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The template source.
+    pub fn context_str(&self, source: &str) -> Result<Template, String> {
+        self.context_compiled(Arc::new(CompiledTemplate::new(source)))
+    }
+
+    /// Creates a render context for a compiled template.
+    ///
+    /// # Arguments
+    ///
+    /// * `compiled` - The compiled template.
+    pub fn context_compiled(&self, compiled: Arc<CompiledTemplate>) -> Result<Template, String> {
+        let mut template = Template::from_compiled_base(self.schema.clone(), compiled)?;
+        template.set_bif_handlers(Arc::clone(&self.bif_handlers));
+        template.set_shared_loader(Arc::clone(&self.loader));
+        template.set_shared_cache(self.cache.clone());
+
+        Ok(template)
+    }
+}
@@ -0,0 +1,527 @@
+//! IPC server and client, enabled with the "ipc" feature.
+//!
+//! The server renders templates for applications written in other languages,
+//! over TCP or a Unix socket. Each message is a frame:
+//!
+//! ```text
+//!  .--------.-------------------.------------------------------.
+//!  │ format │ length (u32, BE)  │ payload                      │
+//!  ·--------·-------------------·------------------------------·
+//!    1 byte       4 bytes          length bytes
+//!
+//!  format: b'J' JSON, b'M' MessagePack
+//! ```
+//!
+//! The request payload is a map with the template file and the schema:
+//!
+//! ```text
+//!  {"template": "index.ntpl", "schema": {"data": {...}}}
+//! ```
+//!
+//! The template file is relative to the root of the server, see `ServerOptions`,
+//! and the files it uses must also be in the root, it is the only entry of
+//! `config.allowed_roots`. Only the config keys in `CLIENT_CONFIG_KEYS` are
+//! taken from the request schema, e.g.: the client cannot set `cache_dir`,
+//! and from `inherit` only `locale.current`, e.g.: the client cannot change
+//! the lists of `declare` used by `{:allow; ... :}`.
+//!
+//! The response uses the format of the request, it is `{"ok": Response}`, or
+//! `{"error": "message"}` if the request cannot be rendered, e.g.: the file
+//! cannot be read. A connection can send any number of requests, the server
+//! answers them in order.
+//!
+//! Without a root the server renders any file the process can read, bind it
+//! to localhost or to a Unix socket.
+
+use crate::{render_error::RenderError, template::Template, utils::is_path_in_roots};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// Max length of a frame payload, 64 MiB.
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Default max number of connections answered at the same time.
+pub const MAX_CONNECTIONS: usize = 64;
+
+/// The config keys that a request can set, the others are removed.
+pub const CLIENT_CONFIG_KEYS: [&str; 10] = [
+    "comments",
+    "error",
+    "app",
+    "cache_on_post",
+    "cache_on_get",
+    "cache_on_cookies",
+    "cache_disable",
+    "cache_vary",
+    "filter_all",
+    "disable_js",
+];
+
+/// The format of the frame payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    MsgPack,
+}
+
+impl Format {
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            b'J' => Ok(Format::Json),
+            b'M' => Ok(Format::MsgPack),
+            _ => Err(invalid_data(format!("unknown frame format: {:#04x}", byte))),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Format::Json => b'J',
+            Format::MsgPack => b'M',
+        }
+    }
+
+    fn encode<T: Serialize>(self, value: &T) -> io::Result<Vec<u8>> {
+        match self {
+            Format::Json => serde_json::to_vec(value).map_err(invalid_data),
+            Format::MsgPack => rmp_serde::to_vec_named(value).map_err(invalid_data),
+        }
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(self, payload: &[u8]) -> io::Result<T> {
+        match self {
+            Format::Json => serde_json::from_slice(payload).map_err(invalid_data),
+            Format::MsgPack => rmp_serde::from_slice(payload).map_err(invalid_data),
+        }
+    }
+}
+
+/// A render request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    /// The template file path, relative to the root of the server.
+    pub template: String,
+    /// The schema merged with the default schema, see `Template::from_file_value`.
+    #[serde(default)]
+    pub schema: Value,
+}
+
+/// The result of a render.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    /// The output of `Template::render`.
+    pub body: String,
+    pub status_code: String,
+    pub status_text: String,
+    pub status_param: String,
+    /// The headers set with the bifs header and cookie.
+    pub headers: Vec<(String, String)>,
+    pub errors: Vec<RenderError>,
+}
+
+impl Response {
+    /// Checks if the render has errors.
+    pub fn has_error(&self) -> bool {
+        !self.errors.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Reply {
+    Ok(Response),
+    Error(String),
+}
+
+/// Writes a frame with the payload.
+pub fn write_frame<W: Write + ?Sized>(
+    writer: &mut W,
+    format: Format,
+    payload: &[u8],
+) -> io::Result<()> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(invalid_data("frame too large"));
+    }
+
+    let mut header = [0u8; 5];
+    header[0] = format.to_byte();
+    header[1..].copy_from_slice(&(payload.len() as u32).to_be_bytes());
+    writer.write_all(&header)?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Reads a frame, `None` if the connection is closed before a new frame.
+pub fn read_frame<R: Read + ?Sized>(reader: &mut R) -> io::Result<Option<(Format, Vec<u8>)>> {
+    let mut header = [0u8; 5];
+
+    match reader.read_exact(&mut header[..1]) {
+        Ok(()) => (),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    reader.read_exact(&mut header[1..])?;
+
+    let format = Format::from_byte(header[0])?;
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(invalid_data("frame too large"));
+    }
+
+    // The buffer grows with the data received, not with the claimed length.
+    let mut payload = Vec::new();
+    reader.take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "incomplete frame",
+        ));
+    }
+
+    Ok(Some((format, payload)))
+}
+
+/// The options of a server.
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// Directory of the templates, relative template paths are resolved from it
+    /// and the files outside it are not rendered. `None` any file.
+    pub root: Option<PathBuf>,
+    /// Max number of connections answered at the same time, the next ones
+    /// wait to be accepted.
+    pub max_connections: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            root: None,
+            max_connections: MAX_CONNECTIONS,
+        }
+    }
+}
+
+impl ServerOptions {
+    /// Sets the root, it must be an existing directory.
+    pub fn with_root<P: AsRef<Path>>(mut self, root: P) -> io::Result<Self> {
+        let root = fs::canonicalize(root)?;
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("not a directory: {}", root.display()),
+            ));
+        }
+        self.root = Some(root);
+
+        Ok(self)
+    }
+
+    // The canonical path of the template, it must be in the root.
+    fn resolve_template(&self, template: &str) -> Result<String, String> {
+        let Some(root) = &self.root else {
+            return Ok(template.to_string());
+        };
+        let roots = [root.to_string_lossy().into_owned()];
+        let path = fs::canonicalize(root.join(template))
+            .map_err(|e| format!("{}: {}", template, e))?
+            .to_string_lossy()
+            .into_owned();
+
+        if !is_path_in_roots(&path, &roots) {
+            return Err(format!("{}: path not allowed", template));
+        }
+
+        Ok(path)
+    }
+
+    // The request schema with only the client config keys and the current
+    // language, and with the root as the only allowed root.
+    fn restrict_schema(&self, mut schema: Value) -> Value {
+        if let Some(config) = schema.get_mut("config").and_then(Value::as_object_mut) {
+            config.retain(|key, _| CLIENT_CONFIG_KEYS.contains(&key.as_str()));
+        }
+        if let Some(inherit) = schema.get_mut("inherit") {
+            *inherit = match inherit.pointer("/locale/current") {
+                Some(Value::String(current)) => json!({ "locale": { "current": current } }),
+                _ => json!({}),
+            };
+        }
+        if let Some(root) = &self.root {
+            if !schema.is_object() {
+                schema = json!({});
+            }
+            schema["config"]["allowed_roots"] = json!([root.to_string_lossy()]);
+        }
+
+        schema
+    }
+}
+
+/// Renders a request.
+pub fn render(request: Request, options: &ServerOptions) -> Result<Response, String> {
+    let file_path = options.resolve_template(&request.template)?;
+    let schema = options.restrict_schema(request.schema);
+    let mut template = Template::from_file_value(&file_path, schema)
+        .map_err(|e| format!("{}: {}", request.template, e))?;
+    let body = template.render();
+
+    Ok(Response {
+        body,
+        status_code: template.get_status_code().clone(),
+        status_text: template.get_status_text().clone(),
+        status_param: template.get_status_param().clone(),
+        headers: template.get_headers(),
+        errors: template.get_errors().to_vec(),
+    })
+}
+
+/// Answers the requests of a connection until it is closed.
+///
+/// A frame that cannot be read ends the connection with an error, a payload
+/// that cannot be decoded is answered with an error reply.
+pub fn handle_connection<S: Read + Write>(
+    stream: &mut S,
+    options: &ServerOptions,
+) -> io::Result<()> {
+    while let Some((format, payload)) = read_frame(stream)? {
+        let reply = match format.decode::<Request>(&payload) {
+            Ok(request) => match render(request, options) {
+                Ok(response) => Reply::Ok(response),
+                Err(e) => Reply::Error(e),
+            },
+            Err(e) => Reply::Error(e.to_string()),
+        };
+        write_frame(stream, format, &format.encode(&reply)?)?;
+    }
+
+    Ok(())
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+/// An IPC server, each connection is answered in its own thread.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralts::ipc::{Server, ServerOptions};
+///
+/// let mut server = Server::bind_tcp("127.0.0.1:4273").unwrap();
+/// server.set_options(ServerOptions::default().with_root("/srv/templates").unwrap());
+/// server.serve().unwrap();
+/// ```
+pub struct Server {
+    listener: Listener,
+    options: Arc<ServerOptions>,
+}
+
+impl Server {
+    /// Listens on a TCP address.
+    pub fn bind_tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Server {
+            listener: Listener::Tcp(TcpListener::bind(addr)?),
+            options: Arc::default(),
+        })
+    }
+
+    /// Listens on a Unix socket, the path must not exist.
+    #[cfg(unix)]
+    pub fn bind_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Server {
+            listener: Listener::Unix(UnixListener::bind(path)?),
+            options: Arc::default(),
+        })
+    }
+
+    /// Sets the options of the server.
+    pub fn set_options(&mut self, options: ServerOptions) {
+        self.options = Arc::new(options);
+    }
+
+    /// The TCP address, useful when bound to port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr(),
+            #[cfg(unix)]
+            Listener::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "not a TCP server",
+            )),
+        }
+    }
+
+    /// Accepts connections until the listener fails.
+    ///
+    /// When `max_connections` are open, the next connection is accepted
+    /// after one of them is closed.
+    pub fn serve(&self) -> io::Result<()> {
+        let slots = Arc::new(Slots::new(self.options.max_connections.max(1)));
+
+        match &self.listener {
+            Listener::Tcp(listener) => loop {
+                let slot = slots.acquire();
+                let (stream, _) = listener.accept()?;
+                self.spawn_connection(stream, slot);
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener) => loop {
+                let slot = slots.acquire();
+                let (stream, _) = listener.accept()?;
+                self.spawn_connection(stream, slot);
+            },
+        }
+    }
+
+    fn spawn_connection<S>(&self, mut stream: S, slot: Slot)
+    where
+        S: Read + Write + Send + 'static,
+    {
+        let options = Arc::clone(&self.options);
+
+        thread::spawn(move || {
+            // The client gets the connection closed, there is nobody else to tell.
+            let _ = handle_connection(&mut stream, &options);
+            drop(slot);
+        });
+    }
+}
+
+// Counts the open connections, acquire waits while there are max.
+struct Slots {
+    open: Mutex<usize>,
+    released: Condvar,
+    max: usize,
+}
+
+impl Slots {
+    fn new(max: usize) -> Self {
+        Slots {
+            open: Mutex::new(0),
+            released: Condvar::new(),
+            max,
+        }
+    }
+
+    fn acquire(self: &Arc<Self>) -> Slot {
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        while *open >= self.max {
+            open = self.released.wait(open).unwrap_or_else(|e| e.into_inner());
+        }
+        *open += 1;
+
+        Slot(Arc::clone(self))
+    }
+}
+
+// An open connection, released on drop, also if the connection panics.
+struct Slot(Arc<Slots>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut open = self.0.open.lock().unwrap_or_else(|e| e.into_inner());
+        *open -= 1;
+        self.0.released.notify_one();
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn as_read_write(&mut self) -> &mut dyn ReadWrite {
+        match self {
+            Stream::Tcp(stream) => stream,
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream,
+        }
+    }
+}
+
+trait ReadWrite: Read + Write {}
+
+impl<T: Read + Write> ReadWrite for T {}
+
+/// An IPC client, the connection is kept open between requests.
+///
+/// # Example
+///
+/// ```no_run
+/// use neutralts::ipc::Client;
+/// use serde_json::json;
+///
+/// let mut client = Client::connect_tcp("127.0.0.1:4273").unwrap();
+/// let response = client
+///     .render("/path/index.ntpl", &json!({ "data": { "name": "World" } }))
+///     .unwrap();
+/// println!("{}", response.body);
+/// ```
+pub struct Client {
+    stream: Stream,
+    format: Format,
+}
+
+impl Client {
+    /// Connects to a TCP server, the requests are sent as JSON.
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Client {
+            stream: Stream::Tcp(TcpStream::connect(addr)?),
+            format: Format::Json,
+        })
+    }
+
+    /// Connects to a Unix socket server, the requests are sent as JSON.
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Client {
+            stream: Stream::Unix(UnixStream::connect(path)?),
+            format: Format::Json,
+        })
+    }
+
+    /// Sets the format of the requests.
+    pub fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    /// Renders a template file of the server with the schema.
+    ///
+    /// # Returns
+    ///
+    /// The response, or an error if the connection fails or the server
+    /// cannot render the request, e.g.: the file cannot be read.
+    pub fn render(&mut self, template: &str, schema: &Value) -> io::Result<Response> {
+        let request = Request {
+            template: template.to_string(),
+            schema: schema.clone(),
+        };
+        let stream = self.stream.as_read_write();
+
+        write_frame(stream, self.format, &self.format.encode(&request)?)?;
+        let (format, payload) = read_frame(stream)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"))?;
+
+        match format.decode(&payload)? {
+            Reply::Ok(response) => Ok(response),
+            Reply::Error(e) => Err(io::Error::other(e)),
+        }
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
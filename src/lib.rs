@@ -65,16 +65,35 @@ pub use serde_json::{json, Value};
 mod macros;
 
 mod bif;
+mod bif_handler;
 mod block_parser;
+pub mod cache;
+mod check;
+mod compiled;
 pub mod constants;
 mod default_json;
 pub mod doc;
+mod engine;
+#[cfg(feature = "ipc")]
+pub mod ipc;
+mod loader;
+#[cfg(feature = "python-module")]
+mod python;
+mod render_error;
+mod render_output;
 mod shared;
 mod template;
 pub mod utils;
 
+pub use bif_handler::{BifContext, BifHandler};
+pub use cache::{CacheBackend, CacheStats, FileCache, MemoryCache};
+pub use compiled::{CompiledBlock, CompiledTemplate, Modifiers};
 pub use constants::*;
 pub use default_json::*;
+pub use engine::Engine;
+pub use loader::{EmbeddedLoader, FileLoader, MemoryLoader, TemplateLoader};
+pub use render_error::{RenderError, RenderErrorKind};
+pub use render_output::{RenderOutput, RenderStats};
 pub use template::Template;
 pub use utils::*;
 
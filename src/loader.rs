@@ -0,0 +1,265 @@
+//! Template loaders.
+//!
+//! The files used by a template, the template itself and those of the bifs
+//! include, locale, data and obj, are read with a `TemplateLoader`:
+//!
+//! ```text
+//!                     .---------------------.
+//!  {:include; ... :} ---->                     |----> FileLoader: the filesystem (default)
+//!  {:locale; ... :}  ---->   TemplateLoader    |----> MemoryLoader: a map of paths and contents
+//!  {:data; ... :}    ---->                     |----> EmbeddedLoader: files compiled into the binary
+//!  {:obj; ... :}     ---->                     |----> ... or your own
+//!                     ·---------------------·
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use neutralts::{MemoryLoader, Template};
+//!
+//! let mut loader = MemoryLoader::new();
+//! loader.insert("tpl/index.ntpl", "<div>{:include; #/header.ntpl :}</div>");
+//! loader.insert("tpl/header.ntpl", "Header");
+//!
+//! let mut template = Template::new().unwrap();
+//! template.set_loader(loader);
+//! template.set_src_path("tpl/index.ntpl").unwrap();
+//! assert_eq!(template.render(), "<div>Header</div>");
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+pub(crate) type SharedLoader = Arc<dyn TemplateLoader>;
+
+pub(crate) fn default_loader() -> SharedLoader {
+    Arc::new(FileLoader)
+}
+
+/// Reads the files used by the templates.
+pub trait TemplateLoader: Send + Sync {
+    /// Resolves a path written in a template.
+    ///
+    /// By default, the prefix "#" is replaced by the directory of the
+    /// current file: `{:include; #/file.ntpl :}`
+    ///
+    /// # Arguments
+    ///
+    /// * `current_dir` - The directory of the file being parsed.
+    /// * `path` - The path as it is written in the template.
+    fn resolve(&self, current_dir: &str, path: &str) -> String {
+        match path.strip_prefix('#') {
+            Some(stripped) => format!("{}{}", current_dir, stripped),
+            None => path.to_string(),
+        }
+    }
+
+    /// The canonical path of a file, the same file always has the same
+    /// canonical path. It is used to know if a file has already been used.
+    ///
+    /// # Returns
+    ///
+    /// The canonical path, or `None` if the file does not exist.
+    fn canonicalize(&self, path: &str) -> Option<String>;
+
+    /// Reads a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The canonical path of the file.
+    fn read(&self, path: &str) -> Result<String, String>;
+
+    /// A value that changes when the file changes, e.g. the modification time.
+    ///
+    /// It is used to know if a compiled file is still valid, if it is `None`
+    /// the file is read and compared with the compiled one.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The canonical path of the file.
+    fn version(&self, _path: &str) -> Option<u64> {
+        None
+    }
+}
+
+/// Loads the files from the filesystem, it is the default loader.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileLoader;
+
+impl TemplateLoader for FileLoader {
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        fs::canonicalize(path)
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        fs::read_to_string(path).map_err(|e| e.to_string())
+    }
+
+    fn version(&self, path: &str) -> Option<u64> {
+        let metadata = fs::metadata(path).ok()?;
+        let mut hasher = DefaultHasher::new();
+        metadata.modified().ok()?.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+
+        Some(hasher.finish())
+    }
+}
+
+/// Loads the files from a map of paths and contents.
+///
+/// Paths are normalized, "tpl/./a.ntpl" and "tpl/b/../a.ntpl" are "tpl/a.ntpl".
+/// Each insert gives the file a new version, so the compiled files are
+/// reused without reading them again.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryLoader {
+    // Content and version of each file.
+    files: HashMap<String, (String, u64)>,
+}
+
+// The versions of the files of all the memory loaders, the compiled files
+// are cached by path, two loaders never give the same version to a path.
+static MEMORY_VERSION: AtomicU64 = AtomicU64::new(0);
+
+impl MemoryLoader {
+    /// Constructs an empty `MemoryLoader`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file, as it is used in the templates.
+    /// * `content` - The content of the file.
+    pub fn insert(&mut self, path: &str, content: &str) {
+        let version = MEMORY_VERSION.fetch_add(1, Ordering::Relaxed);
+        self.files
+            .insert(normalize_path(path), (content.to_string(), version));
+    }
+
+    /// Removes a file.
+    pub fn remove(&mut self, path: &str) -> Option<String> {
+        self.files
+            .remove(&normalize_path(path))
+            .map(|(content, _)| content)
+    }
+}
+
+impl TemplateLoader for MemoryLoader {
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        let path = normalize_path(path);
+        self.files.contains_key(&path).then_some(path)
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        self.files
+            .get(&normalize_path(path))
+            .map(|(content, _)| content.clone())
+            .ok_or_else(|| format!("File not found: {}", path))
+    }
+
+    fn version(&self, path: &str) -> Option<u64> {
+        self.files
+            .get(&normalize_path(path))
+            .map(|(_, version)| *version)
+    }
+}
+
+/// Loads the files compiled into the binary, for single binary deployments.
+///
+/// # Example
+///
+/// ```
+/// use neutralts::{EmbeddedLoader, Template};
+///
+/// static FILES: &[(&str, &str)] = &[
+///     ("tpl/index.ntpl", "<div>{:include; #/header.ntpl :}</div>"),
+///     // ("tpl/header.ntpl", include_str!("../tpl/header.ntpl")),
+///     ("tpl/header.ntpl", "Header"),
+/// ];
+///
+/// let mut template = Template::new().unwrap();
+/// template.set_loader(EmbeddedLoader::new(FILES));
+/// template.set_src_path("tpl/index.ntpl").unwrap();
+/// assert_eq!(template.render(), "<div>Header</div>");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedLoader {
+    files: &'static [(&'static str, &'static str)],
+}
+
+impl EmbeddedLoader {
+    /// Constructs an `EmbeddedLoader`.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - Pairs of path and content, e.g. with `include_str!`.
+    pub const fn new(files: &'static [(&'static str, &'static str)]) -> Self {
+        EmbeddedLoader { files }
+    }
+
+    fn get(&self, path: &str) -> Option<(&'static str, &'static str)> {
+        let path = normalize_path(path);
+        self.files
+            .iter()
+            .find(|(file, _)| normalize_path(file) == path)
+            .copied()
+    }
+}
+
+impl TemplateLoader for EmbeddedLoader {
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        self.get(path).map(|(file, _)| normalize_path(file))
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        self.get(path)
+            .map(|(_, content)| content.to_string())
+            .ok_or_else(|| format!("File not found: {}", path))
+    }
+
+    // The content never changes, its address tells apart the files with the
+    // same path in two loaders.
+    fn version(&self, path: &str) -> Option<u64> {
+        let (_, content) = self.get(path)?;
+        let mut hasher = DefaultHasher::new();
+        content.as_ptr().hash(&mut hasher);
+        content.len().hash(&mut hasher);
+
+        Some(hasher.finish())
+    }
+}
+
+// Normalizes a path without accessing the filesystem: removes ".", empty
+// parts and resolves "..".
+fn normalize_path(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if matches!(parts.last(), Some(last) if *last != "..") {
+                    parts.pop();
+                } else if !path.starts_with('/') {
+                    parts.push(part);
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    let normalized = parts.join("/");
+    if path.starts_with('/') {
+        format!("/{}", normalized)
+    } else {
+        normalized
+    }
+}
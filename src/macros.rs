@@ -35,7 +35,10 @@
 mod macros {
     macro_rules! new_child_parse {
         ($self:expr, $source:expr, $scope:expr) => {{
+            // Use the compiled version of the source if there is one.
+            let compiled = $self.compiled_for($source);
             let mut child_inherit = $self.inherit.clone();
+            $self.set_child_origin(&mut child_inherit, $source);
             let shared = &mut $self.shared;
 
             //  "bif.alias" is used and not "bif.name" because in "var" or "unprintable"
@@ -58,7 +61,10 @@ mod macros {
             }
 
             let mut block = $crate::block_parser::BlockParser::new(shared, child_inherit);
-            let code = block.parse($source, $self.only);
+            let code = match &compiled {
+                Some(compiled) => block.parse_compiled(compiled, $self.only),
+                None => block.parse($source, $self.only),
+            };
 
             // Update this block with the data generated in the child
             if $scope {
@@ -0,0 +1,155 @@
+//! Python extension module, enabled with the "python-module" feature.
+//!
+//! ```text
+//! from neutralts import Template
+//!
+//! template = Template("index.ntpl", {"data": {"name": "World"}})
+//! contents = template.render()
+//! status_code = template.get_status_code()
+//! ```
+//!
+//! It is built with maturin, see pyproject.toml. The schemas are converted
+//! with the Python json module, as in the obj Python engine.
+
+use crate::template::Template;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyModule;
+use serde_json::Value;
+
+/// A Neutral TS template.
+#[pyclass(name = "Template", module = "neutralts")]
+pub struct PyTemplate {
+    template: Template,
+}
+
+#[pymethods]
+impl PyTemplate {
+    /// Template(path=None, schema=None), the path of the template file and
+    /// a schema dict merged with the default schema.
+    #[new]
+    #[pyo3(signature = (path=None, schema=None))]
+    fn new(path: Option<&str>, schema: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let mut template = Template::new().map_err(PyValueError::new_err)?;
+
+        if let Some(schema) = schema {
+            template.merge_schema_value(py_to_value(schema)?);
+        }
+        if let Some(path) = path {
+            template
+                .set_src_path(path)
+                .map_err(|e| PyOSError::new_err(format!("{}: {}", path, e)))?;
+        }
+
+        Ok(PyTemplate { template })
+    }
+
+    /// Sets the template file.
+    fn set_src_path(&mut self, path: &str) -> PyResult<()> {
+        self.template
+            .set_src_path(path)
+            .map_err(|e| PyOSError::new_err(format!("{}: {}", path, e)))
+    }
+
+    /// Sets the template source.
+    fn set_src_str(&mut self, source: &str) {
+        self.template.set_src_str(source);
+    }
+
+    /// Merges a schema dict.
+    fn merge_schema(&mut self, schema: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.template.merge_schema_value(py_to_value(schema)?);
+        Ok(())
+    }
+
+    /// Merges a schema file, JSON.
+    fn merge_schema_path(&mut self, path: &str) -> PyResult<()> {
+        self.template
+            .merge_schema_path(path)
+            .map_err(|e| PyOSError::new_err(format!("{}: {}", path, e)))
+    }
+
+    /// Merges a schema in MessagePack bytes.
+    fn merge_schema_msgpack(&mut self, data: &[u8]) -> PyResult<()> {
+        self.template
+            .merge_schema_msgpack(data)
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Renders the template, the GIL is released while rendering.
+    fn render(&mut self, py: Python<'_>) -> String {
+        py.detach(|| self.template.render())
+    }
+
+    /// Renders the template once, faster, the template is not rendered again.
+    fn render_once(&mut self, py: Python<'_>) -> String {
+        py.detach(|| self.template.render_once())
+    }
+
+    fn get_status_code(&self) -> String {
+        self.template.get_status_code().clone()
+    }
+
+    fn get_status_text(&self) -> String {
+        self.template.get_status_text().clone()
+    }
+
+    fn get_status_param(&self) -> String {
+        self.template.get_status_param().clone()
+    }
+
+    /// The headers set with the bifs header and cookie, a list of (name, value).
+    fn get_headers(&self) -> Vec<(String, String)> {
+        self.template.get_headers()
+    }
+
+    fn has_error(&self) -> bool {
+        self.template.has_error()
+    }
+
+    /// The list of error messages.
+    fn get_error(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        value_to_py(py, &self.template.get_error())
+    }
+
+    /// The errors with the bif, file, line and column, a list of dict.
+    fn get_errors(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let errors = serde_json::to_value(self.template.get_errors())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        value_to_py(py, &errors)
+    }
+
+    /// Render time in seconds.
+    fn get_time_duration(&self) -> f64 {
+        self.template.get_time_duration().as_secs_f64()
+    }
+}
+
+fn py_to_value(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let json = PyModule::import(obj.py(), "json")?;
+    let dumped: String = json.getattr("dumps")?.call1((obj,))?.extract()?;
+
+    serde_json::from_str(&dumped)
+        .map_err(|e| PyValueError::new_err(format!("Invalid schema: {}", e)))
+}
+
+fn value_to_py(py: Python<'_>, value: &Value) -> PyResult<Py<PyAny>> {
+    let json = PyModule::import(py, "json")?;
+    let loaded = json.getattr("loads")?.call1((value.to_string(),))?;
+
+    Ok(loaded.unbind())
+}
+
+/// The neutralts Python module.
+#[pymodule]
+pub fn neutralts(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTemplate>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "python_tests.rs"]
+mod tests;
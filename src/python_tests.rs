@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::*;
+    use pyo3::prelude::*;
+    use pyo3::py_run;
+    use pyo3::types::PyBytes;
+
+    fn with_module<F: FnOnce(Python<'_>, Bound<'_, PyModule>)>(f: F) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = pyo3::wrap_pymodule!(super::super::neutralts)(py);
+            f(py, module.into_bound(py))
+        });
+    }
+
+    #[test]
+    fn test_python_render() {
+        with_module(|py, neutralts| {
+            py_run!(
+                py,
+                neutralts,
+                r#"
+template = neutralts.Template(schema={"data": {"name": "World"}})
+template.set_src_str("<p>Hello {:;name:}</p>")
+assert template.render() == "<p>Hello World</p>"
+assert template.get_status_code() == "200"
+assert template.get_status_text() == "OK"
+assert template.get_status_param() == ""
+assert not template.has_error()
+assert template.get_errors() == []
+assert template.get_time_duration() >= 0
+"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_python_src_path() {
+        with_module(|py, neutralts| {
+            py_run!(
+                py,
+                neutralts,
+                r#"
+template = neutralts.Template("tests/snippets.ntpl")
+template.merge_schema({"data": {"name": "World"}})
+template.render_once()
+assert template.get_status_code() == "200"
+
+template = neutralts.Template()
+try:
+    template.set_src_path("not-found.ntpl")
+    assert False
+except OSError as e:
+    assert "not-found.ntpl" in str(e)
+"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_python_merge_schema() {
+        let msgpack = rmp_serde::to_vec(&serde_json::json!({ "data": { "b": "B" } })).unwrap();
+
+        with_module(|py, neutralts| {
+            let msgpack = PyBytes::new(py, &msgpack);
+            py_run!(
+                py,
+                neutralts msgpack,
+                r#"
+template = neutralts.Template()
+template.merge_schema({"data": {"a": "A", "list": [1, 2], "none": None}})
+template.merge_schema_msgpack(msgpack)
+template.set_src_str("{:;a:}{:;b:}{:;list->1:}")
+assert template.render() == "AB2"
+
+try:
+    template.merge_schema_msgpack(b"\xc1")
+    assert False
+except ValueError:
+    pass
+"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_python_errors() {
+        let schema = SCHEMA;
+
+        with_module(|py, neutralts| {
+            py_run!(
+                py,
+                neutralts schema,
+                r#"
+import json
+template = neutralts.Template(schema=json.loads(schema))
+template.set_src_str("<div>\n  {:include; {:flg; require :} >> not-found.ntpl :}\n</div>")
+template.render()
+assert template.has_error()
+assert len(template.get_error()) == 1
+error = template.get_errors()[0]
+assert error["kind"] == "bif"
+assert error["bif_name"] == "include"
+assert (error["line"], error["column"]) == (2, 3)
+"#
+            );
+        });
+    }
+
+    #[test]
+    fn test_python_status_and_headers() {
+        with_module(|py, neutralts| {
+            py_run!(
+                py,
+                neutralts,
+                r#"
+template = neutralts.Template()
+template.set_src_str("{:header; X-Test >> yes :}{:redirect; 301 >> /login :}")
+template.render()
+assert template.get_status_code() == "301"
+assert template.get_status_param() == "/login"
+assert template.get_headers() == [("X-Test", "yes")]
+"#
+            );
+        });
+    }
+}
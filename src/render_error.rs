@@ -0,0 +1,85 @@
+//! Render errors.
+//!
+//! Each error found while rendering is reported with the bif that caused it
+//! and its position in the source where it is written:
+//!
+//! ```text
+//!  layout.ntpl                           RenderError
+//!  1 <div>                               kind:     Bif
+//!  2   {:include; header.ntpl :}         bif_name: "include"
+//!      ^                                 file:     "layout.ntpl"
+//!      ·-------------------------------> line: 2, column: 3
+//! ```
+//!
+//! For bifs inside included files, the position is in the included file, and
+//! for bifs inside snippets, it is where the snippet was set.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Max length of the bif source in RenderError::snippet
+const SNIPPET_MAX_CHARS: usize = 120;
+
+/// The kind of a render error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderErrorKind {
+    /// Error in a bif, e.g.: a file not found in include.
+    Bif,
+    /// A bif without the name delimiter, e.g.: {:name:}
+    Delimiter,
+    /// A block that is not closed, the render returns a status 500.
+    UnmatchedBlock,
+    /// The render has been stopped, e.g.: the bifs limit has been reached.
+    Render,
+}
+
+/// An error found while rendering, see `Template::get_errors`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderError {
+    pub kind: RenderErrorKind,
+    /// Bif name without modifiers, e.g.: "include", empty for render errors.
+    pub bif_name: String,
+    pub message: String,
+    /// File where the bif is written, empty if it is the template source.
+    pub file: String,
+    /// Line where the bif is written, starting at 1, or 0 if unknown.
+    pub line: usize,
+    /// Column where the bif is written, starting at 1, or 0 if unknown.
+    pub column: usize,
+    /// The bif source, shortened if it is long.
+    pub snippet: String,
+}
+
+impl RenderError {
+    pub(crate) fn shorten(src: &str) -> String {
+        let src = src.replace(['\n', '\r'], " ");
+
+        match src.char_indices().nth(SNIPPET_MAX_CHARS) {
+            Some((pos, _)) => format!("{}...", &src[..pos]),
+            None => src,
+        }
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = if self.file.is_empty() {
+            "source"
+        } else {
+            &self.file
+        };
+
+        if self.line > 0 {
+            write!(f, "{}:{}:{}: ", file, self.line, self.column)?;
+        } else {
+            write!(f, "{}: ", file)?;
+        }
+
+        if self.bif_name.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "({}) {}", self.bif_name, self.message)
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! The result of a render, see `Template::render_full`.
+//!
+//! The body is the rendered template as it is, the status and the headers
+//! tell the HTTP layer how to send it:
+//!
+//! ```text
+//!  {:redirect; 301 >> /login :}          RenderOutput
+//!                                        body:        "..."
+//!                                        status:      "301"
+//!                                        status_text: "Moved Permanently"
+//!                                        location:    Some("/login")
+//!                                        headers:     [("Location", "/login")]
+//! ```
+
+use crate::render_error::RenderError;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Everything produced by a render.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenderOutput {
+    /// The rendered content, not replaced by the status text on 3xx, 4xx or 5xx.
+    pub body: String,
+    /// The status code, "200" unless exit, redirect or an error set another.
+    pub status: String,
+    /// The status text of the status code, e.g.: "Not Found".
+    pub status_text: String,
+    /// The destination URL of a 3xx redirect.
+    pub location: Option<String>,
+    /// The HTTP headers to send: Location on redirects, the bifs header and cookie.
+    pub headers: Vec<(String, String)>,
+    /// The errors of the render, see `Template::get_errors`.
+    pub errors: Vec<RenderError>,
+    /// The time the render took.
+    pub duration: Duration,
+    pub stats: RenderStats,
+}
+
+/// Counters of a render.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RenderStats {
+    /// Number of bifs parsed.
+    pub bifs: u64,
+}
+
+impl RenderOutput {
+    /// Checks if the render has errors.
+    pub fn has_error(&self) -> bool {
+        !self.errors.is_empty()
+    }
+}
@@ -1,12 +1,47 @@
-use crate::utils::{get_from_key, is_bool_key};
+use crate::bif_handler::BifHandlers;
+use crate::cache::*;
+use crate::compiled::SourceOrigin;
+use crate::constants::*;
+use crate::loader::*;
+use crate::render_error::*;
+use crate::utils::*;
+use chrono::Local;
 use serde_json::{json, Value};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::env;
-use std::rc::Rc;
+use std::path::Path;
+use std::sync::Arc;
+
+// The schema shared by the render contexts of an Engine, the schema of each
+// render is layered over it, it is not cloned.
+#[derive(Clone)]
+pub(crate) struct BaseSchema {
+    pub(crate) schema: Arc<Value>,
+    // The inherit of the schema, the root of the block schemas.
+    pub(crate) inherit: Arc<Value>,
+}
+
+impl BaseSchema {
+    pub(crate) fn new(schema: Value) -> Self {
+        let inherit = Arc::new(schema["inherit"].clone());
+
+        BaseSchema {
+            schema: Arc::new(schema),
+            inherit,
+        }
+    }
+
+    pub(crate) fn merge(&mut self, schema: Value) {
+        update_schema_owned(Arc::make_mut(&mut self.schema), schema);
+        self.inherit = Arc::new(self.schema["inherit"].clone());
+    }
+}
 
 pub(crate) struct Shared {
     pub(crate) schema: Value,
-    pub(crate) indir_store: HashMap<String, Rc<Value>>,
+    pub(crate) base: Option<BaseSchema>,
+    pub(crate) indir_store: HashMap<String, Arc<Value>>,
     pub(crate) lang: String,
     pub(crate) comments: String,
     pub(crate) bisf_count: u64,
@@ -20,47 +55,114 @@ pub(crate) struct Shared {
     pub(crate) redirect_js: String,
     pub(crate) filter_all: bool,
     pub(crate) filter_bifs: bool,
-    pub(crate) cache_prefix: String,
-    pub(crate) cache_dir: String,
     pub(crate) cache_on_post: bool,
     pub(crate) cache_on_get: bool,
     pub(crate) cache_on_cookies: bool,
     pub(crate) cache_disable: bool,
+    pub(crate) cache: SharedCache,
+    pub(crate) cache_dir: String,
+    pub(crate) cache_prefix: String,
+    pub(crate) cache_purge_every: u64,
+    pub(crate) cache_max_size: u64,
+    pub(crate) cache_vary: Vec<String>,
+    pub(crate) cache_stats: CacheStats,
     pub(crate) disable_js: bool,
     pub(crate) already_js: bool,
     pub(crate) debug_expire: u64,
     pub(crate) debug_file: String,
     pub(crate) working_dir: String,
+    pub(crate) config_error: String,
+    pub(crate) aborted: bool,
+    pub(crate) errors: Vec<RenderError>,
+    pub(crate) snippet_origins: HashMap<String, SourceOrigin>,
+    pub(crate) block_origins: HashMap<(String, usize), SourceOrigin>,
+    pub(crate) bif_handlers: BifHandlers,
+    pub(crate) loader: SharedLoader,
+    pub(crate) allowed_roots: Vec<String>,
+    pub(crate) template_dirs: Vec<String>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) cookies: Vec<(String, String)>,
 }
 
 impl Shared {
     pub(crate) fn new(schema: Value) -> Self {
-        let bisf_max = schema["config"]["infinite_loop_max_bifs"].as_u64().unwrap();
+        Self::with_base(schema, None)
+    }
+
+    // With a base, the schema only has the keys of the render. The config
+    // is merged, it is small and it is read in many places.
+    pub(crate) fn with_base(mut schema: Value, base: Option<BaseSchema>) -> Self {
+        if let Some(base) = &base {
+            let mut config = base.schema["config"].clone();
+            if let Some(render_config) = schema.get_mut("config") {
+                merge_schema_owned(&mut config, render_config.take());
+            }
+            schema["config"] = config;
+        }
+
+        let mut config_error = String::new();
+        let bisf_max = match schema["config"]["infinite_loop_max_bifs"].as_u64() {
+            Some(max) => max,
+            None => {
+                config_error = "config->infinite_loop_max_bifs must be a number".to_string();
+                0
+            }
+        };
         let comments = get_from_key(&schema["config"], "comments");
-        let lang = get_from_key(&schema["inherit"]["locale"], "current");
+        let lang = match &base {
+            Some(base) => get_from_value(
+                layered_value(&schema, &base.schema, &["inherit", "locale", "current"]).as_deref(),
+            ),
+            None => get_from_key(&schema["inherit"]["locale"], "current"),
+        };
         let filter_all = is_bool_key(&schema["config"], "filter_all");
         let cache_prefix = get_from_key(&schema["config"], "cache_prefix");
-        let mut cache_dir = get_from_key(&schema["config"], "cache_dir");
+        let cache_dir = get_from_key(&schema["config"], "cache_dir");
         let working_dir = env::current_dir().unwrap().to_string_lossy().into_owned();
         let cache_on_post = is_bool_key(&schema["config"], "cache_on_post");
         let cache_on_get = is_bool_key(&schema["config"], "cache_on_get");
         let cache_on_cookies = is_bool_key(&schema["config"], "cache_on_cookies");
         let cache_disable = is_bool_key(&schema["config"], "cache_disable");
+        let cache_purge_every = match schema["config"]["cache_purge_every"].as_u64() {
+            Some(every) => every,
+            None => {
+                config_error = "config->cache_purge_every must be a number".to_string();
+                0
+            }
+        };
+        let cache_max_size = match schema["config"]["cache_max_size"].as_u64() {
+            Some(size) => size,
+            None => {
+                config_error = "config->cache_max_size must be a number".to_string();
+                0
+            }
+        };
+        if !schema["config"]["cache_vary"].is_array() {
+            config_error = "config->cache_vary must be a list".to_string();
+        }
+        let cache_vary = get_str_list(&schema["config"]["cache_vary"]);
         let disable_js = is_bool_key(&schema["config"], "disable_js");
-        let debug_expire = schema["config"]["debug_expire"].as_u64().unwrap();
+        let debug_expire = match schema["config"]["debug_expire"].as_u64() {
+            Some(expire) => expire,
+            None => {
+                config_error = "config->debug_expire must be a number".to_string();
+                0
+            }
+        };
         let debug_file = get_from_key(&schema["config"], "debug_file");
+        let allowed_roots = get_str_list(&schema["config"]["allowed_roots"]);
+        let template_dirs = get_str_list(&schema["config"]["template_dirs"]);
         let mut filter_bifs = false;
 
         if !cache_disable {
             filter_bifs = true;
         }
 
-        if cache_dir.is_empty() {
-            cache_dir = env::temp_dir().to_string_lossy().into_owned();
-        }
+        let cache = Arc::new(FileCache::from_config(&cache_dir, &cache_prefix));
 
         Shared {
             schema,
+            base,
             indir_store: HashMap::new(),
             lang,
             comments,
@@ -75,17 +177,229 @@ impl Shared {
             redirect_js: String::new(),
             filter_all,
             filter_bifs,
-            cache_prefix,
-            cache_dir,
             cache_on_post,
             cache_on_get,
             cache_on_cookies,
             cache_disable,
+            cache,
+            cache_dir,
+            cache_prefix,
+            cache_purge_every,
+            cache_max_size,
+            cache_vary,
+            cache_stats: CacheStats::default(),
             disable_js,
             already_js: false,
             debug_expire,
             debug_file,
             working_dir,
+            config_error,
+            aborted: false,
+            errors: Vec::new(),
+            snippet_origins: HashMap::new(),
+            block_origins: HashMap::new(),
+            bif_handlers: BifHandlers::default(),
+            loader: default_loader(),
+            allowed_roots,
+            template_dirs,
+            headers: Vec::new(),
+            cookies: Vec::new(),
+        }
+    }
+
+    // Clears what a render leaves, for the renders that do not create a new
+    // Shared, see Template::render_plain.
+    pub(crate) fn clear_render(&mut self) {
+        self.bisf_count = 0;
+        self.flags = String::new();
+        self.exit = false;
+        self.has_error = false;
+        self.status_code = "200".to_string();
+        self.status_text = "OK".to_string();
+        self.status_param = String::new();
+        self.redirect_js = String::new();
+        self.already_js = false;
+        self.cache_stats = CacheStats::default();
+        self.aborted = false;
+        self.errors.clear();
+        self.headers.clear();
+        self.cookies.clear();
+    }
+
+    // A key of the data, the schema of the render is layered over the base.
+    pub(crate) fn data_value(&self, key: &str) -> Option<Cow<'_, Value>> {
+        match &self.base {
+            Some(base) => {
+                let mut parts = vec!["data"];
+                parts.extend(pointer_parts(key));
+                layered_value(&self.schema, &base.schema, &parts)
+            }
+            None => resolve_pointer(&self.schema["data"], key).map(Cow::Borrowed),
+        }
+    }
+
+    // A key of the schema, or the whole schema if it is empty. With a base,
+    // the whole schema is a merged copy.
+    pub(crate) fn schema_value(&self, key: &str) -> Option<Cow<'_, Value>> {
+        let parts = if key.is_empty() {
+            Vec::new()
+        } else {
+            pointer_parts(key)
+        };
+
+        match &self.base {
+            Some(base) => layered_value(&self.schema, &base.schema, &parts),
+            None => layered_value(&self.schema, &Value::Null, &parts),
+        }
+    }
+
+    // The inherit of the schema, the root of the block schemas. With a base,
+    // it is only copied if the render changes it.
+    pub(crate) fn root_inherit(&self) -> Arc<Value> {
+        match (&self.base, self.schema.get("inherit")) {
+            (Some(base), None) => Arc::clone(&base.inherit),
+            (Some(base), Some(inherit)) => {
+                let mut merged = base.inherit.as_ref().clone();
+                merge_schema(&mut merged, inherit);
+                Arc::new(merged)
+            }
+            (None, _) => Arc::new(self.schema["inherit"].clone()),
+        }
+    }
+
+    // Escapes the keys and the values of CONTEXT, that of the base is copied
+    // to the schema of the render first.
+    pub(crate) fn filter_context(&mut self) {
+        let in_base = self
+            .base
+            .as_ref()
+            .is_some_and(|base| !base.schema["data"]["CONTEXT"].is_null());
+        if in_base {
+            let context = self.data_value("CONTEXT").map(Cow::into_owned);
+            self.schema["data"]["CONTEXT"] = context.unwrap_or(Value::Null);
+        }
+
+        // Escape CONTEXT values
+        filter_value(&mut self.schema["data"]["CONTEXT"]);
+
+        // Escape CONTEXT keys names
+        filter_value_keys(&mut self.schema["data"]["CONTEXT"]);
+    }
+
+    // Sets the loader, the allowed roots are canonicalized with it.
+    pub(crate) fn set_loader(&mut self, loader: SharedLoader) {
+        for root in self.allowed_roots.iter_mut() {
+            if let Some(canonical) = loader.canonicalize(root) {
+                *root = canonical;
+            }
+        }
+
+        self.loader = loader;
+    }
+
+    // Whether a canonical path is in the allowed roots, any if there are none.
+    pub(crate) fn is_allowed_path(&self, canonical_path: &str) -> bool {
+        is_path_in_roots(canonical_path, &self.allowed_roots)
+    }
+
+    // Resolves a path written in the template with the loader, see
+    // Bif::resolve_file. Returns the resolved path and its canonical path,
+    // None if the file does not exist.
+    pub(crate) fn resolve_path(
+        &self,
+        current_dir: &str,
+        file_path: &str,
+        search_dirs: bool,
+    ) -> (String, Option<String>) {
+        let is_relative = !file_path.starts_with('#') && Path::new(file_path).is_relative();
+        let file_path = self.loader.resolve(current_dir, file_path);
+
+        if search_dirs && is_relative {
+            for dir in &self.template_dirs {
+                let path = format!("{}/{}", dir.trim_end_matches('/'), file_path);
+                if let Some(canonical_path) = self.loader.canonicalize(&path) {
+                    return (path, Some(canonical_path));
+                }
+            }
+        }
+
+        let canonical_path = self.loader.canonicalize(&file_path);
+
+        (file_path, canonical_path)
+    }
+
+    // Sets a response header, replaces the one with the same name.
+    pub(crate) fn set_header(&mut self, name: &str, value: &str) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some(header) => header.1 = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // Sets a cookie, replaces the one with the same name.
+    pub(crate) fn set_cookie(&mut self, name: &str, set_cookie: &str) {
+        match self.cookies.iter_mut().find(|(n, _)| n == name) {
+            Some(cookie) => cookie.1 = set_cookie.to_string(),
+            None => self
+                .cookies
+                .push((name.to_string(), set_cookie.to_string())),
+        }
+    }
+
+    // The response headers, with a Set-Cookie for each cookie.
+    pub(crate) fn response_headers(&self) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+
+        for (_, set_cookie) in &self.cookies {
+            headers.push(("Set-Cookie".to_string(), set_cookie.clone()));
+        }
+
+        headers
+    }
+
+    // Adds an error to __error and to the errors list.
+    pub(crate) fn add_error(&mut self, error: RenderError, error_line: &str) {
+        let show_error = self.schema["config"]["error"]["show"]
+            .as_bool()
+            .unwrap_or(true);
+        let error_line = error_line.replace(['\n', '\r'], " ");
+
+        if let Some(Value::Array(errors)) = self.schema.get_mut("__error") {
+            errors.push(json!(error_line));
+        }
+
+        if show_error {
+            eprintln!("{}", error_line);
+        }
+
+        self.errors.push(error);
+        self.has_error = true;
+    }
+
+    // Stops the render with an error: status 500, the error is added to __error
+    // and the output produced so far is kept.
+    pub(crate) fn abort(&mut self, error: RenderError) {
+        if self.aborted {
+            return;
+        }
+
+        let datetime = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let error_line = format!("[{}] Error (render) {}", datetime, error.message);
+
+        self.aborted = true;
+        self.exit = true;
+        self.status_code = "500".to_string();
+        self.status_param = error.message.clone();
+        self.add_error(error, &error_line);
+
+        if let Some(text) = STATUS_CODES.get(self.status_code.as_str()) {
+            self.status_text = text.to_string();
+        } else {
+            self.status_text = EMPTY_STRING;
         }
     }
 
@@ -100,9 +414,22 @@ impl Shared {
 
     /// Write - clone-on-write
     pub(crate) fn get_indir_mut(&mut self, key: &str) -> &mut Value {
-        let entry = self.indir_store
+        let entry = self
+            .indir_store
             .entry(key.to_string())
-            .or_insert_with(|| Rc::new(json!({})));
-        Rc::make_mut(entry)
+            .or_insert_with(|| Arc::new(json!({})));
+        Arc::make_mut(entry)
+    }
+}
+
+// Strings in a JSON array, others are ignored.
+fn get_str_list(value: &Value) -> Vec<String> {
+    match value.as_array() {
+        Some(list) => list
+            .iter()
+            .filter_map(|item| item.as_str())
+            .map(|item| item.to_string())
+            .collect(),
+        None => Vec::new(),
     }
 }
@@ -1,26 +1,35 @@
 use crate::{
-    block_parser::BlockInherit, block_parser::BlockParser, constants::*, default_json::*,
-    shared::Shared, utils::*,
+    bif_handler::*, block_parser::BlockInherit, block_parser::BlockParser,
+    block_parser::SegmentSink, cache::*, check::Checker, compiled::CompiledBlock,
+    compiled::CompiledTemplate, compiled::SourceOrigin, constants::*, default_json::*, loader::*,
+    render_error::*, render_output::*, shared::BaseSchema, shared::Shared, utils::*,
 };
 use regex::Regex;
 use serde_json::{json, Value};
+use std::collections::HashSet;
 use std::fs;
+use std::io::{self, Write};
 use std::path::Path;
+use std::sync::Arc;
 use std::sync::OnceLock;
 use std::time::{Duration, Instant};
-use std::rc::Rc;
 
-pub struct Template<'a> {
+pub struct Template {
     raw: String,
-    file_path: &'a str,
+    file_path: String,
+    compiled: Option<Arc<CompiledTemplate>>,
+    base_schema: Option<BaseSchema>,
     schema: Value,
     shared: Shared,
+    bif_handlers: BifHandlers,
+    loader: SharedLoader,
+    cache: Option<SharedCache>,
     time_start: Instant,
     time_elapsed: Duration,
     out: String,
 }
 
-fn default_schema_template() -> Result<Value, String> {
+pub(crate) fn default_schema_template() -> Result<Value, String> {
     static DEFAULT_SCHEMA: OnceLock<Result<Value, String>> = OnceLock::new();
     DEFAULT_SCHEMA
         .get_or_init(|| {
@@ -33,7 +42,7 @@ fn default_schema_template() -> Result<Value, String> {
 /// A struct representing a template that can be rendered.
 ///
 /// This struct is used to handle the rendering of templates.
-impl<'a> Template<'a> {
+impl Template {
     /// Constructs a new `Template` instance with default settings.
     ///
     /// It allows you to set up a template and schema with different types.
@@ -43,9 +52,14 @@ impl<'a> Template<'a> {
 
         Ok(Template {
             raw: String::new(),
-            file_path: "",
+            file_path: String::new(),
+            compiled: None,
+            base_schema: None,
             schema: default_schema,
             shared,
+            bif_handlers: BifHandlers::default(),
+            loader: default_loader(),
+            cache: None,
             time_start: Instant::now(),
             time_elapsed: Instant::now().elapsed(),
             out: String::new(),
@@ -63,12 +77,12 @@ impl<'a> Template<'a> {
     ///
     /// A `Result` containing the new `Template` instance or an error message if:
     /// - The file cannot be read.
-    pub fn from_file_value(file_path: &'a str, schema: Value) -> Result<Self, String> {
-        let raw: String = match fs::read_to_string(file_path) {
+    pub fn from_file_value(file_path: &str, schema: Value) -> Result<Self, String> {
+        let raw: String = match FileLoader.read(file_path) {
             Ok(s) => s,
             Err(e) => {
                 eprintln!("Cannot be read: {}", file_path);
-                return Err(e.to_string());
+                return Err(e);
             }
         };
         let mut default_schema = default_schema_template()?;
@@ -80,15 +94,159 @@ impl<'a> Template<'a> {
 
         Ok(Template {
             raw,
-            file_path,
+            file_path: file_path.to_string(),
+            compiled: None,
+            base_schema: None,
             schema: default_schema,
             shared,
+            bif_handlers: BifHandlers::default(),
+            loader: default_loader(),
+            cache: None,
             time_start: Instant::now(),
             time_elapsed: Instant::now().elapsed(),
             out: String::new(),
         })
     }
 
+    // A template that shares a base schema, used by Engine to create the
+    // render contexts. The base schema is never cloned, the schemas merged
+    // later with `merge_schema_*` are layered over it on each render.
+    pub(crate) fn from_compiled_base(
+        base_schema: BaseSchema,
+        compiled: Arc<CompiledTemplate>,
+    ) -> Result<Self, String> {
+        let mut template = Template {
+            raw: String::new(),
+            file_path: String::new(),
+            compiled: None,
+            base_schema: None,
+            schema: json!({}),
+            shared: Shared::with_base(json!({}), Some(base_schema.clone())),
+            bif_handlers: BifHandlers::default(),
+            loader: default_loader(),
+            cache: None,
+            time_start: Instant::now(),
+            time_elapsed: Instant::now().elapsed(),
+            out: String::new(),
+        };
+        template.base_schema = Some(base_schema);
+        template.set_src_compiled(compiled);
+
+        Ok(template)
+    }
+
+    /// Registers a custom bif.
+    ///
+    /// The bif is available in the template and in the files and snippets
+    /// it uses, see `BifHandler`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The bif name, it cannot be the name of a built-in bif.
+    /// * `handler` - The bif handler, e.g. a closure.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or an error message if:
+    /// - The name is not valid or it is a built-in bif.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::{BifContext, Template};
+    ///
+    /// let mut template = Template::new().unwrap();
+    /// template
+    ///     .register_bif("avatar", |ctx: &mut BifContext| {
+    ///         Ok(format!("<img src=\"/avatar/{}.png\">", ctx.parse_code()))
+    ///     })
+    ///     .unwrap();
+    /// template.set_src_str("{:avatar; bob :}");
+    /// assert_eq!(template.render(), "<img src=\"/avatar/bob.png\">");
+    /// ```
+    pub fn register_bif<H>(&mut self, name: &str, handler: H) -> Result<(), String>
+    where
+        H: BifHandler + 'static,
+    {
+        check_bif_name(name)?;
+        Arc::make_mut(&mut self.bif_handlers).insert(name.to_string(), Arc::new(handler));
+
+        Ok(())
+    }
+
+    pub(crate) fn set_bif_handlers(&mut self, bif_handlers: BifHandlers) {
+        self.bif_handlers = bif_handlers;
+    }
+
+    /// Sets the loader used to read the template files, see `TemplateLoader`.
+    ///
+    /// It must be set before `set_src_path`, by default the files are read
+    /// from the filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `loader` - The loader, e.g. `MemoryLoader` or `EmbeddedLoader`.
+    pub fn set_loader<L>(&mut self, loader: L)
+    where
+        L: TemplateLoader + 'static,
+    {
+        self.loader = Arc::new(loader);
+    }
+
+    pub(crate) fn set_shared_loader(&mut self, loader: SharedLoader) {
+        self.loader = loader;
+    }
+
+    /// Sets the backend of the bif cache, see `CacheBackend`.
+    ///
+    /// By default the fragments are stored in files, in `config.cache_dir`.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The backend, e.g. `MemoryCache`, in an `Arc` to share it.
+    pub fn set_cache_backend<C>(&mut self, backend: C)
+    where
+        C: CacheBackend + 'static,
+    {
+        self.cache = Some(Arc::new(backend));
+    }
+
+    pub(crate) fn set_shared_cache(&mut self, cache: Option<SharedCache>) {
+        self.cache = cache;
+    }
+
+    /// Removes the cached fragments with any of the tags, set with
+    /// `{:cache; {:flg; tags product-42 catalog :} /ttl/ >> ... :}`.
+    ///
+    /// The backend is the one set with `set_cache_backend`, or the files in
+    /// `config.cache_dir` of the schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - The tags, e.g. `&["product-42"]`.
+    ///
+    /// # Returns
+    ///
+    /// The number of fragments removed, or an error message.
+    pub fn invalidate_cache_tags(&self, tags: &[&str]) -> Result<u64, String> {
+        match &self.cache {
+            Some(cache) => cache.invalidate_tags(tags),
+            None => FileCache::from_config(
+                &self.config_value("cache_dir"),
+                &self.config_value("cache_prefix"),
+            )
+            .invalidate_tags(tags),
+        }
+    }
+
+    // A config key of the schema, or of the base schema if it is not set.
+    fn config_value(&self, key: &str) -> String {
+        match (&self.base_schema, self.schema["config"].get(key)) {
+            (Some(base), None) => get_from_key(&base.schema["config"], key),
+            _ => get_from_key(&self.schema["config"], key),
+        }
+    }
+
     /// Sets the source path of the template.
     ///
     /// # Arguments
@@ -98,13 +256,14 @@ impl<'a> Template<'a> {
     /// # Returns
     ///
     /// A `Result` indicating success or an error message if the file cannot be read
-    pub fn set_src_path(&mut self, file_path: &'a str) -> Result<(), String> {
-        self.file_path = file_path;
-        self.raw = match fs::read_to_string(file_path) {
+    pub fn set_src_path(&mut self, file_path: &str) -> Result<(), String> {
+        self.file_path = file_path.to_string();
+        self.compiled = None;
+        self.raw = match self.loader.read(file_path) {
             Ok(s) => s,
             Err(e) => {
                 eprintln!("Cannot be read: {}", file_path);
-                return Err(e.to_string());
+                return Err(e);
             }
         };
 
@@ -118,6 +277,45 @@ impl<'a> Template<'a> {
     /// * `source` - A reference to the new string content to be set as the raw content.
     pub fn set_src_str(&mut self, source: &str) {
         self.raw = source.to_string();
+        self.compiled = None;
+    }
+
+    /// Sets the content of the template from a compiled template.
+    ///
+    /// The same compiled template can be shared by many `Template` instances,
+    /// the source is not parsed again on each render. If it was compiled from
+    /// a file, that file is used as the current file for relative paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `compiled` - The compiled template, see `CompiledTemplate`.
+    pub fn set_src_compiled(&mut self, compiled: Arc<CompiledTemplate>) {
+        self.raw = compiled.source().to_string();
+        self.compiled = Some(compiled);
+    }
+
+    /// Returns the compiled form of the template source.
+    ///
+    /// The template is compiled on the first render, or now if it has not
+    /// been rendered yet. Comments are not removed if it is compiled here.
+    ///
+    /// # Returns
+    ///
+    /// The compiled template, it can be shared with `set_src_compiled`.
+    pub fn get_compiled(&mut self) -> Arc<CompiledTemplate> {
+        if self.compiled.is_none() {
+            self.compile_src(false);
+        }
+
+        Arc::clone(self.compiled.as_ref().unwrap())
+    }
+
+    // Compile the template source, the positions of the errors are reported
+    // in the source before removing comments.
+    fn compile_src(&mut self, remove_comments: bool) {
+        let mut compiled = CompiledTemplate::from_source(&self.raw, remove_comments);
+        compiled.set_file_path(&self.file_path);
+        self.compiled = Some(Arc::new(compiled));
     }
 
     /// Merges the schema from a file with the current template schema.
@@ -201,7 +399,7 @@ impl<'a> Template<'a> {
     /// let bytes = vec![129, 164, 100, 97, 116, 97, 129, 163, 107, 101, 121, 165, 118, 97, 108, 117, 101];
     /// let template = Template::from_file_msgpack("template.ntpl", &bytes).unwrap();
     /// ```
-    pub fn from_file_msgpack(file_path: &'a str, bytes: &[u8]) -> Result<Self, String> {
+    pub fn from_file_msgpack(file_path: &str, bytes: &[u8]) -> Result<Self, String> {
         let schema: Value = if bytes.is_empty() {
             json!({})
         } else {
@@ -289,23 +487,179 @@ impl<'a> Template<'a> {
         // This avoids cloning large schemas for templates with plain text/empty source.
         self.time_start = Instant::now();
         if !self.raw.contains(BIF_OPEN) {
-            self.out = self.raw.trim().to_string();
-            self.time_elapsed = self.time_start.elapsed();
+            self.render_plain();
             return self.out.clone();
         }
 
+        self.render_body();
+        self.set_status_code();
+
+        self.out.clone()
+    }
+
+    /// Renders the template content and returns all the render produced.
+    ///
+    /// Instead of calling `render()` and then `get_status_code()`,
+    /// `get_status_param()`, `get_errors()`, ... one after another.
+    ///
+    /// Unlike `render()`, the body is not replaced by the status text on
+    /// 3xx, 4xx or 5xx, the HTTP layer decides how to send these responses.
+    /// A Javascript redirect is a "200 OK" and its body is the script.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::Template;
+    ///
+    /// let mut template = Template::new().unwrap();
+    /// template.set_src_str("<p>Login required</p>{:redirect; 302 >> /login :}");
+    ///
+    /// let output = template.render_full();
+    /// assert_eq!(output.status, "302");
+    /// assert_eq!(output.location.as_deref(), Some("/login"));
+    /// assert_eq!(output.headers, vec![("Location".to_string(), "/login".to_string())]);
+    /// assert_eq!(output.body, "<p>Login required</p>");
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `RenderOutput`: The body, status, headers, errors, duration and stats.
+    pub fn render_full(&mut self) -> RenderOutput {
+        self.time_start = Instant::now();
+        if !self.raw.contains(BIF_OPEN) {
+            self.render_plain();
+        } else {
+            self.render_body();
+            if !self.shared.aborted && !self.shared.redirect_js.is_empty() {
+                self.out = self.shared.redirect_js.clone();
+            }
+        }
+
+        let status = self.shared.status_code.clone();
+        let mut headers = Vec::new();
+        let location = if is_redirect(&status) && !self.shared.status_param.is_empty() {
+            headers.push(("Location".to_string(), self.shared.status_param.clone()));
+            Some(self.shared.status_param.clone())
+        } else {
+            None
+        };
+
+        // The location of the redirect replaces the one set by the bif header.
+        for (name, value) in self.shared.response_headers() {
+            if location.is_none() || !name.eq_ignore_ascii_case("Location") {
+                headers.push((name, value));
+            }
+        }
+
+        RenderOutput {
+            body: std::mem::take(&mut self.out),
+            status,
+            status_text: self.shared.status_text.clone(),
+            location,
+            headers,
+            errors: self.shared.errors.clone(),
+            duration: self.time_elapsed,
+            stats: RenderStats {
+                bifs: self.shared.bisf_count,
+            },
+        }
+    }
+
+    // The output of a source without bifs, the render state of the
+    // previous render is cleared.
+    fn render_plain(&mut self) {
+        self.shared.clear_render();
+        self.out = self.raw.trim().to_string();
+        self.time_elapsed = self.time_start.elapsed();
+    }
+
+    // Parse the template into self.out, without the status code.
+    fn render_body(&mut self) {
         let inherit = self.init_render();
-        self.out = BlockParser::new(&mut self.shared, inherit.clone()).parse(&self.raw, "");
+        let compiled = self.get_compiled();
+        let mut root_inherit = inherit.clone();
+        root_inherit.origin = Some(SourceOrigin::new(Arc::clone(&compiled), 0));
+        self.out = BlockParser::new(&mut self.shared, root_inherit).parse_compiled(&compiled, "");
+        self.finish_render(&inherit);
+    }
 
-        while self.out.contains("{:!cache;") {
-            let out;
-            out = BlockParser::new(&mut self.shared, inherit.clone()).parse(&self.out, "!cache");
-            self.out = out;
+    /// Renders the template content into a writer.
+    ///
+    /// Same as `render()`, but the output is written as each bif of the
+    /// template is parsed, instead of building the whole output first,
+    /// which is useful for large pages.
+    ///
+    /// The output that has been written cannot be changed, so what the bifs
+    /// of the template can still change is held: the output before its last
+    /// exit, redirect or extends, and the output from the tag of each moveto
+    /// until the moveto is parsed. For these the output is the same as `render()`.
+    ///
+    /// The bifs of included files, snippets or custom bifs are not known until
+    /// they are parsed: a moveto only reaches the tags not yet written, and an
+    /// exit or a redirect only replaces the output if nothing has been written.
+    ///
+    /// `get_headers()` has all the headers only when the render ends, the
+    /// headers set after the output has started cannot be sent before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where the output is written, e.g. a file or a socket.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::Template;
+    ///
+    /// let mut template = Template::new().unwrap();
+    /// template.set_src_str("<ul>{:each; __test-arr_nts key value >> <li>{:;value:}</li> :}</ul>");
+    ///
+    /// let mut out = Vec::new();
+    /// template.render_to(&mut out).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), "<ul><li>one</li><li>two</li><li>three</li></ul>");
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// An error if the writer fails, the render errors are in `get_errors()`.
+    pub fn render_to<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.time_start = Instant::now();
+        self.out = String::new();
+        if !self.raw.contains(BIF_OPEN) {
+            self.render_plain();
+            return writer.write_all(std::mem::take(&mut self.out).as_bytes());
         }
 
-        self.ends_render();
+        let inherit = self.init_render();
+        let compiled = self.get_compiled();
+        let mut root_inherit = inherit.clone();
+        root_inherit.origin = Some(SourceOrigin::new(Arc::clone(&compiled), 0));
+        let mut stream = RenderStream::new(writer, inherit.clone(), OutputHolds::new(&compiled));
+        BlockParser::new(&mut self.shared, root_inherit).parse_compiled_to(
+            &compiled,
+            "",
+            &mut stream,
+        );
+
+        if stream.written {
+            // Anything held after an exit or a redirect is not written.
+            if !stream.is_replaced(&self.shared) {
+                stream.write_pending(&mut self.shared, true);
+            }
+            self.time_elapsed = self.time_start.elapsed();
+        } else {
+            self.out = std::mem::take(&mut stream.pending).trim().to_string();
+            self.finish_render(&inherit);
+            self.set_status_code();
+            if stream.error.is_none() {
+                stream.error = stream.writer.write_all(self.out.as_bytes()).err();
+            }
+            self.out = String::new();
+        }
 
-        self.out.clone()
+        match stream.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
     }
 
     /// Renders the template content without cloning the schema.
@@ -372,32 +726,47 @@ impl<'a> Template<'a> {
         // Fast path: when there are no blocks, skip full render initialization.
         self.time_start = Instant::now();
         if !self.raw.contains(BIF_OPEN) {
-            self.out = self.raw.trim().to_string();
-            self.time_elapsed = self.time_start.elapsed();
+            self.render_plain();
             return self.out.clone();
         }
 
         let inherit = self.init_render_once();
-        self.out = BlockParser::new(&mut self.shared, inherit.clone()).parse(&self.raw, "");
+        let compiled = self.get_compiled();
+        let mut root_inherit = inherit.clone();
+        root_inherit.origin = Some(SourceOrigin::new(Arc::clone(&compiled), 0));
+        self.out = BlockParser::new(&mut self.shared, root_inherit).parse_compiled(&compiled, "");
+        self.finish_render(&inherit);
+        self.set_status_code();
 
+        self.out.clone()
+    }
+
+    // Parse the {:!cache; ... :} left by the cache and ends the render.
+    fn finish_render(&mut self, inherit: &BlockInherit) {
         while self.out.contains("{:!cache;") {
-            let out;
-            out = BlockParser::new(&mut self.shared, inherit.clone()).parse(&self.out, "!cache");
+            let out =
+                BlockParser::new(&mut self.shared, inherit.clone()).parse(&self.out, "!cache");
             self.out = out;
         }
 
         self.ends_render();
-
-        self.out.clone()
     }
 
     // Restore vars for render (clones schema for reusability)
     fn init_render(&mut self) -> BlockInherit {
         self.time_start = Instant::now();
-        self.shared = Shared::new(self.schema.clone());
+        self.shared = Shared::with_base(self.schema.clone(), self.base_schema.clone());
+
+        self.shared.bif_handlers = Arc::clone(&self.bif_handlers);
+        self.shared.set_loader(Arc::clone(&self.loader));
+        if let Some(cache) = &self.cache {
+            self.shared.cache = Arc::clone(cache);
+            // The purge is for the files of the default backend.
+            self.shared.cache_purge_every = 0;
+        }
 
-        if self.shared.comments.contains("remove") {
-            self.raw = remove_comments(&self.raw);
+        if self.compiled.is_none() {
+            self.compile_src(self.shared.comments.contains("remove"));
         }
 
         // init inherit
@@ -405,18 +774,26 @@ impl<'a> Template<'a> {
         let indir = inherit.create_block_schema(&mut self.shared);
         self.shared.schema["__moveto"] = json!({});
         self.shared.schema["__error"] = json!([]);
+        if !self.shared.config_error.is_empty() {
+            self.shared.abort(RenderError {
+                kind: RenderErrorKind::Render,
+                bif_name: String::new(),
+                message: self.shared.config_error.clone(),
+                file: self.src_path().to_string(),
+                line: 0,
+                column: 0,
+                snippet: String::new(),
+            });
+        }
         self.shared.indir_store.clear();
-        self.shared.indir_store.insert(indir, Rc::new(self.shared.schema["inherit"].clone()));
-        inherit.current_file = self.file_path.to_string();
-
-        // Escape CONTEXT values
-        filter_value(&mut self.shared.schema["data"]["CONTEXT"]);
+        let root_inherit = self.shared.root_inherit();
+        self.shared.indir_store.insert(indir, root_inherit);
+        inherit.current_file = self.src_path().to_string();
 
-        // Escape CONTEXT keys names
-        filter_value_keys(&mut self.shared.schema["data"]["CONTEXT"]);
+        self.shared.filter_context();
 
-        if !self.file_path.is_empty() {
-            let path = Path::new(&self.file_path);
+        if !self.src_path().is_empty() {
+            let path = Path::new(self.src_path());
 
             if let Some(parent) = path.parent() {
                 inherit.current_dir = parent.display().to_string();
@@ -437,10 +814,18 @@ impl<'a> Template<'a> {
         self.time_start = Instant::now();
         // Take ownership of schema instead of cloning - leaves empty object in place
         let schema = std::mem::take(&mut self.schema);
-        self.shared = Shared::new(schema);
+        self.shared = Shared::with_base(schema, self.base_schema.clone());
+
+        self.shared.bif_handlers = Arc::clone(&self.bif_handlers);
+        self.shared.set_loader(Arc::clone(&self.loader));
+        if let Some(cache) = &self.cache {
+            self.shared.cache = Arc::clone(cache);
+            // The purge is for the files of the default backend.
+            self.shared.cache_purge_every = 0;
+        }
 
-        if self.shared.comments.contains("remove") {
-            self.raw = remove_comments(&self.raw);
+        if self.compiled.is_none() {
+            self.compile_src(self.shared.comments.contains("remove"));
         }
 
         // init inherit
@@ -448,18 +833,26 @@ impl<'a> Template<'a> {
         let indir = inherit.create_block_schema(&mut self.shared);
         self.shared.schema["__moveto"] = json!({});
         self.shared.schema["__error"] = json!([]);
+        if !self.shared.config_error.is_empty() {
+            self.shared.abort(RenderError {
+                kind: RenderErrorKind::Render,
+                bif_name: String::new(),
+                message: self.shared.config_error.clone(),
+                file: self.src_path().to_string(),
+                line: 0,
+                column: 0,
+                snippet: String::new(),
+            });
+        }
         self.shared.indir_store.clear();
-        self.shared.indir_store.insert(indir, Rc::new(self.shared.schema["inherit"].clone()));
-        inherit.current_file = self.file_path.to_string();
+        let root_inherit = self.shared.root_inherit();
+        self.shared.indir_store.insert(indir, root_inherit);
+        inherit.current_file = self.src_path().to_string();
 
-        // Escape CONTEXT values
-        filter_value(&mut self.shared.schema["data"]["CONTEXT"]);
+        self.shared.filter_context();
 
-        // Escape CONTEXT keys names
-        filter_value_keys(&mut self.shared.schema["data"]["CONTEXT"]);
-
-        if !self.file_path.is_empty() {
-            let path = Path::new(&self.file_path);
+        if !self.src_path().is_empty() {
+            let path = Path::new(self.src_path());
 
             if let Some(parent) = path.parent() {
                 inherit.current_dir = parent.display().to_string();
@@ -475,15 +868,28 @@ impl<'a> Template<'a> {
         inherit
     }
 
+    // Path of the template file, if the source was set from a compiled file
+    // it is the one of the compiled template.
+    fn src_path(&self) -> &str {
+        match &self.compiled {
+            Some(compiled) if self.file_path.is_empty() => compiled.file_path(),
+            _ => &self.file_path,
+        }
+    }
+
     // Rendering ends
     fn ends_render(&mut self) {
         self.set_moveto();
         self.replacements();
-        self.set_status_code();
         self.time_elapsed = self.time_start.elapsed();
     }
 
     fn set_status_code(&mut self) {
+        // The output produced before the render was stopped is kept.
+        if self.shared.aborted {
+            return;
+        }
+
         let status_code = self.shared.status_code.as_str();
 
         if ("400"..="599").contains(&status_code) {
@@ -492,12 +898,7 @@ impl<'a> Template<'a> {
             return;
         }
 
-        if status_code == "301"
-            || status_code == "302"
-            || status_code == "303"
-            || status_code == "307"
-            || status_code == "308"
-        {
+        if is_redirect(status_code) {
             self.out = format!(
                 "{} {}\n{}",
                 self.shared.status_code, self.shared.status_text, self.shared.status_param
@@ -512,50 +913,55 @@ impl<'a> Template<'a> {
     }
 
     fn set_moveto(&mut self) {
-        if let Value::Object(data_map) = &self.shared.schema["__moveto"] {
-            for (_key, value) in data_map {
-                if let Value::Object(inner_map) = value {
-                    for (inner_key, inner_value) in inner_map {
-                        let mut tag;
-
-                        // although it should be "<tag" or "</tag" it also supports
-                        // "tag", "/tag", "<tag>" and "</tag>
-                        if !inner_key.starts_with("<") {
-                            tag = format!("<{}", inner_key);
-                        } else {
-                            tag = inner_key.to_string();
-                        }
-                        if tag.ends_with(">") {
-                            tag = tag[..tag.len() - 1].to_string();
-                        }
-
-                        // if it does not find it, it does nothing
-                        let position = find_tag_position(&self.out, &tag);
-                        if let Some(pos) = position {
-                            let mut insert = inner_value.as_str().unwrap().to_string();
-                            insert = insert.to_string();
-                            self.out.insert_str(pos, &insert);
-                        }
-                    }
-                }
-            }
-        }
+        insert_moveto(
+            &mut self.out,
+            &self.shared.schema["__moveto"],
+            &mut HashSet::new(),
+        );
     }
 
     fn replacements(&mut self) {
-        if self.out.contains(BACKSPACE) {
-            lazy_static::lazy_static! {
-                static ref RE: Regex = Regex::new(&format!(r"\s*{}", BACKSPACE)).expect("Failed to create regex with constant pattern");
-            }
-            if let std::borrow::Cow::Owned(s) = RE.replace_all(&self.out, "") {
-                self.out = s;
-            }
-        }
+        replacements(&mut self.out);
+    }
 
-        // UNPRINTABLE should be substituted after BACKSPACE
-        if self.out.contains(UNPRINTABLE) {
-            self.out = self.out.replace(UNPRINTABLE, "");
-        }
+    /// Checks the template without rendering it.
+    ///
+    /// The template and the files it includes are walked without parsing
+    /// them, so the problems in code paths that a render does not reach are
+    /// also found:
+    ///
+    /// - Unmatched blocks and bifs without the name delimiter.
+    /// - Unknown bifs, the custom bifs registered are known.
+    /// - Modifiers and flags that a bif does not accept.
+    /// - The deprecated bif count.
+    /// - Snippets and declare set outside snippet files.
+    ///
+    /// The included files are only checked if their name is not set by bifs,
+    /// as the schema data is not used. The config of the schema is used,
+    /// e.g.: `template_dirs` or `allowed_roots`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::Template;
+    ///
+    /// let mut template = Template::new().unwrap();
+    /// template.set_src_str("{:bool; logged >> {:&include; user.ntpl :} :}");
+    ///
+    /// let errors = template.check();
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].bif_name, "include");
+    /// assert_eq!(errors[0].message, "modifier not allowed");
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<RenderError>`: The problems found, empty if there are none.
+    pub fn check(&mut self) -> Vec<RenderError> {
+        let inherit = self.init_render();
+        let compiled = self.get_compiled();
+
+        Checker::new(&self.shared).check(compiled, &inherit.current_file, &inherit.current_dir)
     }
 
     /// Retrieves the status code.
@@ -595,6 +1001,34 @@ impl<'a> Template<'a> {
         &self.shared.status_param
     }
 
+    /// Retrieves the response headers.
+    ///
+    /// The headers set with `{:header; ... :}` and a "Set-Cookie" for each
+    /// `{:cookie; ... :}`, in the order they were first set. The application
+    /// adds them to the response.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::Template;
+    ///
+    /// let mut template = Template::new().unwrap();
+    /// template.set_src_str("{:header; Cache-Control >> no-store :}{:cookie; theme >> dark; Path=/ :}");
+    /// template.render();
+    ///
+    /// assert_eq!(template.get_headers(), vec![
+    ///     ("Cache-Control".to_string(), "no-store".to_string()),
+    ///     ("Set-Cookie".to_string(), "theme=dark; Path=/".to_string()),
+    /// ]);
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(String, String)>`: The name and value of each header.
+    pub fn get_headers(&self) -> Vec<(String, String)> {
+        self.shared.response_headers()
+    }
+
     /// Checks if there is an error.
     ///
     /// If any error has occurred, in the parse or otherwise, it will return true.
@@ -615,6 +1049,42 @@ impl<'a> Template<'a> {
         self.shared.schema["__error"].clone()
     }
 
+    /// Get the errors of the last render.
+    ///
+    /// The same errors as `get_error`, with the bif and the file, line
+    /// and column where it is written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use neutralts::{RenderErrorKind, Template};
+    ///
+    /// let mut template = Template::new().unwrap();
+    /// template.set_src_str("<div>\n  {:include; {:flg; require :} >> not-found.ntpl :}\n</div>");
+    /// template.render();
+    ///
+    /// let error = &template.get_errors()[0];
+    /// assert_eq!(error.kind, RenderErrorKind::Bif);
+    /// assert_eq!(error.bif_name, "include");
+    /// assert_eq!((error.line, error.column), (2, 3));
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// * `&[RenderError]`: The list of errors, empty if there are none.
+    pub fn get_errors(&self) -> &[RenderError] {
+        &self.shared.errors
+    }
+
+    /// Retrieves the counters of the bif cache of the last render.
+    ///
+    /// # Returns
+    ///
+    /// * `&CacheStats`: The hits, misses, writes, errors and bytes served.
+    pub fn get_cache_stats(&self) -> &CacheStats {
+        &self.shared.cache_stats
+    }
+
     /// Retrieves the time duration for template rendering.
     ///
     /// # Returns
@@ -626,3 +1096,211 @@ impl<'a> Template<'a> {
         duration
     }
 }
+
+// Inserts each moveto before its tag, those inserted are added to "inserted"
+// and skipped the next time.
+fn insert_moveto(out: &mut String, moveto: &Value, inserted: &mut HashSet<String>) {
+    if let Value::Object(data_map) = moveto {
+        for (key, value) in data_map {
+            if inserted.contains(key) {
+                continue;
+            }
+
+            if let Value::Object(inner_map) = value {
+                for (inner_key, inner_value) in inner_map {
+                    let tag = moveto_tag(inner_key);
+
+                    // if it does not find it, it does nothing
+                    let position = find_tag_position(out, &tag);
+                    if let Some(pos) = position {
+                        let mut insert = inner_value.as_str().unwrap().to_string();
+                        insert = insert.to_string();
+                        out.insert_str(pos, &insert);
+                        inserted.insert(key.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The tag where a moveto inserts its code.
+fn moveto_tag(to: &str) -> String {
+    let mut tag;
+
+    // although it should be "<tag" or "</tag" it also supports
+    // "tag", "/tag", "<tag>" and "</tag>
+    if !to.starts_with("<") {
+        tag = format!("<{}", to);
+    } else {
+        tag = to.to_string();
+    }
+    if tag.ends_with(">") {
+        tag = tag[..tag.len() - 1].to_string();
+    }
+
+    tag
+}
+
+fn is_redirect(status_code: &str) -> bool {
+    ["301", "302", "303", "307", "308"].contains(&status_code)
+}
+
+fn replacements(out: &mut String) {
+    if out.contains(BACKSPACE) {
+        lazy_static::lazy_static! {
+            static ref RE: Regex = Regex::new(&format!(r"\s*{}", BACKSPACE)).expect("Failed to create regex with constant pattern");
+        }
+        if let std::borrow::Cow::Owned(s) = RE.replace_all(out, "") {
+            *out = s;
+        }
+    }
+
+    // UNPRINTABLE should be substituted after BACKSPACE
+    if out.contains(UNPRINTABLE) {
+        *out = out.replace(UNPRINTABLE, "");
+    }
+}
+
+// The bifs of the template that change the output produced before them,
+// each segment of Template::render_to is the output of a top-level bif.
+#[derive(Default)]
+struct OutputHolds {
+    // The segments before the last top-level bif with an exit, a redirect,
+    // an extends or a moveto whose tag is not known until it is parsed.
+    until: usize,
+    // The segment of each moveto and its tag.
+    moveto: Vec<(usize, String)>,
+}
+
+impl OutputHolds {
+    fn new(compiled: &CompiledTemplate) -> Self {
+        let mut holds = OutputHolds::default();
+        for (segment, block) in compiled.blocks().unwrap_or_default().iter().enumerate() {
+            holds.add(segment, block);
+        }
+
+        holds
+    }
+
+    fn add(&mut self, segment: usize, block: &CompiledBlock) {
+        match block.name() {
+            "moveto" if !block.params().contains(BIF_OPEN) => {
+                self.moveto.push((segment, moveto_tag(block.params())));
+            }
+            "moveto" | "exit" | "redirect" | "extends" => self.until = segment + 1,
+            _ => (),
+        }
+
+        for tree in block.params_tree().into_iter().chain(block.code_tree()) {
+            for child in tree.blocks().unwrap_or_default() {
+                self.add(segment, child);
+            }
+        }
+    }
+
+    // Where the output can be written up to, the tags of the movetos not
+    // parsed yet are kept.
+    fn write_end(&self, segments: usize, out: &str) -> usize {
+        self.moveto
+            .iter()
+            .filter(|(segment, _)| *segment >= segments)
+            .filter_map(|(_, tag)| out.find(tag.as_str()))
+            .fold(out.len(), usize::min)
+    }
+}
+
+// Writes the output of Template::render_to as the bifs of the template are
+// parsed, what can still be changed by the end of the render is kept.
+struct RenderStream<'w, W: Write> {
+    writer: &'w mut W,
+    inherit: BlockInherit,
+    holds: OutputHolds,
+    segments: usize,
+    pending: String,
+    written: bool,
+    moveto_inserted: HashSet<String>,
+    error: Option<io::Error>,
+}
+
+impl<'w, W: Write> RenderStream<'w, W> {
+    fn new(writer: &'w mut W, inherit: BlockInherit, holds: OutputHolds) -> Self {
+        RenderStream {
+            writer,
+            inherit,
+            holds,
+            segments: 0,
+            pending: String::new(),
+            written: false,
+            moveto_inserted: HashSet::new(),
+            error: None,
+        }
+    }
+
+    // The output is replaced by the status code or a redirect, see set_status_code.
+    fn is_replaced(&self, shared: &Shared) -> bool {
+        let status_code = shared.status_code.as_str();
+
+        !shared.aborted
+            && (("400"..="599").contains(&status_code)
+                || is_redirect(status_code)
+                || !shared.redirect_js.is_empty())
+    }
+
+    fn write_pending(&mut self, shared: &mut Shared, last: bool) {
+        let mut chunk = std::mem::take(&mut self.pending);
+
+        // The output is trimmed as in render, the trailing whitespace is
+        // written with the next segment.
+        let end = chunk.trim_end().len();
+        if !last {
+            let end = self.holds.write_end(self.segments, &chunk[..end]);
+            self.pending = chunk.split_off(end);
+        } else {
+            chunk.truncate(end);
+        }
+        if !self.written {
+            chunk = chunk.trim_start().to_string();
+        }
+
+        if chunk.is_empty() || self.error.is_some() {
+            return;
+        }
+
+        while chunk.contains("{:!cache;") {
+            let start = chunk.len() - chunk.trim_start().len();
+            let end = chunk.trim_end().len();
+            let out =
+                BlockParser::new(shared, self.inherit.clone()).parse(&chunk[start..end], "!cache");
+            chunk = format!("{}{}{}", &chunk[..start], out, &chunk[end..]);
+        }
+
+        insert_moveto(
+            &mut chunk,
+            &shared.schema["__moveto"],
+            &mut self.moveto_inserted,
+        );
+        replacements(&mut chunk);
+
+        match self.writer.write_all(chunk.as_bytes()) {
+            Ok(()) => self.written = true,
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+impl<W: Write> SegmentSink for RenderStream<'_, W> {
+    fn segment(&mut self, shared: &mut Shared, segment: &str) {
+        self.pending.push_str(segment);
+        self.segments += 1;
+
+        if self.segments >= self.holds.until && !self.is_replaced(shared) {
+            self.write_pending(shared, false);
+        }
+    }
+
+    fn discard(&mut self) {
+        self.pending.clear();
+        self.segments += 1;
+    }
+}
@@ -287,16 +287,16 @@ pub fn strip_prefix_suffix<'a>(str: &'a str, prefix: &'a str, suffix: &'a str) -
 ///
 /// * A `String` containing the retrieved value, or an empty string if the key is not found.
 pub fn get_from_key(schema: &Value, key: &str) -> String {
-    if let Some(v) = resolve_pointer(schema, key) {
-        match v {
-            Value::Null => String::new(),
-            Value::Bool(b) => b.to_string(),
-            Value::Number(n) => n.to_string(),
-            Value::String(s) => s.clone(),
-            _ => String::new(),
-        }
-    } else {
-        String::new()
+    get_from_value(resolve_pointer(schema, key))
+}
+
+// The value of a key as a string, see get_from_key.
+pub(crate) fn get_from_value(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::Bool(b)) => b.to_string(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::String(s)) => s.clone(),
+        _ => String::new(),
     }
 }
 
@@ -311,7 +311,12 @@ pub fn get_from_key(schema: &Value, key: &str) -> String {
 ///
 /// * `true` if the value is considered empty, otherwise `false`.
 pub fn is_empty_key(schema: &Value, key: &str) -> bool {
-    if let Some(value) = resolve_pointer(schema, key) {
+    is_empty_value(resolve_pointer(schema, key))
+}
+
+// Whether the value of a key is empty, see is_empty_key.
+pub(crate) fn is_empty_value(value: Option<&Value>) -> bool {
+    if let Some(value) = value {
         match value {
             Value::Object(map) => map.is_empty(),
             Value::Array(arr) => arr.is_empty(),
@@ -336,7 +341,12 @@ pub fn is_empty_key(schema: &Value, key: &str) -> bool {
 ///
 /// * `true` if the value is considered a boolean true, otherwise `false`.
 pub fn is_bool_key(schema: &Value, key: &str) -> bool {
-    if let Some(value) = resolve_pointer(schema, key) {
+    is_bool_value(resolve_pointer(schema, key))
+}
+
+// Whether the value of a key is true, see is_bool_key.
+pub(crate) fn is_bool_value(value: Option<&Value>) -> bool {
+    if let Some(value) = value {
         match value {
             Value::Object(obj) => !obj.is_empty(),
             Value::Array(arr) => !arr.is_empty(),
@@ -362,15 +372,12 @@ pub fn is_bool_key(schema: &Value, key: &str) -> bool {
 ///
 /// * `true` if the value is an array, otherwise `false`.
 pub fn is_array_key(schema: &Value, key: &str) -> bool {
-    if let Some(value) = resolve_pointer(schema, key) {
-        match value {
-            Value::Object(_) => true,
-            Value::Array(_) => true,
-            _ => false,
-        }
-    } else {
-        false
-    }
+    is_array_value(resolve_pointer(schema, key))
+}
+
+// Whether the value of a key is an array or an object, see is_array_key.
+pub(crate) fn is_array_value(value: Option<&Value>) -> bool {
+    matches!(value, Some(Value::Object(_)) | Some(Value::Array(_)))
 }
 
 /// Checks if the value associated with a key in the schema is considered defined.
@@ -384,10 +391,12 @@ pub fn is_array_key(schema: &Value, key: &str) -> bool {
 ///
 /// * `true` if the value is defined and not null, otherwise `false`.
 pub fn is_defined_key(schema: &Value, key: &str) -> bool {
-    match resolve_pointer(schema, key) {
-        Some(value) => !value.is_null(),
-        None => false,
-    }
+    is_defined_value(resolve_pointer(schema, key))
+}
+
+// Whether the value of a key is defined and not null, see is_defined_key.
+pub(crate) fn is_defined_value(value: Option<&Value>) -> bool {
+    matches!(value, Some(value) if !value.is_null())
 }
 
 /// Helper function to resolve a pointer-like key (e.g., "a->b->0") in a JSON Value.
@@ -449,6 +458,59 @@ pub(crate) fn resolve_pointer<'a>(schema: &'a Value, key: &str) -> Option<&'a Va
     Some(current)
 }
 
+// The parts of a pointer-like key, see resolve_pointer.
+pub(crate) fn pointer_parts(key: &str) -> Vec<&str> {
+    if !key.contains(BIF_ARRAY) && !key.contains('/') {
+        return vec![key];
+    }
+
+    key.split('/')
+        .flat_map(|part| part.split(BIF_ARRAY))
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+// A value of a schema layered over a base schema, as if it had been merged
+// into the base with merge_schema. The objects that are in both are merged
+// into a copy, the other values are not cloned.
+pub(crate) fn layered_value<'a>(
+    schema: &'a Value,
+    base: &'a Value,
+    parts: &[&str],
+) -> Option<Cow<'a, Value>> {
+    let mut top = Some(schema);
+    let mut below = Some(base);
+
+    for part in parts {
+        // Only an object is merged, other values replace those of the base.
+        if let Some(value) = top {
+            if !value.is_object() || !below.is_some_and(Value::is_object) {
+                below = None;
+            }
+        }
+        top = top.and_then(|value| pointer_get(value, part));
+        below = below.and_then(|value| pointer_get(value, part));
+    }
+
+    match (top, below) {
+        (Some(top @ Value::Object(_)), Some(below @ Value::Object(_))) => {
+            let mut merged = below.clone();
+            merge_schema(&mut merged, top);
+            Some(Cow::Owned(merged))
+        }
+        (Some(top), _) => Some(Cow::Borrowed(top)),
+        (None, below) => below.map(Cow::Borrowed),
+    }
+}
+
+fn pointer_get<'a>(value: &'a Value, part: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map.get(part),
+        Value::Array(arr) => arr.get(part.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
 /// Finds the position of the first occurrence of BIF_CODE_B in the source string,
 /// but only when it is not inside any nested brackets.
 ///
@@ -494,9 +556,109 @@ pub fn get_code_position(src: &str) -> Option<usize> {
     None
 }
 
+/// Splits named arguments, the values can be quoted and contain bifs.
+///
+/// ```text
+///  title="Hello World" body={:;text:} footer
+///  -> [("title", Some("Hello World")), ("body", Some("{:;text:}")), ("footer", None)]
+/// ```
+pub(crate) fn split_named_args(src: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut args = Vec::new();
+    let mut rest = src.trim_start();
+
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!("invalid argument name: {}", name));
+        }
+
+        rest = &rest[name_end..];
+
+        let Some(value_src) = rest.strip_prefix('=') else {
+            args.push((name.to_string(), None));
+            rest = rest.trim_start();
+            continue;
+        };
+
+        let quoted = value_src.starts_with('"');
+        let value_src = if quoted { &value_src[1..] } else { value_src };
+        let mut level = 0;
+        let mut value_end = None;
+        let mut i = 0;
+
+        while i < value_src.len() {
+            let part = &value_src[i..];
+            if part.starts_with(BIF_OPEN) {
+                level += 1;
+                i += BIF_OPEN.len();
+            } else if part.starts_with(BIF_CLOSE) && level > 0 {
+                level -= 1;
+                i += BIF_CLOSE.len();
+            } else {
+                let c = part.chars().next().unwrap_or_default();
+                if level == 0 && ((quoted && c == '"') || (!quoted && c.is_whitespace())) {
+                    value_end = Some(i);
+                    break;
+                }
+                i += c.len_utf8();
+            }
+        }
+
+        let value_end = match value_end {
+            Some(end) => end,
+            None if quoted => return Err(format!("unclosed quote in argument: {}", name)),
+            None => value_src.len(),
+        };
+
+        args.push((name.to_string(), Some(value_src[..value_end].to_string())));
+
+        let skip = if quoted { value_end + 1 } else { value_end };
+        rest = value_src[skip..].trim_start();
+    }
+
+    Ok(args)
+}
+
+/// Checks if it is a valid name for an HTTP header or a cookie (RFC 7230 token).
+pub(crate) fn is_http_token(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+/// Checks if a canonical path is in one of the canonical roots, any if there are none.
+pub(crate) fn is_path_in_roots(canonical_path: &str, roots: &[String]) -> bool {
+    roots.is_empty()
+        || roots
+            .iter()
+            .any(|root| std::path::Path::new(canonical_path).starts_with(root))
+}
+
 /// Removes comments from the template source.
 pub fn remove_comments(raw_source: &str) -> String {
     let mut result = String::new();
+    let mut prev_end = 0;
+
+    for (start, end) in comments_spans(raw_source) {
+        result.push_str(&raw_source[prev_end..start]);
+        prev_end = end;
+    }
+    result.push_str(&raw_source[prev_end..]);
+
+    result
+}
+
+// Positions of the text removed by remove_comments, in order.
+pub(crate) fn comments_spans(raw_source: &str) -> Vec<(usize, usize)> {
     let mut blocks = Vec::new();
     let bytes = raw_source.as_bytes();
     let mut curr_pos: usize = 0;
@@ -541,14 +703,13 @@ pub fn remove_comments(raw_source: &str) -> String {
         }
     }
 
-    let mut prev_end = 0;
-    for (start, end) in &blocks {
-        result.push_str(&raw_source[prev_end..*start]);
-        prev_end = *end;
+    // An unclosed comment is removed up to where the search ended.
+    let prev_end = blocks.last().map_or(0, |(_, end)| *end);
+    if curr_pos > prev_end {
+        blocks.push((prev_end, curr_pos));
     }
-    result.push_str(&raw_source[curr_pos..]);
 
-    result
+    blocks
 }
 
 /// Performs a wildcard matching between a text and a pattern.
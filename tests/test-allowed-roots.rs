@@ -0,0 +1,106 @@
+use neutralts::test_helpers::*;
+use neutralts::Template;
+use serde_json::json;
+use std::fs;
+
+fn new_template(allowed_roots: serde_json::Value) -> Template {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.merge_schema_value(json!({
+        "config": { "allowed_roots": allowed_roots },
+        "inherit": { "locale": { "current": "es" } }
+    }));
+    template
+}
+
+#[test]
+fn test_allowed_roots_empty() {
+    let mut template = new_template(json!([]));
+    template.set_src_str("<div>{:include; tests/include.txt :}</div>");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div>Lorem Ipsum <div></div></div>");
+}
+
+#[test]
+fn test_allowed_roots_inside() {
+    let mut template = new_template(json!(["tests"]));
+    template.set_src_str(
+        "{:locale; tests/locale.es.json :}{:data; tests/local-data.json :}<div>{:include; tests/include.txt :}</div>",
+    );
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div>Lorem Ipsum <div></div></div>");
+}
+
+#[test]
+fn test_allowed_roots_outside() {
+    let mut template = new_template(json!(["tests"]));
+    template.set_src_str("<div>{:include; tests/../Cargo.toml :}</div>");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(result, "<div></div>");
+    let error = &template.get_errors()[0];
+    assert_eq!(error.bif_name, "include");
+    assert!(error.message.contains("path outside allowed roots"));
+}
+
+#[test]
+fn test_allowed_roots_passwd() {
+    for bif in ["data", "locale", "include"] {
+        let mut template = new_template(json!(["tests"]));
+        template.set_src_str(&format!("<div>{{:{}; /etc/passwd :}}</div>", bif));
+        let result = template.render();
+        assert!(template.has_error());
+        assert_eq!(result, "<div></div>");
+        assert!(!result.contains("root:"));
+        assert!(template.get_errors()[0]
+            .message
+            .contains("path outside allowed roots"));
+    }
+}
+
+#[test]
+fn test_allowed_roots_prefix_is_not_a_root() {
+    let mut template = new_template(json!(["test"]));
+    template.set_src_str("<div>{:include; tests/include.txt :}</div>");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(result, "<div></div>");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_allowed_roots_symlink() {
+    let dir = std::env::temp_dir().join(format!("neutralts-roots-{}", std::process::id()));
+    let root = dir.join("root");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(dir.join("secret.txt"), "secret").unwrap();
+    fs::write(root.join("public.txt"), "public").unwrap();
+    let link = root.join("link.txt");
+    let _ = fs::remove_file(&link);
+    std::os::unix::fs::symlink(dir.join("secret.txt"), &link).unwrap();
+
+    let mut template = new_template(json!([root.to_string_lossy()]));
+    template.set_src_str(&format!(
+        "<div>{{:include; {} :}}</div><div>{{:include; {} :}}</div>",
+        root.join("public.txt").to_string_lossy(),
+        link.to_string_lossy()
+    ));
+    let result = template.render();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(template.has_error());
+    assert_eq!(result, "<div>public</div><div></div>");
+    assert!(template.get_errors()[0]
+        .message
+        .contains("path outside allowed roots"));
+}
+
+#[test]
+fn test_allowed_roots_obj() {
+    let mut template = new_template(json!(["src"]));
+    template.set_src_str("<div>{:obj; tests/obj.json :}</div>");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(result, "<div></div>");
+}
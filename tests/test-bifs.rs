@@ -384,3 +384,54 @@ fn test_bif_complete_tpl() {
     assert_eq!(template.get_status_param(), "");
     assert_eq!(result, out);
 }
+
+#[test]
+fn test_bif_limit_render_error() {
+    let schema = r#"
+{
+    "config": {
+        "infinite_loop_max_bifs": 3
+    }
+}
+"#
+    .trim();
+
+    let mut template = neutralts::Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.merge_schema_str(schema).unwrap();
+    template.set_src_str("<div>{:;__test-nts:}</div>{:for; n 1 10 >> {:;n:} :}");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(template.get_status_code(), "500");
+    assert_eq!(template.get_status_text(), "Internal Server Error");
+    assert_eq!(result, "<div>nts</div>1");
+    let errors = template.get_error();
+    assert_eq!(errors.as_array().unwrap().len(), 1);
+    assert!(errors[0].as_str().unwrap().contains("Infinite loop?"));
+}
+
+#[test]
+fn test_bif_limit_config_error() {
+    let schema = r#"
+{
+    "config": {
+        "infinite_loop_max_bifs": null,
+        "debug_expire": "none"
+    }
+}
+"#
+    .trim();
+
+    let mut template = neutralts::Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.merge_schema_str(schema).unwrap();
+    template.set_src_str("<div>{:;__test-nts:}</div>");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(template.get_status_code(), "500");
+    assert_eq!(result, "");
+    assert!(template.get_error()[0]
+        .as_str()
+        .unwrap()
+        .contains("must be a number"));
+}
@@ -0,0 +1,257 @@
+use serde_json::json;
+use std::fs;
+use std::process::{Command, Output};
+
+fn neutralts(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_neutralts"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// The path of a file in the temporary directory.
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("neutralts-cli-{}-{}", std::process::id(), name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// Writes a file in the temporary directory, the test removes it.
+fn temp_file<C: AsRef<[u8]>>(name: &str, content: C) -> String {
+    let path = temp_path(name);
+    fs::write(&path, content).unwrap();
+
+    path
+}
+
+#[test]
+fn test_cli_check_ok() {
+    let output = neutralts(&["check", "tests/snippets.ntpl", "tests/extends-child.ntpl"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_cli_check_errors() {
+    let output = neutralts(&[
+        "check",
+        "tests/snippets.ntpl",
+        "tests/snippets-declare-invalid-flag.ntpl",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "tests/snippets-declare-invalid-flag.ntpl:1:1: (declare) flags not allowed\n"
+    );
+}
+
+#[test]
+fn test_cli_check_usage() {
+    assert_eq!(neutralts(&["check"]).status.code(), Some(2));
+    assert_eq!(neutralts(&["unknown"]).status.code(), Some(2));
+    assert_eq!(neutralts(&["check", "--schema"]).status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_file_errors() {
+    let invalid = temp_file("invalid.json", "{not json");
+
+    // Not found or invalid files are not usage errors.
+    for args in [
+        vec!["check", "--schema", "not-found.json", "tests/snippets.ntpl"],
+        vec!["check", "--schema", &invalid, "tests/snippets.ntpl"],
+        vec!["check", "tests/not-found.ntpl"],
+        vec!["render", "tests/not-found.ntpl"],
+    ] {
+        let output = neutralts(&args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(stderr.contains("neutralts: "), "{:?}", args);
+        assert!(!stderr.contains("Usage:"), "{:?}", args);
+    }
+
+    fs::remove_file(&invalid).unwrap();
+}
+
+#[test]
+fn test_cli_render() {
+    let schema = temp_file("render-schema.json", r#"{"data": {"name": "World"}}"#);
+    let template = temp_file("render.ntpl", "<p>Hello {:;name:}</p>\n");
+
+    let output = neutralts(&["render", "--schema", &schema, &template]);
+
+    fs::remove_file(schema).unwrap();
+    fs::remove_file(template).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "<p>Hello World</p>"
+    );
+}
+
+#[test]
+fn test_cli_render_msgpack() {
+    let data = rmp_serde::to_vec(&json!({"data": {"name": "World"}})).unwrap();
+    let schema = temp_file("render-schema.msgpack", data);
+    let template = temp_file("render-msgpack.ntpl", "<p>Hello {:;name:}</p>");
+
+    let output = neutralts(&["render", "-s", &schema, &template]);
+
+    fs::remove_file(schema).unwrap();
+    fs::remove_file(template).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "<p>Hello World</p>"
+    );
+}
+
+#[test]
+fn test_cli_render_errors() {
+    let template = temp_file(
+        "render-errors.ntpl",
+        "<p>{:include; {:flg; require :} >> not-found.ntpl :}</p>",
+    );
+    let errors = temp_path("render-errors.json");
+
+    let output = neutralts(&["render", "--errors", &errors, &template]);
+    let dump = fs::read_to_string(&errors).unwrap();
+
+    fs::remove_file(template).unwrap();
+    fs::remove_file(errors).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "<p></p>");
+    let dump: serde_json::Value = serde_json::from_str(&dump).unwrap();
+    assert!(dump.as_array().is_some_and(|errors| !errors.is_empty()));
+}
+
+#[test]
+fn test_cli_render_server_error() {
+    let template = temp_file("render-server-error.ntpl", "{:exit; 503 :}");
+
+    let output = neutralts(&["render", "--errors", "-", &template]);
+
+    fs::remove_file(template).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn test_cli_render_usage() {
+    assert_eq!(neutralts(&["render"]).status.code(), Some(2));
+    assert_eq!(
+        neutralts(&["render", "tests/snippets.ntpl", "tests/snippets.ntpl"])
+            .status
+            .code(),
+        Some(2)
+    );
+    assert_eq!(neutralts(&["render", "--errors"]).status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_cache_purge() {
+    let dir = std::env::temp_dir().join(format!("neutral-cache-cli-{}", std::process::id()));
+    let subdir = dir.join("abc");
+    fs::create_dir_all(&subdir).unwrap();
+    fs::write(subdir.join("abc123-0"), "0123456789").unwrap();
+    fs::write(subdir.join("abc456-3600"), "0123456789").unwrap();
+    let dir_arg = dir.to_str().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let output = neutralts(&["cache", "purge", "--dry-run", dir_arg]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("files: 2 (20 bytes)"));
+    assert!(stdout.contains("would be removed: 1 files (10 bytes)"));
+    assert!(subdir.join("abc123-0").exists());
+
+    let output = neutralts(&["cache", "purge", "--max-size", "0K", dir_arg]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("expired: 1\nevicted: 1\n"));
+    assert!(stdout.contains("removed: 2 files (20 bytes), 1 directories"));
+    assert!(!subdir.exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_cache_purge_usage() {
+    assert_eq!(neutralts(&["cache"]).status.code(), Some(2));
+    assert_eq!(neutralts(&["cache", "purge"]).status.code(), Some(2));
+    assert_eq!(
+        neutralts(&["cache", "purge", "--max-size", "1X", "/tmp/neutral-cache"])
+            .status
+            .code(),
+        Some(2)
+    );
+
+    let output = neutralts(&["cache", "purge", "/tmp"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("must contain 'neutral-cache'"));
+}
+
+#[test]
+fn test_cli_cache_invalidate() {
+    let dir = std::env::temp_dir().join(format!("neutral-cache-cli-tags-{}", std::process::id()));
+    let schema = dir.join("schema.json");
+    let template = dir.join("tags.ntpl");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        &schema,
+        json!({ "config": { "cache_dir": dir, "cache_prefix": "" } }).to_string(),
+    )
+    .unwrap();
+    fs::write(&template, "{:cache; {:flg; tags catalog :} /60/ >> list :}").unwrap();
+    let dir_arg = dir.to_str().unwrap();
+
+    let output = neutralts(&[
+        "render",
+        "--schema",
+        schema.to_str().unwrap(),
+        template.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = neutralts(&["cache", "invalidate", dir_arg, "catalog", "other"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "removed: 1 fragments\n"
+    );
+    assert_eq!(
+        neutralts(&["cache", "invalidate", dir_arg]).status.code(),
+        Some(2)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_cache_inspect() {
+    let dir =
+        std::env::temp_dir().join(format!("neutral-cache-cli-inspect-{}", std::process::id()));
+    fs::create_dir_all(dir.join("abc")).unwrap();
+    fs::write(dir.join("abc").join("abc123-3600"), "0123456789").unwrap();
+
+    let output = neutralts(&["cache", "inspect", dir.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].starts_with("KEY"));
+    assert!(lines[1].starts_with("abc123 "));
+    assert!(lines[1].contains(" 3600 "));
+    assert!(!lines[1].contains("expired"));
+    assert_eq!(lines[2], "entries: 1 (10 bytes), expired: 0");
+
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        neutralts(&["cache", "inspect", dir.to_str().unwrap()])
+            .status
+            .code(),
+        Some(1)
+    );
+}
@@ -0,0 +1,102 @@
+use neutralts::test_helpers::*;
+use neutralts::{CompiledTemplate, Template};
+use serde_json::json;
+use std::fs;
+use std::sync::Arc;
+
+#[test]
+fn test_compiled_block_parts() {
+    let compiled = CompiledTemplate::new("<div>{:!snippet; name >> {:;text:} :}</div>");
+    let blocks = compiled.blocks().unwrap();
+
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].span(), 5..37);
+    assert_eq!(blocks[0].full_name(), "!snippet");
+    assert_eq!(blocks[0].name(), "snippet");
+    assert!(blocks[0].modifiers().negate);
+    assert!(!blocks[0].modifiers().scope);
+    assert_eq!(blocks[0].params(), "name");
+    assert_eq!(blocks[0].code(), "{:;text:}");
+    assert!(blocks[0].params_tree().is_none());
+
+    let code = blocks[0].code_tree().unwrap();
+    assert_eq!(code.blocks().unwrap()[0].name(), "");
+    assert_eq!(code.blocks().unwrap()[0].src(), "text");
+}
+
+#[test]
+fn test_compiled_unmatched_block() {
+    let compiled = Arc::new(CompiledTemplate::new("<div>{:code; :} :}</div>"));
+    assert_eq!(compiled.blocks().unwrap_err(), 16);
+
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.set_src_compiled(compiled);
+    let result = template.render();
+    assert_eq!(template.get_status_code(), "500");
+    assert_eq!(result, "500 Internal Server Error");
+}
+
+#[test]
+fn test_compiled_render_many_schemas() {
+    let compiled = Arc::new(CompiledTemplate::new(
+        "<div>{:;name:}{:for; n 1 3 >> {:;n:} :}{:snippet; __hello-nts :}</div>",
+    ));
+
+    for name in ["one", "two", "three"] {
+        let mut template = Template::new().unwrap();
+        template.merge_schema_str(SCHEMA).unwrap();
+        template.merge_schema_value(json!({ "data": { "name": name } }));
+        template.set_src_compiled(compiled.clone());
+        let result = template.render();
+        assert!(!template.has_error());
+        assert_eq!(result, format!("<div>{}123<div>Hello</div></div>", name));
+    }
+}
+
+#[test]
+fn test_compiled_render_twice() {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.set_src_str("<div>{:include; tests/include-snippets.ntpl :}{:;__test-nts:}</div>");
+    let first = template.render();
+    let compiled = template.get_compiled();
+    let second = template.render();
+    assert!(!template.has_error());
+    assert_eq!(first, "<div>Oknts</div>");
+    assert_eq!(second, first);
+    assert!(Arc::ptr_eq(&compiled, &template.get_compiled()));
+}
+
+#[test]
+fn test_compiled_from_file() {
+    let compiled =
+        Arc::new(CompiledTemplate::from_file("tests/include-snippets.ntpl", true).unwrap());
+    assert_eq!(compiled.file_path(), "tests/include-snippets.ntpl");
+    assert_eq!(compiled.source().trim(), "Ok");
+    assert!(CompiledTemplate::from_file("tests/not-found.ntpl", true).is_err());
+}
+
+#[test]
+fn test_compiled_include_modified() {
+    let include_path = "tests/test_compiled_include.ntpl";
+    fs::write(include_path, "{:;__test-nts:}").unwrap();
+
+    let compiled = Arc::new(CompiledTemplate::new(
+        "<div>{:include; tests/test_compiled_include.ntpl :}</div>",
+    ));
+
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.set_src_compiled(compiled.clone());
+    assert_eq!(template.render(), "<div>nts</div>");
+
+    // A modified file is compiled again
+    fs::write(include_path, "{:;__test-nts:}-{:;__test-nts:}").unwrap();
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.set_src_compiled(compiled);
+    assert_eq!(template.render(), "<div>nts-nts</div>");
+
+    fs::remove_file(include_path).unwrap();
+}
@@ -0,0 +1,131 @@
+use neutralts::test_helpers::*;
+use neutralts::{Engine, Template};
+use serde_json::json;
+use std::sync::Arc;
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_engine_is_send_sync() {
+    assert_send_sync::<Engine>();
+    assert_send_sync::<Template>();
+}
+
+#[test]
+fn test_engine_context() {
+    let mut engine = Engine::new().unwrap();
+    engine.merge_schema_str(SCHEMA).unwrap();
+
+    let mut template = engine.context("tests/include-snippets.ntpl").unwrap();
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "Ok");
+
+    // The base schema is not modified by the render context
+    let mut template = engine.context_str("{:;__test-nts:}-{:;name:}").unwrap();
+    template.merge_schema_value(json!({ "data": { "name": "context" } }));
+    assert_eq!(template.render(), "nts-context");
+    assert_eq!(template.render(), "nts-context");
+    assert!(engine.get_schema()["data"]["name"].is_null());
+}
+
+#[test]
+fn test_engine_context_layered() {
+    let mut engine = Engine::new().unwrap();
+    engine.merge_schema_value(json!({
+        "data": {
+            "user": { "name": "base", "role": "admin" },
+            "list": ["a", "b", "c"],
+            "item": "base-item"
+        }
+    }));
+    let source = "{:;user->name:}-{:;user->role:}|{:each; user k v >>{:;k:}={:;v:},:}|{:each; list k item >>{:;item:}:}|{:;item:}|{:defined; list->2 >>2:}";
+
+    // The keys of the context are merged over those of the base
+    let mut template = engine.context_str(source).unwrap();
+    template.merge_schema_value(json!({
+        "data": {
+            "user": { "name": "context" },
+            "list": ["x"]
+        }
+    }));
+    assert_eq!(
+        template.render(),
+        "context-admin|name=context,role=admin,|x|base-item|"
+    );
+    assert!(!template.has_error());
+
+    let mut template = engine.context_str(source).unwrap();
+    assert_eq!(
+        template.render(),
+        "base-admin|name=base,role=admin,|abc|base-item|2"
+    );
+    assert_eq!(engine.get_schema()["data"]["user"]["name"], "base");
+}
+
+#[test]
+fn test_engine_context_not_found() {
+    let engine = Engine::new().unwrap();
+    assert!(engine.context("tests/not-found.ntpl").is_err());
+}
+
+#[test]
+fn test_engine_compile_once() {
+    let engine = Engine::new().unwrap();
+    let first = engine.compile("tests/include-snippets.ntpl").unwrap();
+    let second = engine.compile("tests/include-snippets.ntpl").unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+
+    // The same file with another path.
+    let third = engine
+        .compile("tests/../tests/include-snippets.ntpl")
+        .unwrap();
+    assert!(Arc::ptr_eq(&first, &third));
+}
+
+#[test]
+fn test_engine_compile_modified() {
+    let path = std::env::temp_dir().join(format!("neutralts-engine-{}.ntpl", std::process::id()));
+    let path = path.to_str().unwrap();
+    let engine = Engine::new().unwrap();
+
+    std::fs::write(path, "one").unwrap();
+    let first = engine.compile(path).unwrap();
+    assert_eq!(engine.context(path).unwrap().render(), "one");
+
+    std::fs::write(path, "two 2").unwrap();
+    let second = engine.compile(path).unwrap();
+    let out = engine.context(path).unwrap().render();
+
+    std::fs::remove_file(path).unwrap();
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert_eq!(out, "two 2");
+}
+
+#[test]
+fn test_engine_threads() {
+    let engine = Arc::new(Engine::from_value(serde_json::from_str(SCHEMA).unwrap()).unwrap());
+    let compiled = Arc::new(neutralts::CompiledTemplate::new(
+        "<div>{:;n:}{:snippet; __hello-nts :}{:include; tests/include-snippets.ntpl :}</div>",
+    ));
+
+    thread::scope(|scope| {
+        for n in 0..8 {
+            let engine = Arc::clone(&engine);
+            let compiled = Arc::clone(&compiled);
+            scope.spawn(move || {
+                for i in 0..20 {
+                    let mut template = engine.context_compiled(compiled.clone()).unwrap();
+                    template.merge_schema_value(json!({ "data": { "n": n * 100 + i } }));
+                    let result = template.render_once();
+                    assert!(!template.has_error());
+                    assert_eq!(
+                        result,
+                        format!("<div>{}<div>Hello</div>Ok</div>", n * 100 + i)
+                    );
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,356 @@
+#![cfg(feature = "ipc")]
+
+use neutralts::ipc::{self, Client, Format, Server, ServerOptions};
+use neutralts::RenderErrorKind;
+use serde_json::json;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::thread;
+
+// The root of the servers, the templates are written by each test.
+fn root_dir() -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join("neutralts-ipc-tests")
+        .join("root");
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+fn tests_root() -> ServerOptions {
+    ServerOptions::default().with_root(root_dir()).unwrap()
+}
+
+fn start_server_with(options: ServerOptions) -> SocketAddr {
+    let mut server = Server::bind_tcp("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    server.set_options(options);
+    thread::spawn(move || server.serve());
+
+    addr
+}
+
+fn start_tcp_server() -> SocketAddr {
+    start_server_with(tests_root())
+}
+
+// Closed with or without a reset, there is no reply.
+fn assert_closed(stream: &mut TcpStream) {
+    let mut buf = [0u8; 1];
+    assert!(!matches!(stream.read(&mut buf), Ok(n) if n > 0));
+}
+
+fn write_template(name: &str, source: &str) -> String {
+    let path = root_dir().join(name);
+    fs::write(&path, source).unwrap();
+
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_ipc_render_json() {
+    let template = write_template("ipc-json.ntpl", "<p>Hello {:;name:}</p>");
+    let addr = start_tcp_server();
+
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let response = client
+        .render(&template, &json!({ "data": { "name": "World" } }))
+        .unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(response.body, "<p>Hello World</p>");
+    assert_eq!(response.status_code, "200");
+    assert_eq!(response.status_text, "OK");
+    assert!(!response.has_error());
+}
+
+#[test]
+fn test_ipc_render_msgpack() {
+    let template = write_template(
+        "ipc-msgpack.ntpl",
+        "{:header; X-Name >> {:;name:} :}{:redirect; 302 >> /login :}",
+    );
+    let addr = start_tcp_server();
+
+    let mut client = Client::connect_tcp(addr).unwrap();
+    client.set_format(Format::MsgPack);
+    let response = client
+        .render(&template, &json!({ "data": { "name": "World" } }))
+        .unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(response.status_code, "302");
+    assert_eq!(response.status_param, "/login");
+    assert_eq!(
+        response.headers,
+        vec![("X-Name".to_string(), "World".to_string())]
+    );
+}
+
+#[test]
+fn test_ipc_render_errors() {
+    let template = write_template(
+        "ipc-errors.ntpl",
+        "<p>{:include; {:flg; require :} >> not-found.ntpl :}</p>",
+    );
+    let addr = start_tcp_server();
+
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let response = client.render(&template, &json!({})).unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(response.body, "<p></p>");
+    assert!(response.has_error());
+    assert_eq!(response.errors[0].kind, RenderErrorKind::Bif);
+    assert_eq!(response.errors[0].bif_name, "include");
+}
+
+#[test]
+fn test_ipc_same_connection() {
+    let template = write_template("ipc-same-connection.ntpl", "{:;n:}");
+    let addr = start_tcp_server();
+
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let mut bodies = Vec::new();
+    for n in 0..3 {
+        if n == 1 {
+            client.set_format(Format::MsgPack);
+        }
+        let response = client.render(&template, &json!({ "data": { "n": n } }));
+        bodies.push(response.unwrap().body);
+    }
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(bodies, vec!["0", "1", "2"]);
+}
+
+#[test]
+fn test_ipc_template_not_found() {
+    let addr = start_tcp_server();
+
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let error = client.render("not-found.ntpl", &json!({})).unwrap_err();
+    assert!(error.to_string().contains("not-found.ntpl"));
+
+    // The connection is still usable.
+    let template = write_template("ipc-not-found.ntpl", "ok");
+    let response = client.render(&template, &json!({})).unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(response.body, "ok");
+}
+
+#[test]
+fn test_ipc_raw_frames() {
+    let template = write_template("ipc-raw-frames.ntpl", "{:;name:}");
+    let addr = start_tcp_server();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let request = format!(
+        r#"{{"template": "{}", "schema": {{"data": {{"name": "raw"}}}}}}"#,
+        template
+    );
+    ipc::write_frame(&mut stream, Format::Json, request.as_bytes()).unwrap();
+    let (format, payload) = ipc::read_frame(&mut stream).unwrap().unwrap();
+    let reply: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(format, Format::Json);
+    assert_eq!(reply["ok"]["body"], "raw");
+    assert_eq!(reply["ok"]["status_code"], "200");
+
+    ipc::write_frame(&mut stream, Format::Json, b"not json").unwrap();
+    let (_, payload) = ipc::read_frame(&mut stream).unwrap().unwrap();
+    let reply: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+    assert!(reply["error"].is_string());
+}
+
+#[test]
+fn test_ipc_invalid_frame() {
+    let addr = start_tcp_server();
+
+    // Unknown format, the server closes the connection.
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"X\0\0\0\x02{}").unwrap();
+    assert_closed(&mut stream);
+
+    // Too large, the payload is not allocated.
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"J\xff\xff\xff\xff").unwrap();
+    assert_closed(&mut stream);
+}
+
+#[test]
+fn test_ipc_incomplete_frame() {
+    let mut frame: &[u8] = b"J\0\0\0\x10{}";
+    let error = ipc::read_frame(&mut frame).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_ipc_root() {
+    let template = write_template("ipc-root.ntpl", "<p>{:;name:}</p>");
+    let addr = start_tcp_server();
+
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let schema = json!({ "data": { "name": "root" } });
+
+    // Relative to the root.
+    let response = client.render("ipc-root.ntpl", &schema).unwrap();
+    assert_eq!(response.body, "<p>root</p>");
+
+    // Outside the root.
+    let outside = root_dir().join("../ipc-root-outside.ntpl");
+    fs::write(&outside, "outside").unwrap();
+    let error = client
+        .render("../ipc-root-outside.ntpl", &schema)
+        .unwrap_err();
+    assert!(error.to_string().contains("path not allowed"));
+    let error = client
+        .render(outside.to_str().unwrap(), &schema)
+        .unwrap_err();
+    assert!(error.to_string().contains("path not allowed"));
+
+    fs::remove_file(&outside).unwrap();
+    fs::remove_file(&template).unwrap();
+}
+
+#[test]
+fn test_ipc_client_config() {
+    let template = write_template(
+        "ipc-client-config.ntpl",
+        concat!(
+            "{:include; {:flg; require :} >> ",
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/snippets.ntpl :}"
+        ),
+    );
+    let addr = start_tcp_server();
+
+    // The client cannot allow other roots, the include is outside the root.
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let schema = json!({
+        "config": {
+            "allowed_roots": ["/"],
+            "comments": "keep"
+        }
+    });
+    let response = client.render("ipc-client-config.ntpl", &schema).unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert!(response.has_error());
+    assert!(response.errors[0].message.contains("outside allowed roots"));
+}
+
+#[test]
+fn test_ipc_client_inherit() {
+    let template = write_template(
+        "ipc-client-inherit.ntpl",
+        "{:!allow; traversal >> {:;path:} :}|{:allow; any >> {:;path:} :}|{:trans; Hello nts :}",
+    );
+    let addr = start_tcp_server();
+
+    // The client cannot change the declared lists nor the translations,
+    // only the current language.
+    let mut client = Client::connect_tcp(addr).unwrap();
+    let schema = json!({
+        "data": { "path": "../secret" },
+        "inherit": {
+            "declare": { "traversal": "none", "any": "none" },
+            "locale": {
+                "current": "es",
+                "trans": { "es": { "Hello nts": "Injected" } }
+            }
+        }
+    });
+    let response = client.render("ipc-client-inherit.ntpl", &schema).unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert!(!response.has_error());
+    assert_eq!(response.body, "|../secret|Hola");
+}
+
+#[test]
+fn test_ipc_max_connections() {
+    let template = write_template("ipc-max-connections.ntpl", "ok");
+    let mut options = tests_root();
+    options.max_connections = 1;
+    let addr = start_server_with(options);
+
+    let mut first = Client::connect_tcp(addr).unwrap();
+    assert_eq!(first.render(&template, &json!({})).unwrap().body, "ok");
+
+    // Answered when the first connection is closed.
+    let second_template = template.clone();
+    let second = thread::spawn(move || {
+        let mut client = Client::connect_tcp(addr).unwrap();
+        client.render(&second_template, &json!({})).unwrap().body
+    });
+    thread::sleep(std::time::Duration::from_millis(100));
+    assert!(!second.is_finished());
+
+    drop(first);
+    let body = second.join().unwrap();
+
+    fs::remove_file(&template).unwrap();
+    assert_eq!(body, "ok");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ipc_unix_socket() {
+    let template = write_template("ipc-unix-socket.ntpl", "<p>{:;name:}</p>");
+    let socket = std::env::temp_dir().join(format!("neutralts-ipc-{}.sock", std::process::id()));
+    let _ = fs::remove_file(&socket);
+
+    let server = Server::bind_unix(&socket).unwrap();
+    assert!(server.local_addr().is_err());
+    thread::spawn(move || server.serve());
+
+    let mut client = Client::connect_unix(&socket).unwrap();
+    let response = client
+        .render(&template, &json!({ "data": { "name": "unix" } }))
+        .unwrap();
+
+    fs::remove_file(&template).unwrap();
+    fs::remove_file(&socket).unwrap();
+    assert_eq!(response.body, "<p>unix</p>");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ipc_cli_serve() {
+    let template = write_template("ipc-cli-serve.ntpl", "<p>{:;name:}</p>");
+    let socket = std::env::temp_dir().join(format!("neutralts-cli-{}.sock", std::process::id()));
+    let _ = fs::remove_file(&socket);
+
+    let mut server = std::process::Command::new(env!("CARGO_BIN_EXE_neutralts"))
+        .arg("serve")
+        .arg("--root")
+        .arg(root_dir())
+        .arg(format!("unix:{}", socket.display()))
+        .spawn()
+        .unwrap();
+
+    let mut client = None;
+    for _ in 0..100 {
+        match Client::connect_unix(&socket) {
+            Ok(c) => {
+                client = Some(c);
+                break;
+            }
+            Err(_) => thread::sleep(std::time::Duration::from_millis(20)),
+        }
+    }
+    let response = client
+        .expect("server not started")
+        .render(&template, &json!({ "data": { "name": "cli" } }));
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+    fs::remove_file(&template).unwrap();
+    fs::remove_file(&socket).unwrap();
+    assert_eq!(response.unwrap().body, "<p>cli</p>");
+}
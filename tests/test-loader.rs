@@ -0,0 +1,207 @@
+use neutralts::test_helpers::*;
+use neutralts::{EmbeddedLoader, Engine, MemoryLoader, Template, TemplateLoader};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn memory_loader() -> MemoryLoader {
+    let mut loader = MemoryLoader::new();
+    loader.insert(
+        "tpl/index.ntpl",
+        "<div>{:include; #/parts/header.ntpl :}</div>",
+    );
+    loader.insert(
+        "tpl/parts/header.ntpl",
+        "{:* header *:}<h1>{:;local::title:}</h1>{:include; #/../footer.ntpl :}",
+    );
+    loader.insert("tpl/footer.ntpl", "<p>{:trans; test-locale :}</p>");
+    loader.insert(
+        "tpl/locale.es.json",
+        r#"{ "trans": { "es": { "test-locale": "Ok" } } }"#,
+    );
+    loader.insert("tpl/data.json", r#"{ "data": { "title": "Title" } }"#);
+    loader
+}
+
+fn new_template() -> Template {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.merge_schema_value(json!({ "inherit": { "locale": { "current": "es" } } }));
+    template
+}
+
+#[test]
+fn test_loader_memory() {
+    let mut template = new_template();
+    template.set_loader(memory_loader());
+    template.set_src_str(
+        "{:locale; tpl/locale.es.json :}{:data; tpl/data.json :}{:include; tpl/index.ntpl :}",
+    );
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div><h1>Title</h1><p>Ok</p></div>");
+}
+
+#[test]
+fn test_loader_memory_src_path() {
+    let mut template = new_template();
+    template.set_loader(memory_loader());
+    template.set_src_path("tpl/./parts/header.ntpl").unwrap();
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<h1></h1><p>test-locale</p>");
+    assert!(template.set_src_path("tpl/not-found.ntpl").is_err());
+}
+
+#[test]
+fn test_loader_memory_not_found() {
+    let mut template = new_template();
+    template.set_loader(memory_loader());
+    template.set_src_str("<div>{:include; tests/include-snippets.ntpl :}</div>");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div></div>");
+
+    template.set_src_str("<div>{:include; {:flg; require :} >> tpl/none.ntpl :}</div>");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(result, "<div></div>");
+}
+
+#[test]
+fn test_loader_memory_canonical() {
+    let mut template = new_template();
+    template.set_loader(memory_loader());
+    template.set_src_str(
+        "{:include; tpl/footer.ntpl :}{:!include; tpl/parts/../footer.ntpl :}{:include; tpl//footer.ntpl :}",
+    );
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<p>test-locale</p><p>test-locale</p>");
+}
+
+#[test]
+fn test_loader_memory_modified() {
+    let mut loader = memory_loader();
+    let mut template = new_template();
+    template.set_loader(loader.clone());
+    template.set_src_str("{:include; tpl/footer.ntpl :}");
+    assert_eq!(template.render(), "<p>test-locale</p>");
+
+    // The insert gives the file a new version, it is compiled again.
+    loader.insert("tpl/footer.ntpl", "<p>{:;__test-nts:}</p>");
+    template.set_loader(loader);
+    assert_eq!(template.render(), "<p>nts</p>");
+}
+
+// A loader that counts the reads
+struct CountLoader<L: TemplateLoader>(L, Arc<AtomicUsize>);
+
+impl<L: TemplateLoader> TemplateLoader for CountLoader<L> {
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        self.0.canonicalize(path)
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        self.1.fetch_add(1, Ordering::Relaxed);
+        self.0.read(path)
+    }
+
+    fn version(&self, path: &str) -> Option<u64> {
+        self.0.version(path)
+    }
+}
+
+// The reads to compile the same file twice.
+fn compile_reads<L: TemplateLoader + 'static>(loader: L) -> usize {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let mut engine = Engine::new().unwrap();
+    engine.set_loader(CountLoader(loader, Arc::clone(&reads)));
+    let first = engine.compile("tpl/version.ntpl").unwrap();
+    let second = engine.compile("tpl/version.ntpl").unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+
+    reads.load(Ordering::Relaxed)
+}
+
+#[test]
+fn test_loader_version() {
+    static FILES: &[(&str, &str)] = &[("tpl/version.ntpl", "embedded")];
+
+    let mut loader = MemoryLoader::new();
+    loader.insert("tpl/version.ntpl", "memory");
+    let version = loader.version("tpl/version.ntpl");
+    assert!(version.is_some());
+    assert_eq!(loader.version("tpl/version.ntpl"), version);
+    loader.insert("tpl/version.ntpl", "memory");
+    assert_ne!(loader.version("tpl/version.ntpl"), version);
+
+    // With a version the compiled file is reused without reading it again.
+    assert_eq!(compile_reads(loader), 1);
+    assert_eq!(compile_reads(EmbeddedLoader::new(FILES)), 1);
+}
+
+#[test]
+fn test_loader_embedded() {
+    static FILES: &[(&str, &str)] = &[
+        ("tpl/index.ntpl", "<div>{:include; #/header.ntpl :}</div>"),
+        ("tpl/header.ntpl", "{:;__test-nts:}"),
+    ];
+
+    let loader = EmbeddedLoader::new(FILES);
+    assert_eq!(
+        loader.canonicalize("./tpl/header.ntpl"),
+        Some("tpl/header.ntpl".to_string())
+    );
+    assert_eq!(loader.canonicalize("tpl/none.ntpl"), None);
+
+    let mut template = new_template();
+    template.set_loader(loader);
+    template.set_src_path("tpl/index.ntpl").unwrap();
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div>nts</div>");
+}
+
+#[test]
+fn test_loader_engine() {
+    let mut engine = Engine::new().unwrap();
+    engine.merge_schema_str(SCHEMA).unwrap();
+    engine.set_loader(memory_loader());
+    let mut template = engine.context("tpl/index.ntpl").unwrap();
+    template.merge_schema_value(json!({ "data": { "title": "Engine" } }));
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div><h1></h1><p>test-locale</p></div>");
+    assert!(engine.context("tpl/none.ntpl").is_err());
+}
+
+// A loader with its own prefix instead of "#"
+struct PrefixLoader(MemoryLoader);
+
+impl TemplateLoader for PrefixLoader {
+    fn resolve(&self, _current_dir: &str, path: &str) -> String {
+        match path.strip_prefix("@") {
+            Some(stripped) => format!("tpl/{}", stripped),
+            None => path.to_string(),
+        }
+    }
+
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        self.0.canonicalize(path)
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        self.0.read(path)
+    }
+}
+
+#[test]
+fn test_loader_custom() {
+    let mut template = new_template();
+    template.set_loader(PrefixLoader(memory_loader()));
+    template.set_src_str("{:include; @footer.ntpl :}");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<p>test-locale</p>");
+}
@@ -0,0 +1,87 @@
+use neutralts::test_helpers::*;
+use neutralts::{RenderErrorKind, Template};
+
+fn render(source: &str) -> Template {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.set_src_str(source);
+    template.render();
+    template
+}
+
+#[test]
+fn test_render_error_position() {
+    let template = render("<div>\n  {:* comment\n  *:}<p>{:include; {:flg; require :} >> not-found.ntpl :}</p>\n</div>");
+    let errors = template.get_errors();
+    assert!(template.has_error());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, RenderErrorKind::Bif);
+    assert_eq!(errors[0].bif_name, "include");
+    assert_eq!(errors[0].file, "");
+    assert_eq!((errors[0].line, errors[0].column), (3, 9));
+    assert_eq!(
+        errors[0].snippet,
+        "{:include; {:flg; require :} >> not-found.ntpl :}"
+    );
+    assert_eq!(template.get_error().as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_render_error_nested() {
+    let template = render("{:code;\n  {:code; {:;__test-nts:}\n    {:unknownbif; x :} :}\n:}");
+    let errors = template.get_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].bif_name, "unknownbif");
+    assert_eq!((errors[0].line, errors[0].column), (3, 5));
+}
+
+#[test]
+fn test_render_error_include_and_snippet() {
+    let source = "{:include; tests/render-error-snippets.ntpl :}\n<div>\n    {:include; tests/render-error-include.ntpl :}\n    {:snippet; render-error-snippet :}\n</div>";
+    let template = render(source);
+    let errors = template.get_errors();
+    assert_eq!(errors.len(), 2);
+
+    // In the included file
+    assert!(errors[0].file.ends_with("tests/render-error-include.ntpl"));
+    assert_eq!((errors[0].line, errors[0].column), (4, 4));
+
+    // Where the snippet is set
+    assert!(errors[1].file.ends_with("tests/render-error-snippets.ntpl"));
+    assert_eq!((errors[1].line, errors[1].column), (5, 8));
+}
+
+#[test]
+fn test_render_error_for_iterations() {
+    let template = render("<div>\n{:for; n 1 2 >> {:;n:}{:unknownbif; x :} :}</div>");
+    let errors = template.get_errors();
+    assert_eq!(errors.len(), 2);
+    assert_eq!((errors[0].line, errors[0].column), (2, 23));
+    assert_eq!(errors[1], errors[0]);
+}
+
+#[test]
+fn test_render_error_delimiter() {
+    let template = render("<div>\n{:name:}</div>");
+    let errors = template.get_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, RenderErrorKind::Delimiter);
+    assert_eq!((errors[0].line, errors[0].column), (2, 1));
+}
+
+#[test]
+fn test_render_error_unmatched_block() {
+    let template = render("<div>\n{:code; :} :}</div>");
+    let errors = template.get_errors();
+    assert_eq!(template.get_status_code(), "500");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, RenderErrorKind::UnmatchedBlock);
+    assert_eq!((errors[0].line, errors[0].column), (2, 12));
+}
+
+#[test]
+fn test_render_error_display() {
+    let template = render("<div>{:include; {:flg; require :} >> not-found.ntpl :}</div>");
+    let error = template.get_errors()[0].to_string();
+    assert!(error.starts_with("source:1:6: (include) "));
+}
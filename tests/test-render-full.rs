@@ -0,0 +1,130 @@
+use neutralts::test_helpers::*;
+use neutralts::{RenderErrorKind, Template};
+
+fn new_template(src: &str) -> Template {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.set_src_str(src);
+    template
+}
+
+#[test]
+fn test_render_full_ok() {
+    let mut template = new_template("  <div>{:;__test-nts:}</div>  ");
+    let output = template.render_full();
+    assert_eq!(output.body, "<div>nts</div>");
+    assert_eq!(output.status, "200");
+    assert_eq!(output.status_text, "OK");
+    assert_eq!(output.location, None);
+    assert!(output.headers.is_empty());
+    assert!(!output.has_error());
+    assert_eq!(output.stats.bifs, 1);
+
+    let mut template = new_template("  <div>{:;__test-nts:}</div>  ");
+    assert_eq!(template.render(), output.body);
+}
+
+#[test]
+fn test_render_full_no_bifs() {
+    let mut template = new_template("  <div>nts</div>  ");
+    let output = template.render_full();
+    assert_eq!(output.body, "<div>nts</div>");
+    assert_eq!(output.status, "200");
+    assert_eq!(output.stats.bifs, 0);
+}
+
+// The render of a source without bifs does not keep the previous render.
+#[test]
+fn test_render_full_twice() {
+    let mut template = new_template("<div>{:include; {:flg; require :} >> not-found.ntpl :}</div>");
+    assert!(template.render_full().has_error());
+
+    template.set_src_str("plain");
+    let output = template.render_full();
+    assert_eq!(output.body, "plain");
+    assert!(!output.has_error());
+    assert!(!template.has_error());
+
+    template.set_src_str("{:redirect; 301 >> /page :}");
+    assert_eq!(template.render_full().status, "301");
+
+    template.set_src_str("plain");
+    let output = template.render_full();
+    assert_eq!(output.status, "200");
+    assert_eq!(output.status_text, "OK");
+    assert_eq!(output.location, None);
+    assert!(output.headers.is_empty());
+    assert_eq!(template.get_status_code(), "200");
+}
+
+#[test]
+fn test_render_full_exit() {
+    let mut template = new_template("<div>{:;__test-nts:}</div>{:exit; 404 :}");
+    let output = template.render_full();
+    assert_eq!(output.body, "<div>nts</div>");
+    assert_eq!(output.status, "404");
+    assert_eq!(output.status_text, "Not Found");
+    assert_eq!(output.location, None);
+    assert!(output.headers.is_empty());
+
+    // render() replaces the body
+    let mut template = new_template("<div>{:;__test-nts:}</div>{:exit; 404 :}");
+    assert_eq!(template.render(), "404 Not Found");
+}
+
+#[test]
+fn test_render_full_redirect() {
+    let mut template = new_template("<div>{:;__test-nts:}</div>{:redirect; 301 >> /page :}");
+    let output = template.render_full();
+    assert_eq!(output.body, "<div>nts</div>");
+    assert_eq!(output.status, "301");
+    assert_eq!(output.status_text, "Moved Permanently");
+    assert_eq!(output.location.as_deref(), Some("/page"));
+    assert_eq!(
+        output.headers,
+        vec![("Location".to_string(), "/page".to_string())]
+    );
+}
+
+#[test]
+fn test_render_full_redirect_js() {
+    let mut template = new_template("{:redirect; js:reload:top :}<div>{:;__test-nts:}</div>");
+    let output = template.render_full();
+    assert_eq!(output.status, "200");
+    assert_eq!(output.location, None);
+    assert!(output.body.contains("<script"));
+
+    let mut template = new_template("{:redirect; js:reload:top :}<div>{:;__test-nts:}</div>");
+    assert_eq!(template.render(), output.body);
+}
+
+#[test]
+fn test_render_full_errors() {
+    let mut template = new_template("<div>{:include; {:flg; require :} >> not-found.ntpl :}</div>");
+    let output = template.render_full();
+    assert_eq!(output.body, "<div></div>");
+    assert_eq!(output.status, "200");
+    assert!(output.has_error());
+    assert_eq!(output.errors.len(), 1);
+    assert_eq!(output.errors[0].kind, RenderErrorKind::Bif);
+    assert_eq!(output.errors[0].bif_name, "include");
+    assert_eq!(output.errors, template.get_errors());
+}
+
+#[test]
+fn test_render_full_unmatched_block() {
+    let mut template = new_template("<div>{:code; :} :}</div>");
+    let output = template.render_full();
+    assert_eq!(output.status, "500");
+    assert_eq!(output.status_text, "Internal Server Error");
+    assert_eq!(output.errors[0].kind, RenderErrorKind::UnmatchedBlock);
+}
+
+#[test]
+fn test_render_full_serialize() {
+    let mut template = new_template("{:redirect; 302 >> /login :}");
+    let output = serde_json::to_value(template.render_full()).unwrap();
+    assert_eq!(output["status"], "302");
+    assert_eq!(output["location"], "/login");
+    assert_eq!(output["headers"][0][0], "Location");
+}
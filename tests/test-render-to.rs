@@ -0,0 +1,176 @@
+use neutralts::test_helpers::*;
+use neutralts::{BifContext, Template};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+fn new_template(schema: &str, src: &str) -> Template {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(schema).unwrap();
+    template.set_src_str(src);
+    template
+}
+
+fn render_to(template: &mut Template) -> String {
+    let mut out = Vec::new();
+    template.render_to(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+// render_to writes the same output as render
+fn assert_same_render(schema: &str, src: &str) {
+    let mut template = new_template(schema, src);
+    let expected = template.render();
+    let expected_status = template.get_status_code().clone();
+
+    let mut template = new_template(schema, src);
+    let result = render_to(&mut template);
+    assert_eq!(result, expected, "src: {}", src);
+    assert_eq!(template.get_status_code(), &expected_status, "src: {}", src);
+}
+
+#[test]
+fn test_render_to_same_as_render() {
+    let sources = [
+        "",
+        "  plain text  ",
+        "  <div>{:;__test-nts:}</div>  \n  {:code; x :}  <p> {:;:} </p> \n ",
+        "<div>\n    {:^;__test-nts:}</div>\n{:^;__test-nts:}",
+        "<div>{:moveto; </body >> <script></script> :}</div><body></body>",
+        "{:exit; 404 :}<div>{:;__test-nts:}</div>",
+        "{:redirect; 301 >> /page :}<div>{:;__test-nts:}</div>",
+        "<div>{:;__test-nts</div>",
+        "{:include; tests/snippets.ntpl :}<div>{:snippet; test-snippet :}</div>",
+        "<html>{:include; tests/extends-child.ntpl :}</html>",
+        "text {:extends; tests/extends-layout.ntpl :}{:block; title >> Title :}",
+    ];
+
+    for src in sources {
+        assert_same_render(SCHEMA, src);
+    }
+}
+
+#[test]
+fn test_render_to_cache() {
+    let src = "<div>{:cache; /60/ >> <p>{:;__test-nts:}</p>{:!cache; {:;__test-nts:} :} :}</div>";
+    assert_same_render(SCHEMA_CACHE, src);
+    assert_same_render(SCHEMA_CACHE, src);
+}
+
+#[derive(Clone, Default)]
+struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Renders with the bif {:written;:}, whose output is what has been written.
+fn render_written(src: &str) -> (String, Template) {
+    let mut writer = SharedWriter::default();
+    let written = writer.clone();
+    let mut template = new_template(SCHEMA, src);
+    template
+        .register_bif("written", move |_ctx: &mut BifContext| {
+            let out = written.0.lock().unwrap().clone();
+            Ok(format!("[{}]", String::from_utf8(out).unwrap()))
+        })
+        .unwrap();
+
+    template.render_to(&mut writer).unwrap();
+    let result = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+    (result, template)
+}
+
+#[test]
+fn test_render_to_streaming() {
+    let (result, template) = render_written("<p>{:;__test-nts:}</p>\n{:written;:}\n<p>three</p>");
+    assert!(!template.has_error());
+    assert_eq!(result, "<p>nts</p>\n[<p>nts]\n<p>three</p>");
+}
+
+#[test]
+fn test_render_to_exit_after_output() {
+    let src = "<p>{:;__test-nts:}</p>{:exit; 404 :}<p>two</p>";
+    assert_same_render(SCHEMA, src);
+
+    let mut template = new_template(SCHEMA, src);
+    let result = render_to(&mut template);
+    assert_eq!(template.get_status_code(), "404");
+    assert_eq!(result, "404 Not Found");
+}
+
+#[test]
+fn test_render_to_moveto_written() {
+    let src = "<head></head>{:;__test-nts:}<body>{:moveto; </head >> <style></style> :}{:moveto; </body >> <script></script> :}</body>";
+    assert_same_render(SCHEMA, src);
+
+    let mut template = new_template(SCHEMA, src);
+    let result = render_to(&mut template);
+    assert!(!template.has_error());
+    assert_eq!(
+        result,
+        "<head><style></style></head>nts<body><script></script></body>"
+    );
+}
+
+// A moveto in a snippet only reaches the tags not yet written.
+#[test]
+fn test_render_to_moveto_in_snippet() {
+    let schema = r#"{
+        "inherit": {
+            "snippets": {
+                "script": "{:moveto; </body >> <script></script> :}"
+            }
+        }
+    }"#;
+    let src = "<div>{:;__test-nts:}</div>{:snippet; script :}<body></body>";
+    assert_same_render(schema, src);
+
+    let mut template = new_template(schema, src);
+    assert_eq!(
+        render_to(&mut template),
+        "<div>nts</div><body><script></script></body>"
+    );
+}
+
+// The output before the tag of a moveto is written before the moveto is parsed.
+#[test]
+fn test_render_to_streaming_moveto() {
+    let src = "<p>one</p>{:;:}{:written;:}<head></head>{:moveto; </head >> <style></style> :}";
+    let (result, template) = render_written(src);
+    assert!(!template.has_error());
+    assert_eq!(result, "<p>one</p>[<p>one</p>]<head><style></style></head>");
+}
+
+// An include does not stop the output from being written.
+#[test]
+fn test_render_to_streaming_include() {
+    let src = "{:include; tests/snippets.ntpl :}<p>one</p>{:;:}{:written;:}";
+    let (result, template) = render_written(src);
+    assert!(!template.has_error());
+    assert_eq!(result, "<p>one</p>[<p>one</p>]");
+}
+
+struct FailWriter;
+
+impl Write for FailWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_render_to_writer_error() {
+    let mut template = new_template(SCHEMA, "<div>{:;__test-nts:}</div>");
+    let error = template.render_to(&mut FailWriter).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+}
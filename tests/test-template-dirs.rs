@@ -0,0 +1,93 @@
+use neutralts::test_helpers::*;
+use neutralts::{MemoryLoader, Template};
+use serde_json::json;
+
+fn memory_loader() -> MemoryLoader {
+    let mut loader = MemoryLoader::new();
+    loader.insert(
+        "base/index.ntpl",
+        "{:include; header.ntpl :}{:include; footer.ntpl :}",
+    );
+    loader.insert("base/header.ntpl", "<h1>base</h1>");
+    loader.insert("base/footer.ntpl", "<p>base {:include; #/copy.ntpl :}</p>");
+    loader.insert("base/copy.ntpl", "(c) base");
+    loader.insert("theme/header.ntpl", "<h1>theme</h1>");
+    loader.insert("theme/copy.ntpl", "(c) theme");
+    loader.insert(
+        "theme/locale.es.json",
+        r#"{ "trans": { "es": { "test-locale": "theme" } } }"#,
+    );
+    loader.insert(
+        "base/locale.es.json",
+        r#"{ "trans": { "es": { "test-locale": "base" } } }"#,
+    );
+    loader.insert("base/data.json", r#"{ "data": { "title": "base" } }"#);
+    loader
+}
+
+fn new_template(template_dirs: serde_json::Value) -> Template {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.merge_schema_value(json!({
+        "config": { "template_dirs": template_dirs },
+        "inherit": { "locale": { "current": "es" } }
+    }));
+    template.set_loader(memory_loader());
+    template
+}
+
+#[test]
+fn test_template_dirs_order() {
+    let mut template = new_template(json!(["theme", "base"]));
+    template.set_src_str("{:include; index.ntpl :}");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<h1>theme</h1><p>base (c) base</p>");
+
+    let mut template = new_template(json!(["base/", "theme/"]));
+    template.set_src_str("{:include; index.ntpl :}");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<h1>base</h1><p>base (c) base</p>");
+}
+
+#[test]
+fn test_template_dirs_locale_data() {
+    let mut template = new_template(json!(["theme", "base"]));
+    template.set_src_str(
+        "{:locale; locale.es.json :}{:data; data.json :}{:trans; test-locale :} {:;local::title:}",
+    );
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "theme base");
+}
+
+#[test]
+fn test_template_dirs_not_searched() {
+    let mut template = new_template(json!(["theme", "base"]));
+    template.set_src_str("<div>{:include; /header.ntpl :}{:include; base/header.ntpl :}</div>");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div><h1>base</h1></div>");
+}
+
+#[test]
+fn test_template_dirs_working_dir() {
+    let mut template = Template::new().unwrap();
+    template.merge_schema_str(SCHEMA).unwrap();
+    template.merge_schema_value(json!({ "config": { "template_dirs": ["src", "doc"] } }));
+    template.set_src_str("<div>{:include; {:flg; require :} >> tests/include.txt :}</div>");
+    let result = template.render();
+    assert!(!template.has_error());
+    assert_eq!(result, "<div>Lorem Ipsum <div></div></div>");
+}
+
+#[test]
+fn test_template_dirs_allowed_roots() {
+    let mut template = new_template(json!(["theme", "base"]));
+    template.merge_schema_value(json!({ "config": { "allowed_roots": ["base"] } }));
+    template.set_src_str("<div>{:include; header.ntpl :}</div>");
+    let result = template.render();
+    assert!(template.has_error());
+    assert_eq!(result, "<div></div>");
+}